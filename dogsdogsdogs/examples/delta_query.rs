@@ -9,7 +9,7 @@ use timely::dataflow::operators::probe::Handle;
 use differential_dataflow::input::Input;
 use graph_map::GraphMMap;
 
-use dogsdogsdogs::altneu::AltNeu;
+use differential_dataflow::lattice::AltNeu;
 
 fn main() {
 
@@ -9,7 +9,8 @@ use timely::dataflow::operators::probe::Handle;
 use differential_dataflow::input::Input;
 use graph_map::GraphMMap;
 
-use dogsdogsdogs::{CollectionIndex, altneu::AltNeu};
+use dogsdogsdogs::CollectionIndex;
+use differential_dataflow::lattice::AltNeu;
 use dogsdogsdogs::{ProposeExtensionMethod};
 
 fn main() {
@@ -1,11 +1,7 @@
-#[macro_use]
-extern crate abomonation_derive;
 extern crate abomonation;
 extern crate timely;
 extern crate timely_sort;
 extern crate differential_dataflow;
-#[macro_use]
-extern crate serde_derive;
 extern crate serde;
 
 use std::hash::Hash;
@@ -23,9 +19,13 @@ use differential_dataflow::lattice::Lattice;
 use differential_dataflow::operators::arrange::TraceAgent;
 use differential_dataflow::operators::arrange::{ArrangeBySelf, ArrangeByKey};
 
-pub mod altneu;
 pub mod operators;
 
+/// The `AltNeu` timestamp, which has moved to `differential_dataflow::lattice`.
+pub mod altneu {
+    pub use differential_dataflow::lattice::AltNeu;
+}
+
 /// A type capable of extending a stream of prefixes.
 ///
 /**
@@ -1,7 +1,3 @@
-#[macro_use]
-extern crate abomonation_derive;
-extern crate abomonation;
-
 extern crate rand;
 extern crate timely;
 extern crate differential_dataflow;
@@ -15,7 +11,7 @@ use timely::dataflow::operators::unordered_input::UnorderedInput;
 use differential_dataflow::AsCollection;
 use differential_dataflow::operators::*;
 
-use pair::Pair;
+use differential_dataflow::lattice::Pair;
 
 fn main() {
 
@@ -163,176 +159,3 @@ fn main() {
 
     }).unwrap();
 }
-
-/// This module contains a definition of a new timestamp time, a "pair" or product.
-///
-/// This is a minimal self-contained implementation, in that it doesn't borrow anything
-/// from the rest of the library other than the traits it needs to implement. With this
-/// type and its implementations, you can use it as a timestamp type.
-mod pair {
-
-    /// A pair of timestamps, partially ordered by the product order.
-    #[derive(Hash, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Abomonation)]
-    pub struct Pair<S, T> {
-        pub first: S,
-        pub second: T,
-    }
-
-    impl<S, T> Pair<S, T> {
-        /// Create a new pair.
-        pub fn new(first: S, second: T) -> Self {
-            Pair { first, second }
-        }
-    }
-
-    // Implement timely dataflow's `PartialOrder` trait.
-    use timely::order::PartialOrder;
-    impl<S: PartialOrder, T: PartialOrder> PartialOrder for Pair<S, T> {
-        fn less_equal(&self, other: &Self) -> bool {
-            self.first.less_equal(&other.first) && self.second.less_equal(&other.second)
-        }
-    }
-
-    use timely::progress::timestamp::Refines;
-    impl<S: Timestamp, T: Timestamp> Refines<()> for Pair<S, T> {
-        fn to_inner(_outer: ()) -> Self { Default::default() }
-        fn to_outer(self) -> () { () }
-        fn summarize(_summary: <Self>::Summary) -> () { () }
-    }
-
-    // Implement timely dataflow's `PathSummary` trait.
-    // This is preparation for the `Timestamp` implementation below.
-    use timely::progress::PathSummary;
-
-    impl<S: Timestamp, T: Timestamp> PathSummary<Pair<S,T>> for () {
-        fn results_in(&self, timestamp: &Pair<S, T>) -> Option<Pair<S,T>> {
-            Some(timestamp.clone())
-        }
-        fn followed_by(&self, other: &Self) -> Option<Self> {
-            Some(other.clone())
-        }
-    }
-
-    // Implement timely dataflow's `Timestamp` trait.
-    use timely::progress::Timestamp;
-    impl<S: Timestamp, T: Timestamp> Timestamp for Pair<S, T> {
-        type Summary = ();
-    }
-
-    // Implement differential dataflow's `Lattice` trait.
-    // This extends the `PartialOrder` implementation with additional structure.
-    use differential_dataflow::lattice::Lattice;
-    impl<S: Lattice, T: Lattice> Lattice for Pair<S, T> {
-        fn minimum() -> Self { Pair { first: S::minimum(), second: T::minimum() }}
-        fn join(&self, other: &Self) -> Self {
-            Pair {
-                first: self.first.join(&other.first),
-                second: self.second.join(&other.second),
-            }
-        }
-        fn meet(&self, other: &Self) -> Self {
-            Pair {
-                first: self.first.meet(&other.first),
-                second: self.second.meet(&other.second),
-            }
-        }
-    }
-
-    use std::fmt::{Formatter, Error, Debug};
-
-    /// Debug implementation to avoid seeing fully qualified path names.
-    impl<TOuter: Debug, TInner: Debug> Debug for Pair<TOuter, TInner> {
-        fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-            f.write_str(&format!("({:?}, {:?})", self.first, self.second))
-        }
-    }
-
-}
-
-/// This module contains a definition of a new timestamp time, a "pair" or product.
-///
-/// This is a minimal self-contained implementation, in that it doesn't borrow anything
-/// from the rest of the library other than the traits it needs to implement. With this
-/// type and its implementations, you can use it as a timestamp type.
-mod vector {
-
-    /// A pair of timestamps, partially ordered by the product order.
-    #[derive(Hash, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Abomonation, Debug)]
-    pub struct Vector<T> {
-        pub vector: Vec<T>,
-    }
-
-    impl<T> Vector<T> {
-        /// Create a new pair.
-        pub fn new(vector: Vec<T>) -> Self {
-            Vector { vector }
-        }
-    }
-
-    // Implement timely dataflow's `PartialOrder` trait.
-    use timely::order::PartialOrder;
-    impl<T: PartialOrder+Default> PartialOrder for Vector<T> {
-        fn less_equal(&self, other: &Self) -> bool {
-            self.vector
-                .iter()
-                .enumerate()
-                .all(|(index, time)| time.less_equal(other.vector.get(index).unwrap_or(&Default::default())))
-        }
-    }
-
-    use timely::progress::timestamp::Refines;
-    impl<T: Timestamp> Refines<()> for Vector<T> {
-        fn to_inner(_outer: ()) -> Self { Default::default() }
-        fn to_outer(self) -> () { () }
-        fn summarize(_summary: <Self>::Summary) -> () { () }
-    }
-
-    // Implement timely dataflow's `PathSummary` trait.
-    // This is preparation for the `Timestamp` implementation below.
-    use timely::progress::PathSummary;
-
-    impl<T: Timestamp> PathSummary<Vector<T>> for () {
-        fn results_in(&self, timestamp: &Vector<T>) -> Option<Vector<T>> {
-            Some(timestamp.clone())
-        }
-        fn followed_by(&self, other: &Self) -> Option<Self> {
-            Some(other.clone())
-        }
-    }
-
-    // Implement timely dataflow's `Timestamp` trait.
-    use timely::progress::Timestamp;
-    impl<T: Timestamp> Timestamp for Vector<T> {
-        type Summary = ();
-    }
-
-    // Implement differential dataflow's `Lattice` trait.
-    // This extends the `PartialOrder` implementation with additional structure.
-    use differential_dataflow::lattice::Lattice;
-    impl<T: Lattice+Default+Clone> Lattice for Vector<T> {
-        fn minimum() -> Self { Self { vector: Vec::new() } }
-        fn join(&self, other: &Self) -> Self {
-            let min_len = ::std::cmp::min(self.vector.len(), other.vector.len());
-            let max_len = ::std::cmp::max(self.vector.len(), other.vector.len());
-            let mut vector = Vec::with_capacity(max_len);
-            for index in 0 .. min_len {
-                vector.push(self.vector[index].join(&other.vector[index]));
-            }
-            for time in &self.vector[min_len..] {
-                vector.push(time.clone());
-            }
-            for time in &other.vector[min_len..] {
-                vector.push(time.clone());
-            }
-            Self { vector }
-        }
-        fn meet(&self, other: &Self) -> Self {
-            let min_len = ::std::cmp::min(self.vector.len(), other.vector.len());
-            let mut vector = Vec::with_capacity(min_len);
-            for index in 0 .. min_len {
-                vector.push(self.vector[index].meet(&other.vector[index]));
-            }
-            Self { vector }
-        }
-    }
-}
\ No newline at end of file
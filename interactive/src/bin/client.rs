@@ -1,8 +1,8 @@
 extern crate interactive;
 
 use std::time::Duration;
-use interactive::{Command, Plan};
-use interactive::concrete::{Session, Value};
+use interactive::{Command, Plan, Schema};
+use interactive::concrete::{Session, Value, Type};
 
 fn main() {
 
@@ -10,7 +10,7 @@ fn main() {
     let mut session = Session::new(socket);
 
     // Create initially empty set of edges.
-    session.issue(Command::CreateInput("Edges".to_string(), Vec::new()));
+    session.issue(Command::CreateInput("Edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), Vec::new()));
 
     for node in 0 .. 1000 {
         let edge = vec![Value::Usize(node), Value::Usize(node+1)];
@@ -18,7 +18,7 @@ fn main() {
     }
 
     // Create initially empty set of edges.
-    session.issue(Command::CreateInput("Nodes".to_string(), Vec::new()));
+    session.issue(Command::CreateInput("Nodes".to_string(), Schema::new(vec![("node", Type::Usize)]), Vec::new()));
 
     session.issue(
         Plan::source("Nodes")
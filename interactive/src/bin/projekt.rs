@@ -1,17 +1,17 @@
 extern crate interactive;
 
 use std::time::Duration;
-use interactive::{Command, Plan};
-use interactive::concrete::{Session, Value};
+use interactive::{Command, Plan, Schema};
+use interactive::concrete::{Session, Value, Type};
 
 fn main() {
 
     let socket = std::net::TcpStream::connect("127.0.0.1:8000".to_string()).expect("failed to connect");
     let mut session = Session::new(socket);
 
-    session.issue(Command::CreateInput("XYZ".to_string(), Vec::new()));
-    session.issue(Command::CreateInput("XYGoal".to_string(), Vec::new()));
-    session.issue(Command::CreateInput("XZGoal".to_string(), Vec::new()));
+    session.issue(Command::CreateInput("XYZ".to_string(), Schema::new(vec![("x", Type::Usize), ("y", Type::Usize), ("z", Type::Usize)]), Vec::new()));
+    session.issue(Command::CreateInput("XYGoal".to_string(), Schema::new(vec![("x", Type::Usize), ("y", Type::Usize)]), Vec::new()));
+    session.issue(Command::CreateInput("XZGoal".to_string(), Schema::new(vec![("x", Type::Usize), ("z", Type::Usize)]), Vec::new()));
 
     // Determine errors in the xy plane.
     session.issue(
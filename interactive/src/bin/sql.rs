@@ -0,0 +1,93 @@
+extern crate interactive;
+
+use std::io::BufRead;
+use std::time::Duration;
+use interactive::{Command, Response};
+use interactive::concrete::{Session, Value};
+
+/// A read-eval-print loop issuing SQL statements to a server.
+///
+/// Statements may span multiple lines, and are terminated by a semicolon. Lines starting
+/// with a backslash are client commands:
+///
+///   \advance <seconds>    advances all inputs and traces to the time
+///   \subscribe <name>     prints changes to a published rule as they happen
+///   \peek <name> <secs>   prints the contents of a published rule at a time
+///   \shutdown             terminates the server
+///   \quit                 exits the client
+fn main() {
+
+    let address = std::env::args().nth(1).unwrap_or("127.0.0.1:8000".to_string());
+    let socket = std::net::TcpStream::connect(address).expect("failed to connect");
+    let mut responses = socket.try_clone().expect("failed to clone socket");
+    let mut session = Session::new(socket);
+
+    // Print responses as they arrive.
+    std::thread::spawn(move || {
+        while let Ok(response) = Response::<Value>::deserialize_from(&mut responses) {
+            match response {
+                Response::Updates(name, updates) => {
+                    for (tuple, time, diff) in updates {
+                        println!("{}\t{:?}\t{:?}\t{}", name, tuple, time, diff);
+                    }
+                },
+                Response::Snapshot(name, time, tuples) => {
+                    println!("{} at {:?}:", name, time);
+                    for (tuple, diff) in tuples {
+                        println!("\t{:?}\t{}", tuple, diff);
+                    }
+                },
+                Response::Ack => { },
+                Response::Error(error) => {
+                    println!("error: {}", error);
+                },
+            }
+        }
+    });
+
+    let stdin = std::io::stdin();
+    let mut statement = String::new();
+    for line in stdin.lock().lines() {
+
+        let line = line.expect("failed to read line");
+        let trimmed = line.trim();
+
+        if statement.is_empty() && trimmed.starts_with('\\') {
+            let mut words = trimmed[1..].split_whitespace();
+            match words.next() {
+                Some("advance") => {
+                    match words.next().and_then(|seconds| seconds.parse().ok()) {
+                        Some(seconds) => session.issue(Command::<Value>::AdvanceTime(Duration::from_secs(seconds))),
+                        None => println!("usage: \\advance <seconds>"),
+                    }
+                },
+                Some("subscribe") => {
+                    match words.next() {
+                        Some(name) => session.issue(Command::<Value>::Subscribe(name.to_string())),
+                        None => println!("usage: \\subscribe <name>"),
+                    }
+                },
+                Some("peek") => {
+                    match (words.next(), words.next().and_then(|seconds| seconds.parse().ok())) {
+                        (Some(name), Some(seconds)) => session.issue(Command::<Value>::Peek(name.to_string(), Duration::from_secs(seconds))),
+                        _ => println!("usage: \\peek <name> <seconds>"),
+                    }
+                },
+                Some("shutdown") => {
+                    session.issue(Command::<Value>::Shutdown);
+                    break;
+                },
+                Some("quit") => break,
+                _ => println!("unknown command: {}", trimmed),
+            }
+        }
+        else {
+            statement.push_str(&line);
+            statement.push('\n');
+            if trimmed.ends_with(';') {
+                session.issue(Command::<Value>::Sql(statement.trim().to_string()));
+                statement.clear();
+            }
+        }
+    }
+}
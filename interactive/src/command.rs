@@ -1,7 +1,7 @@
 //! Commands accepted by the system.
 
 use std::hash::Hash;
-use std::io::Write;
+use std::io::{Read, Write};
 
 use timely::communication::Allocate;
 use timely::worker::Worker;
@@ -11,24 +11,54 @@ use differential_dataflow::logging::DifferentialEvent;
 
 use differential_dataflow::ExchangeData;
 
-use super::{Query, Rule, Plan, Time, Diff, Manager, Datum};
+use super::{Query, Rule, Plan, Time, Diff, Manager, Datum, Schema};
+use crate::manager::Statistics;
+use crate::response::{Response, Clients};
+use crate::wal::CommandLog;
 use crate::logging::LoggingValue;
+use crate::sql::SqlValue;
 
 /// Commands accepted by the system.
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Command<V: Datum> {
     /// Installs the query and publishes public rules.
+    ///
+    /// The query is only installed if it type checks against the schemas of the relations it
+    /// uses; otherwise an error is returned to the client.
     Query(Query<V>),
+    /// Drops the installed query with a rule of the given name, and unpublishes its rules.
+    ///
+    /// The query's dataflow is shut down and its arrangements released once no other
+    /// installed query uses them.
+    DropQuery(String),
     /// Advances all inputs and traces to `time`, and advances computation.
     AdvanceTime(Time),
-    /// Creates a new named input, with initial input.
-    CreateInput(String, Vec<Vec<V>>),
+    /// Creates a new named input, with the schema of its tuples, and initial input.
+    CreateInput(String, Schema<V>, Vec<Vec<V>>),
     /// Introduces updates to a specified input.
+    ///
+    /// The updates are only introduced if all tuples match the schema of the input.
     UpdateInput(String, Vec<(Vec<V>, Time, Diff)>),
     /// Closes a specified input.
     CloseInput(String),
     /// Attaches a logging source. (address, flavor, number, granularity, name_as)
     SourceLogging(String, String, usize, u64, String),
+    /// Compiles a SQL statement into commands, and executes them.
+    Sql(String),
+    /// Streams consolidated changes to a published rule back to the issuing client.
+    ///
+    /// The subscription is shut down once the client disconnects, or its responses fail to send.
+    /// It is also shut down when the rule is dropped, and does not keep the rule's query from
+    /// being released.
+    Subscribe(String),
+    /// Returns the contents of a published rule at a time to the issuing client.
+    ///
+    /// If the rule is dropped before the time is complete, the client receives an error instead.
+    Peek(String, Time),
+    /// Shuts down the subscriptions of the issuing client, which has disconnected.
+    ///
+    /// The server issues this command on behalf of each client whose connection closes.
+    Disconnect,
     /// Terminates the system.
     Shutdown,
 }
@@ -43,11 +73,37 @@ impl<V: Datum> From<Rule<V>> for Command<V> {
 
 impl<V: Datum> Command<V>
 where
-    V: ExchangeData+Hash+LoggingValue,
+    V: ExchangeData+Hash+LoggingValue+SqlValue,
 {
 
-    /// Executes a command.
-    pub fn execute<A: Allocate>(self, manager: &mut Manager<V>, worker: &mut Worker<A>) {
+    /// Executes a command issued by `client`, replying with an acknowledgement or an error.
+    ///
+    /// Responses to the command are sent to `client` by its responding worker, which is in the
+    /// process with the connection to the client (see `Clients::responder`). Errors for clients
+    /// without connections are printed instead. If the manager has a log, a successfully executed
+    /// command is appended to it before it is acknowledged.
+    pub fn execute<A: Allocate>(self, client: usize, manager: &mut Manager<V>, worker: &mut Worker<A>) {
+        let logged = if manager.log.is_some() && CommandLog::records(&self) { Some(self.clone()) } else { None };
+        let result = self.apply(client, manager, worker);
+        if let (Ok(()), Some(command), Some(log)) = (&result, logged, manager.log.as_mut()) {
+            log.append(&command).expect("failed to append to command log");
+        }
+        if worker.index() == Clients::responder(client, worker.peers()) {
+            match result {
+                Ok(()) => { manager.clients.send(client, &Response::<V>::Ack); },
+                Err(error) => {
+                    if !manager.clients.send(client, &Response::<V>::Error(error.clone())) {
+                        println!("{}", error);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Executes a command issued by `client`, returning an error if it could not be executed.
+    fn apply<A: Allocate>(self, client: usize, manager: &mut Manager<V>, worker: &mut Worker<A>) -> Result<(), String> {
+
+        let responder = Clients::responder(client, worker.peers());
 
         match self {
 
@@ -66,6 +122,26 @@ where
                 // traces, and the types present in imported traces are not
                 // the same as those in arrangements.
 
+                // The dataflows of retained queries use their rules under the same names.
+                if let Some(rule) = query.rules.iter().find(|rule| manager.traces.is_retained(&rule.name)) {
+                    return Err(format!("Query error: {:?} was dropped, but is still used by other queries", rule.name));
+                }
+
+                let schemas =
+                query
+                    .schemas(&|name: &str| manager.schema(name))
+                    .map_err(|error| format!("Query error: {}", error))?;
+
+                let names = query.rules.iter().map(|rule| rule.name.clone()).collect::<Vec<_>>();
+                for (name, schema) in names.iter().zip(schemas.into_iter()) {
+                    match schema {
+                        Some(schema) => { manager.schemas.insert(name.clone(), schema); },
+                        None => { manager.schemas.remove(name); },
+                    }
+                }
+
+                manager.traces.begin_query();
+
                 worker.dataflow(|scope| {
 
                     use timely::dataflow::operators::Probe;
@@ -75,52 +151,112 @@ where
                     let mut collections = std::collections::HashMap::new();
                     // let mut arrangements = std::collections::HashMap::new();
 
-                    for Rule { name, plan } in query.rules.into_iter() {
-                        let collection =
-                        plan.render(scope, &mut collections, &mut manager.traces)
-                            .arrange_by_self();
+                    if query.is_recursive() {
+
+                        // Recursive rules are rendered together, and published once complete.
+                        let results = plan::fixpoint::render_fixpoint(&query.rules[..], scope, &mut collections, &mut manager.traces);
 
-                        collection.stream.probe_with(&mut manager.probe);
-                        let trace = collection.trace;
+                        for (Rule { name, plan }, collection) in query.rules.into_iter().zip(results.into_iter()) {
+                            let collection = collection.arrange_by_self();
 
-                        // Can bind the trace to both the plan and the name.
-                        manager.traces.set_unkeyed(&plan, &trace);
-                        manager.traces.set_unkeyed(&Plan::Source(name), &trace);
+                            collection.stream.probe_with(&mut manager.probe);
+                            let trace = collection.trace;
+
+                            manager.traces.set_unkeyed(&plan, &trace);
+                            manager.traces.set_unkeyed(&Plan::Source(name), &trace);
+                        }
+                    }
+                    else {
+
+                        for Rule { name, plan } in query.rules.into_iter() {
+                            let collection =
+                            plan.render(scope, &mut collections, &mut manager.traces)
+                                .arrange_by_self();
+
+                            collection.stream.probe_with(&mut manager.probe);
+                            let trace = collection.trace;
+
+                            // Can bind the trace to both the plan and the name.
+                            manager.traces.set_unkeyed(&plan, &trace);
+                            manager.traces.set_unkeyed(&Plan::Source(name), &trace);
+                        }
                     }
 
                 });
+
+                manager.traces.end_query(names);
+            },
+
+            Command::DropQuery(name) => {
+                if manager.traces.drop_query(&name) {
+                    let traces = &manager.traces;
+                    manager.schemas.retain(|name, _| traces.is_published(name));
+                    manager.catalog.retain(|name, _| traces.is_published(name));
+                    manager.unsubscribe_unpublished();
+                }
+                else {
+                    return Err(format!("Query not found: {:?}", name));
+                }
             },
 
             Command::AdvanceTime(time) => {
+                // Inputs cannot go back in time, as neither can their sessions.
+                if time < manager.inputs.time {
+                    return Err(format!("Cannot advance time to {:?}, before {:?}", time, manager.inputs.time));
+                }
                 manager.advance_time(&time);
                 while manager.probe.less_than(&time) {
                     worker.step();
                 }
             },
 
-            Command::CreateInput(name, updates) => {
+            Command::CreateInput(name, schema, updates) => {
 
                 use differential_dataflow::input::Input;
                 use differential_dataflow::operators::arrange::ArrangeBySelf;
 
+                updates
+                    .iter()
+                    .map(|tuple| schema.check(tuple))
+                    .collect::<Result<(), _>>()
+                    .map_err(|error| format!("Input error for {}: {}", name, error))?;
+
+                // Sizes of inputs are known to all workers, which use them to plan queries.
+                let statistics = Statistics { records: updates.len() as Diff };
+
                 let (input, trace) = worker.dataflow(|scope| {
                     let (input, collection) = scope.new_collection_from(updates.into_iter());
                     let trace = collection.arrange_by_self().trace;
                     (input, trace)
                 });
 
-                manager.insert_input(name, input, trace);
+                manager.traces.set_statistics(&Plan::Source(name.clone()), statistics);
+                manager.insert_input(name, schema, input, trace);
 
             },
 
             Command::UpdateInput(name, updates) => {
+                if let Some(schema) = manager.schemas.get(&name) {
+                    updates
+                        .iter()
+                        .map(|(tuple, _, _)| schema.check(tuple))
+                        .collect::<Result<(), _>>()
+                        .map_err(|error| format!("Input error for {}: {}", name, error))?;
+                }
+                let advanced = manager.inputs.time.clone();
                 if let Some(input) = manager.inputs.sessions.get_mut(&name) {
+                    // Updates at times already passed would panic the session; reject them all before applying any.
+                    if let Some((_, time, _)) = updates.iter().find(|(_, time, _)| time < input.time() || time < &advanced) {
+                        return Err(format!("Input error for {}: update at {:?}, before {:?}", name, time, ::std::cmp::max(input.time(), &advanced)));
+                    }
+                    let records = updates.iter().map(|(_, _, diff)| diff).sum();
                     for (data, time, diff) in updates.into_iter() {
                         input.update_at(data, time, diff);
                     }
+                    manager.traces.count_records(&Plan::Source(name), records);
                 }
                 else {
-                    println!("Input not found: {:?}", name);
+                    return Err(format!("Input not found: {:?}", name));
                 }
             },
 
@@ -178,20 +314,180 @@ where
                         }
                         crate::logging::publish_differential_logging(manager, worker, granularity, &name_as, streams);
                     },
-                    _ => { println!("{}", format!("Unknown logging flavor: {}", flavor)); }
+                    _ => { return Err(format!("Unknown logging flavor: {}", flavor)); }
                 }
 
             }
 
+            Command::Sql(statement) => {
+                // Each worker compiles the statement, maintaining the same catalog. The catalog
+                // changes only once the compiled commands are applied, as failed statements are
+                // not logged, and must not affect the catalog of a replaying server.
+                let mut catalog = manager.catalog.clone();
+                let commands =
+                V::compile(&statement, &mut catalog)
+                    .map_err(|error| format!("SQL error: {}", error))?;
+
+                for command in commands.into_iter() {
+                    command.apply(client, manager, worker)?;
+                }
+                manager.catalog = catalog;
+            },
+
+            Command::Subscribe(name) => {
+                if let Some(mut trace) = manager.traces.get_unkeyed(&Plan::Source(name.clone())) {
+
+                    use timely::dataflow::channels::pact::Exchange;
+                    use timely::dataflow::operators::Operator;
+                    use differential_dataflow::consolidation::consolidate_updates;
+
+                    let clients = manager.clients.clone();
+                    let subscription = worker.dataflow(|scope| {
+
+                        let mut pending = Vec::new();
+                        let mut buffer = Vec::new();
+
+                        let (arranged, button) = trace.import_core(scope, "Subscribe");
+                        let subscription = std::rc::Rc::new(std::cell::RefCell::new(Some(button)));
+                        let sink_subscription = subscription.clone();
+                        let sink_name = name.clone();
+
+                        // Updates are sent from the responding worker, once their times are complete.
+                        arranged
+                            .as_collection(|tuple, &()| tuple.clone())
+                            .inner
+                            .sink(Exchange::new(move |_: &(Vec<V>, Time, Diff)| responder as u64), "Subscribe", move |input| {
+
+                                input.for_each(|_time, data| {
+                                    data.swap(&mut buffer);
+                                    pending.extend(buffer.drain(..));
+                                });
+
+                                let frontier = input.frontier();
+                                let mut ready = Vec::new();
+                                let mut index = 0;
+                                while index < pending.len() {
+                                    if !frontier.less_equal(&pending[index].1) {
+                                        ready.push(pending.swap_remove(index));
+                                    }
+                                    else {
+                                        index += 1;
+                                    }
+                                }
+
+                                // Once shut down, updates at times through which the rule is not complete may be ready.
+                                consolidate_updates(&mut ready);
+                                if !ready.is_empty() && sink_subscription.borrow().is_some() {
+                                    ready.sort_by(|x, y| (&x.1, &x.0).cmp(&(&y.1, &y.0)));
+                                    // Stop importing the trace once the client is gone.
+                                    if !clients.send(client, &Response::Updates(sink_name.clone(), ready)) {
+                                        if let Some(mut button) = sink_subscription.borrow_mut().take() {
+                                            button.press();
+                                        }
+                                    }
+                                }
+                            });
+
+                        subscription
+                    });
+
+                    manager.subscribe(client, name, subscription);
+                }
+                else {
+                    return Err(format!("Rule not found: {:?}", name));
+                }
+            },
+
+            Command::Peek(name, time) => {
+                if let Some(mut trace) = manager.traces.get_unkeyed(&Plan::Source(name.clone())) {
+
+                    use timely::dataflow::channels::pact::Exchange;
+                    use timely::dataflow::operators::Operator;
+                    use timely::order::PartialOrder;
+                    use differential_dataflow::consolidation::consolidate;
+                    use differential_dataflow::trace::TraceReader;
+
+                    // Updates at times the trace has compacted may no longer be distinguished from later updates.
+                    if !trace.advance_frontier().iter().any(|since| since.less_equal(&time)) {
+                        return Err(format!("Peek time {:?} for {:?} is no longer available", time, name));
+                    }
+
+                    let clients = manager.clients.clone();
+                    let subscription = worker.dataflow(|scope| {
+
+                        let index = scope.index();
+                        let (arranged, button) = trace.import_core(scope, "Peek");
+                        let subscription = std::rc::Rc::new(std::cell::RefCell::new(Some(button)));
+                        let sink_subscription = subscription.clone();
+                        let sink_name = name.clone();
+
+                        let mut snapshot = Vec::new();
+                        let mut buffer = Vec::new();
+                        let mut complete = false;
+
+                        // The snapshot is sent from the responding worker, after which the dataflow shuts down.
+                        // If the manager shut the dataflow down first, the snapshot may be incomplete.
+                        arranged
+                            .as_collection(|tuple, &()| tuple.clone())
+                            .inner
+                            .sink(Exchange::new(move |_: &(Vec<V>, Time, Diff)| responder as u64), "Peek", move |input| {
+
+                                input.for_each(|_time, data| {
+                                    data.swap(&mut buffer);
+                                    snapshot.extend(buffer.drain(..).filter(|x| x.1.less_equal(&time)).map(|(data, _time, diff)| (data, diff)));
+                                });
+
+                                if !complete && !input.frontier().less_equal(&time) {
+                                    let button = sink_subscription.borrow_mut().take();
+                                    if index == responder {
+                                        let response =
+                                        if button.is_some() {
+                                            consolidate(&mut snapshot);
+                                            Response::Snapshot(sink_name.clone(), time, std::mem::replace(&mut snapshot, Vec::new()))
+                                        }
+                                        else {
+                                            Response::Error(format!("Peek of {:?} at {:?} interrupted, as the rule was dropped", sink_name, time))
+                                        };
+                                        clients.send(client, &response);
+                                    }
+                                    if let Some(mut button) = button {
+                                        button.press();
+                                    }
+                                    complete = true;
+                                }
+                            });
+
+                        subscription
+                    });
+
+                    manager.subscribe(client, name, subscription);
+                }
+                else {
+                    return Err(format!("Rule not found: {:?}", name));
+                }
+            },
+
+            Command::Disconnect => {
+                manager.disconnect(client);
+            },
+
             Command::Shutdown => {
                 println!("Shutdown received");
                 manager.shutdown(worker);
             }
         }
+
+        Ok(())
     }
 
     /// Serialize the command at a writer.
     pub fn serialize_into<W: Write>(&self, writer: W) {
         bincode::serialize_into(writer, self).expect("bincode: serialization failed");
     }
-}
\ No newline at end of file
+
+    /// Deserialize a command from a reader.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, bincode::Error> {
+        bincode::deserialize_from(reader)
+    }
+}
+
@@ -1,7 +1,7 @@
 //! An example value type.
 
 use std::time::Duration;
-use super::{Datum, VectorFrom, Command};
+use super::{Datum, VectorFrom, Command, Diff};
 
 /// A session.
 pub struct Session<W: std::io::Write> {
@@ -32,12 +32,344 @@ pub enum Value {
     Vector(Vec<Value>),
     /// duration
     Duration(Duration),
+    /// the error produced by evaluating an ill-typed expression
+    Error(String),
+}
+
+impl Value {
+    /// The name of the type of the value, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "bool",
+            Value::Usize(_) => "usize",
+            Value::String(_) => "string",
+            Value::Vector(_) => "vector",
+            Value::Duration(_) => "duration",
+            Value::Error(_) => "error",
+        }
+    }
+    /// The type of the value, or none for errors.
+    pub fn typ(&self) -> Option<Type> {
+        match self {
+            Value::Bool(_) => Some(Type::Bool),
+            Value::Usize(_) => Some(Type::Usize),
+            Value::String(_) => Some(Type::String),
+            Value::Vector(_) => Some(Type::Vector),
+            Value::Duration(_) => Some(Type::Duration),
+            Value::Error(_) => None,
+        }
+    }
+}
+
+/// Types of values, and that values may be cast to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Type {
+    /// boolean
+    Bool,
+    /// integer
+    Usize,
+    /// string
+    String,
+    /// duration, cast to and from integers as nanoseconds
+    Duration,
+    /// vector of values of any types, which values cannot be cast to
+    Vector,
+}
+
+/// Operators with one argument.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum UnaryOp {
+    /// boolean negation
+    Not,
+    /// the number of characters in a string, or elements in a vector
+    Length,
+    /// a string in upper case
+    Upper,
+    /// a string in lower case
+    Lower,
+}
+
+/// Operators with two arguments.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum BinaryOp {
+    /// integer or duration addition
+    Add,
+    /// integer or duration subtraction
+    Sub,
+    /// integer multiplication
+    Mul,
+    /// integer division
+    Div,
+    /// integer remainder
+    Mod,
+    /// string concatenation
+    Concat,
+    /// equality
+    Eq,
+    /// inequality
+    Ne,
+    /// strictly less than, between values of the same type
+    Lt,
+    /// less than or equal, between values of the same type
+    Le,
+    /// strictly greater than, between values of the same type
+    Gt,
+    /// greater than or equal, between values of the same type
+    Ge,
+    /// boolean conjunction
+    And,
+    /// boolean disjunction
+    Or,
+}
+
+/// Scalar expressions over tuples of values.
+///
+/// Evaluating an expression does not panic: type errors, arithmetic overflow, and division
+/// by zero produce a `Value::Error`, which propagates through enclosing expressions. Errors
+/// in mapped expressions appear as `Value::Error` values in the results reported to clients,
+/// whereas filters drop the tuples for which their predicate produces an error.
+///
+/// Conjunctions and disjunctions evaluate their second argument only when the first does not
+/// determine the result, so that it may guard against errors in the second argument.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Expression {
+    /// The value at an index of the tuple.
+    Column(usize),
+    /// A constant value.
+    Literal(Value),
+    /// An operator applied to one argument.
+    Unary(UnaryOp, Box<Expression>),
+    /// An operator applied to two arguments.
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    /// The conversion of a value to a type.
+    Cast(Box<Expression>, Type),
+    /// The result of the first branch whose condition is true, or the final result otherwise.
+    Case(Vec<(Expression, Expression)>, Box<Expression>),
+}
+
+impl Expression {
+    /// Evaluates the expression against a tuple of values.
+    pub fn evaluate(&self, data: &[Value]) -> Value {
+        match self {
+            Expression::Column(index) => {
+                data.get(*index)
+                    .cloned()
+                    .unwrap_or_else(|| Value::Error(format!("column {} out of range for tuple of length {}", index, data.len())))
+            },
+            Expression::Literal(value) => value.clone(),
+            Expression::Unary(op, arg) => {
+                match (op, arg.evaluate(data)) {
+                    (_, Value::Error(error)) => Value::Error(error),
+                    (UnaryOp::Not, Value::Bool(x)) => Value::Bool(!x),
+                    (UnaryOp::Length, Value::String(x)) => Value::Usize(x.chars().count()),
+                    (UnaryOp::Length, Value::Vector(x)) => Value::Usize(x.len()),
+                    (UnaryOp::Upper, Value::String(x)) => Value::String(x.to_uppercase()),
+                    (UnaryOp::Lower, Value::String(x)) => Value::String(x.to_lowercase()),
+                    (op, x) => Value::Error(format!("cannot apply {:?} to {}", op, x.type_name())),
+                }
+            },
+            Expression::Binary(op, arg1, arg2) => {
+                let arg1 = arg1.evaluate(data);
+                match (op, &arg1) {
+                    (BinaryOp::And, Value::Bool(false)) => { return Value::Bool(false); },
+                    (BinaryOp::Or, Value::Bool(true)) => { return Value::Bool(true); },
+                    _ => { },
+                }
+                match (op, arg1, arg2.evaluate(data)) {
+                    (_, Value::Error(error), _) => Value::Error(error),
+                    (_, _, Value::Error(error)) => Value::Error(error),
+                    (BinaryOp::Add, Value::Usize(x), Value::Usize(y)) => checked(x.checked_add(y).map(Value::Usize), "overflow"),
+                    (BinaryOp::Sub, Value::Usize(x), Value::Usize(y)) => checked(x.checked_sub(y).map(Value::Usize), "underflow"),
+                    (BinaryOp::Mul, Value::Usize(x), Value::Usize(y)) => checked(x.checked_mul(y).map(Value::Usize), "overflow"),
+                    (BinaryOp::Div, Value::Usize(x), Value::Usize(y)) => checked(x.checked_div(y).map(Value::Usize), "division by zero"),
+                    (BinaryOp::Mod, Value::Usize(x), Value::Usize(y)) => checked(x.checked_rem(y).map(Value::Usize), "division by zero"),
+                    (BinaryOp::Add, Value::Duration(x), Value::Duration(y)) => checked(x.checked_add(y).map(Value::Duration), "overflow"),
+                    (BinaryOp::Sub, Value::Duration(x), Value::Duration(y)) => checked(x.checked_sub(y).map(Value::Duration), "underflow"),
+                    (BinaryOp::Concat, Value::String(x), Value::String(y)) => Value::String(x + &y),
+                    (BinaryOp::Eq, x, y) => Value::Bool(x == y),
+                    (BinaryOp::Ne, x, y) => Value::Bool(x != y),
+                    (BinaryOp::Lt, ref x, ref y) if x.type_name() == y.type_name() => Value::Bool(x < y),
+                    (BinaryOp::Le, ref x, ref y) if x.type_name() == y.type_name() => Value::Bool(x <= y),
+                    (BinaryOp::Gt, ref x, ref y) if x.type_name() == y.type_name() => Value::Bool(x > y),
+                    (BinaryOp::Ge, ref x, ref y) if x.type_name() == y.type_name() => Value::Bool(x >= y),
+                    (BinaryOp::And, Value::Bool(x), Value::Bool(y)) => Value::Bool(x && y),
+                    (BinaryOp::Or, Value::Bool(x), Value::Bool(y)) => Value::Bool(x || y),
+                    (op, x, y) => Value::Error(format!("cannot apply {:?} to {} and {}", op, x.type_name(), y.type_name())),
+                }
+            },
+            Expression::Cast(arg, typ) => {
+                match (arg.evaluate(data), typ) {
+                    (Value::Error(error), _) => Value::Error(error),
+                    (Value::Bool(x), Type::Bool) => Value::Bool(x),
+                    (Value::Usize(x), Type::Bool) => Value::Bool(x != 0),
+                    (Value::String(x), Type::Bool) => checked(x.parse().ok().map(Value::Bool), "cannot parse bool"),
+                    (Value::Bool(x), Type::Usize) => Value::Usize(if x { 1 } else { 0 }),
+                    (Value::Usize(x), Type::Usize) => Value::Usize(x),
+                    (Value::String(x), Type::Usize) => checked(x.parse().ok().map(Value::Usize), "cannot parse usize"),
+                    (Value::Duration(x), Type::Usize) => {
+                        let nanos = x.as_nanos();
+                        checked(if nanos <= usize::max_value() as u128 { Some(Value::Usize(nanos as usize)) } else { None }, "overflow")
+                    },
+                    (Value::Usize(x), Type::Duration) => Value::Duration(Duration::from_nanos(x as u64)),
+                    (Value::Duration(x), Type::Duration) => Value::Duration(x),
+                    (Value::String(x), Type::String) => Value::String(x),
+                    (Value::Bool(x), Type::String) => Value::String(x.to_string()),
+                    (Value::Usize(x), Type::String) => Value::String(x.to_string()),
+                    (x, typ) => Value::Error(format!("cannot cast {} to {:?}", x.type_name(), typ)),
+                }
+            },
+            Expression::Case(branches, otherwise) => {
+                for (condition, result) in branches.iter() {
+                    match condition.evaluate(data) {
+                        Value::Bool(true) => { return result.evaluate(data); },
+                        Value::Bool(false) => { },
+                        Value::Error(error) => { return Value::Error(error); },
+                        x => { return Value::Error(format!("case condition must be bool, found {}", x.type_name())); },
+                    }
+                }
+                otherwise.evaluate(data)
+            },
+        }
+    }
+}
+
+impl Expression {
+    /// Determines the type of the expression applied to tuples whose values have `types`.
+    ///
+    /// An error indicates that the expression would produce an error for any such tuple,
+    /// except that casts from strings may fail or succeed depending on the string.
+    pub fn type_of(&self, types: &[Type]) -> Result<Type, String> {
+        match self {
+            Expression::Column(index) => {
+                types.get(*index)
+                    .cloned()
+                    .ok_or_else(|| format!("column {} out of range for tuple of length {}", index, types.len()))
+            },
+            Expression::Literal(value) => {
+                match value {
+                    Value::Error(error) => Err(error.clone()),
+                    value => Ok(value.typ().expect("errors handled above")),
+                }
+            },
+            Expression::Unary(op, arg) => {
+                match (op, arg.type_of(types)?) {
+                    (UnaryOp::Not, Type::Bool) => Ok(Type::Bool),
+                    (UnaryOp::Length, Type::String) => Ok(Type::Usize),
+                    (UnaryOp::Length, Type::Vector) => Ok(Type::Usize),
+                    (UnaryOp::Upper, Type::String) => Ok(Type::String),
+                    (UnaryOp::Lower, Type::String) => Ok(Type::String),
+                    (op, typ) => Err(format!("cannot apply {:?} to {:?}", op, typ)),
+                }
+            },
+            Expression::Binary(op, arg1, arg2) => {
+                match (op, arg1.type_of(types)?, arg2.type_of(types)?) {
+                    (BinaryOp::Add, Type::Usize, Type::Usize) => Ok(Type::Usize),
+                    (BinaryOp::Sub, Type::Usize, Type::Usize) => Ok(Type::Usize),
+                    (BinaryOp::Mul, Type::Usize, Type::Usize) => Ok(Type::Usize),
+                    (BinaryOp::Div, Type::Usize, Type::Usize) => Ok(Type::Usize),
+                    (BinaryOp::Mod, Type::Usize, Type::Usize) => Ok(Type::Usize),
+                    (BinaryOp::Add, Type::Duration, Type::Duration) => Ok(Type::Duration),
+                    (BinaryOp::Sub, Type::Duration, Type::Duration) => Ok(Type::Duration),
+                    (BinaryOp::Concat, Type::String, Type::String) => Ok(Type::String),
+                    (BinaryOp::Eq, x, y) |
+                    (BinaryOp::Ne, x, y) |
+                    (BinaryOp::Lt, x, y) |
+                    (BinaryOp::Le, x, y) |
+                    (BinaryOp::Gt, x, y) |
+                    (BinaryOp::Ge, x, y) if x == y => Ok(Type::Bool),
+                    (BinaryOp::And, Type::Bool, Type::Bool) => Ok(Type::Bool),
+                    (BinaryOp::Or, Type::Bool, Type::Bool) => Ok(Type::Bool),
+                    (op, x, y) => Err(format!("cannot apply {:?} to {:?} and {:?}", op, x, y)),
+                }
+            },
+            Expression::Cast(arg, typ) => {
+                match (arg.type_of(types)?, typ) {
+                    (Type::Bool, Type::Bool) |
+                    (Type::Usize, Type::Bool) |
+                    (Type::String, Type::Bool) |
+                    (Type::Bool, Type::Usize) |
+                    (Type::Usize, Type::Usize) |
+                    (Type::String, Type::Usize) |
+                    (Type::Duration, Type::Usize) |
+                    (Type::Usize, Type::Duration) |
+                    (Type::Duration, Type::Duration) |
+                    (Type::String, Type::String) |
+                    (Type::Bool, Type::String) |
+                    (Type::Usize, Type::String) => Ok(*typ),
+                    (x, typ) => Err(format!("cannot cast {:?} to {:?}", x, typ)),
+                }
+            },
+            Expression::Case(branches, otherwise) => {
+                let typ = otherwise.type_of(types)?;
+                for (condition, result) in branches.iter() {
+                    match condition.type_of(types)? {
+                        Type::Bool => { },
+                        x => { return Err(format!("case condition must be Bool, found {:?}", x)); },
+                    }
+                    let result = result.type_of(types)?;
+                    if result != typ {
+                        return Err(format!("case results have different types: {:?} and {:?}", result, typ));
+                    }
+                }
+                Ok(typ)
+            },
+        }
+    }
+}
+
+/// Produces the value if present, and an error with `message` otherwise.
+fn checked(value: Option<Value>, message: &str) -> Value {
+    value.unwrap_or_else(|| Value::Error(message.to_string()))
 }
 
 impl Datum for Value {
-    type Expression = usize;
-    fn subject_to(data: &[Self], expr: &Self::Expression) -> Self { data[*expr].clone() }
-    fn projection(index: usize) -> Self::Expression { index }
+    type Expression = Expression;
+    type Type = Type;
+    fn subject_to(data: &[Self], expr: &Self::Expression) -> Self { expr.evaluate(data) }
+    fn satisfies(data: &[Self], expr: &Self::Expression) -> Option<bool> {
+        match expr.evaluate(data) {
+            Value::Bool(x) => Some(x),
+            _ => None,
+        }
+    }
+    fn projection(index: usize) -> Self::Expression { Expression::Column(index) }
+    fn has_type(&self, typ: &Self::Type) -> bool { self.typ() == Some(*typ) }
+    fn type_of(types: &[Self::Type], expr: &Self::Expression) -> Result<Self::Type, String> { expr.type_of(types) }
+    fn predicate_type() -> Self::Type { Type::Bool }
+    fn count_type() -> Self::Type { Type::Usize }
+    fn sum_type(typ: &Self::Type) -> Result<Self::Type, String> {
+        match typ {
+            Type::Usize | Type::Duration => Ok(*typ),
+            typ => Err(format!("cannot sum {:?}", typ)),
+        }
+    }
+    fn from_count(count: Diff) -> Self { Value::Usize(count as usize) }
+    fn sum(values: &[(&Self, Diff)]) -> Self {
+        // Accumulate in a wider type, whose overflow and negative totals are errors.
+        let duration = match values.first().map(|x| x.0) { Some(Value::Duration(_)) => true, _ => false };
+        let mut total: i128 = 0;
+        for (value, diff) in values.iter() {
+            let amount = match (value, duration) {
+                (Value::Duration(x), true) => x.as_nanos() as i128,
+                (Value::Usize(x), false) => *x as i128,
+                (value, true) => { return Value::Error(format!("cannot sum duration and {}", value.type_name())); },
+                (value, false) => { return Value::Error(format!("cannot sum {}", value.type_name())); },
+            };
+            match amount.checked_mul(*diff as i128).and_then(|product| total.checked_add(product)) {
+                Some(sum) => { total = sum; },
+                None => { return Value::Error("overflow".to_string()); },
+            }
+        }
+        if total < 0 {
+            Value::Error("negative sum".to_string())
+        }
+        else if duration {
+            checked(if total <= u64::max_value() as i128 { Some(Value::Duration(Duration::from_nanos(total as u64))) } else { None }, "overflow")
+        }
+        else {
+            checked(if total <= usize::max_value() as i128 { Some(Value::Usize(total as usize)) } else { None }, "overflow")
+        }
+    }
 }
 
 impl From<usize> for Value { fn from(x: usize) -> Self { Value::Usize(x) } }
@@ -125,4 +457,150 @@ impl VectorFrom<DifferentialEvent> for Value {
             _ => { vec![] },
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+    use super::{Value, Type, Expression, UnaryOp, BinaryOp};
+    use Datum;
+    use super::Expression::{Column, Literal};
+
+    fn number(x: usize) -> Expression { Literal(Value::Usize(x)) }
+    fn text(x: &str) -> Expression { Literal(Value::String(x.to_string())) }
+    fn truth(x: bool) -> Expression { Literal(Value::Bool(x)) }
+    fn unary(op: UnaryOp, x: Expression) -> Expression { Expression::Unary(op, Box::new(x)) }
+    fn binary(op: BinaryOp, x: Expression, y: Expression) -> Expression { Expression::Binary(op, Box::new(x), Box::new(y)) }
+    fn cast(x: Expression, typ: Type) -> Expression { Expression::Cast(Box::new(x), typ) }
+
+    fn is_error(value: Value) -> bool {
+        match value { Value::Error(_) => true, _ => false }
+    }
+
+    #[test]
+    fn sum() {
+        let (one, two) = (Value::Usize(1), Value::Usize(2));
+        assert_eq!(Value::sum(&[(&one, 3), (&two, 2)]), Value::Usize(7));
+        assert_eq!(Value::sum(&[(&one, 3), (&two, -2)]), Value::Error("negative sum".to_string()));
+
+        let max = Value::Usize(usize::max_value());
+        assert_eq!(Value::sum(&[(&max, 2)]), Value::Error("overflow".to_string()));
+        assert_eq!(Value::sum(&[(&max, isize::max_value()), (&max, isize::max_value()), (&max, isize::max_value())]), Value::Error("overflow".to_string()));
+
+        let second = Value::Duration(Duration::from_secs(1));
+        assert_eq!(Value::sum(&[(&second, 3)]), Value::Duration(Duration::from_secs(3)));
+        assert_eq!(Value::sum(&[(&second, -1)]), Value::Error("negative sum".to_string()));
+        assert_eq!(Value::sum(&[(&second, isize::max_value())]), Value::Error("overflow".to_string()));
+        assert_eq!(Value::sum(&[(&second, 1), (&one, 1)]), Value::Error("cannot sum duration and usize".to_string()));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let data = vec![Value::Usize(7), Value::Usize(2)];
+        assert_eq!(binary(BinaryOp::Add, Column(0), Column(1)).evaluate(&data), Value::Usize(9));
+        assert_eq!(binary(BinaryOp::Sub, Column(0), Column(1)).evaluate(&data), Value::Usize(5));
+        assert_eq!(binary(BinaryOp::Mul, Column(0), Column(1)).evaluate(&data), Value::Usize(14));
+        assert_eq!(binary(BinaryOp::Div, Column(0), Column(1)).evaluate(&data), Value::Usize(3));
+        assert_eq!(binary(BinaryOp::Mod, Column(0), Column(1)).evaluate(&data), Value::Usize(1));
+
+        let second = Value::Duration(Duration::from_secs(1));
+        assert_eq!(binary(BinaryOp::Add, Literal(second.clone()), Literal(second.clone())).evaluate(&[]), Value::Duration(Duration::from_secs(2)));
+
+        // Failures produce errors rather than panics.
+        assert_eq!(binary(BinaryOp::Div, Column(0), number(0)).evaluate(&data), Value::Error("division by zero".to_string()));
+        assert_eq!(binary(BinaryOp::Mod, Column(0), number(0)).evaluate(&data), Value::Error("division by zero".to_string()));
+        assert_eq!(binary(BinaryOp::Sub, Column(1), Column(0)).evaluate(&data), Value::Error("underflow".to_string()));
+        assert_eq!(binary(BinaryOp::Add, number(usize::max_value()), number(1)).evaluate(&data), Value::Error("overflow".to_string()));
+        assert!(is_error(binary(BinaryOp::Add, Column(0), text("x")).evaluate(&data)));
+        assert!(is_error(Column(2).evaluate(&data)));
+
+        // Errors propagate through enclosing expressions.
+        assert_eq!(binary(BinaryOp::Add, binary(BinaryOp::Div, Column(0), number(0)), number(1)).evaluate(&data), Value::Error("division by zero".to_string()));
+    }
+
+    #[test]
+    fn strings() {
+        let data = vec![Value::String("Dog".to_string())];
+        assert_eq!(unary(UnaryOp::Length, Column(0)).evaluate(&data), Value::Usize(3));
+        assert_eq!(unary(UnaryOp::Upper, Column(0)).evaluate(&data), Value::String("DOG".to_string()));
+        assert_eq!(unary(UnaryOp::Lower, Column(0)).evaluate(&data), Value::String("dog".to_string()));
+        assert_eq!(binary(BinaryOp::Concat, Column(0), text("s")).evaluate(&data), Value::String("Dogs".to_string()));
+        assert_eq!(binary(BinaryOp::Lt, Column(0), text("Emu")).evaluate(&data), Value::Bool(true));
+        assert_eq!(unary(UnaryOp::Length, Literal(Value::Vector(vec![Value::Usize(0), Value::Bool(true)]))).evaluate(&data), Value::Usize(2));
+        assert!(is_error(unary(UnaryOp::Upper, number(0)).evaluate(&data)));
+        assert!(is_error(binary(BinaryOp::Lt, Column(0), number(0)).evaluate(&data)));
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(cast(text("12"), Type::Usize).evaluate(&[]), Value::Usize(12));
+        assert_eq!(cast(text("true"), Type::Bool).evaluate(&[]), Value::Bool(true));
+        assert_eq!(cast(number(2), Type::Bool).evaluate(&[]), Value::Bool(true));
+        assert_eq!(cast(truth(true), Type::Usize).evaluate(&[]), Value::Usize(1));
+        assert_eq!(cast(number(12), Type::String).evaluate(&[]), Value::String("12".to_string()));
+        assert_eq!(cast(number(5), Type::Duration).evaluate(&[]), Value::Duration(Duration::from_nanos(5)));
+        assert_eq!(cast(Literal(Value::Duration(Duration::from_nanos(5))), Type::Usize).evaluate(&[]), Value::Usize(5));
+        assert_eq!(cast(text("twelve"), Type::Usize).evaluate(&[]), Value::Error("cannot parse usize".to_string()));
+        assert!(is_error(cast(Literal(Value::Vector(vec![])), Type::String).evaluate(&[])));
+    }
+
+    #[test]
+    fn case() {
+        // CASE WHEN x = 0 THEN 'zero' WHEN x < 10 THEN 'small' ELSE 'large' END
+        let expression = Expression::Case(
+            vec![
+                (binary(BinaryOp::Eq, Column(0), number(0)), text("zero")),
+                (binary(BinaryOp::Lt, Column(0), number(10)), text("small")),
+            ],
+            Box::new(text("large")),
+        );
+        assert_eq!(expression.evaluate(&[Value::Usize(0)]), Value::String("zero".to_string()));
+        assert_eq!(expression.evaluate(&[Value::Usize(5)]), Value::String("small".to_string()));
+        assert_eq!(expression.evaluate(&[Value::Usize(50)]), Value::String("large".to_string()));
+
+        // Only the selected branch is evaluated.
+        let guarded = Expression::Case(
+            vec![(binary(BinaryOp::Eq, Column(0), number(0)), number(0))],
+            Box::new(binary(BinaryOp::Div, number(10), Column(0))),
+        );
+        assert_eq!(guarded.evaluate(&[Value::Usize(0)]), Value::Usize(0));
+        assert_eq!(guarded.evaluate(&[Value::Usize(5)]), Value::Usize(2));
+
+        // Conditions must be truth values.
+        let ill_typed = Expression::Case(vec![(number(1), number(0))], Box::new(number(1)));
+        assert!(is_error(ill_typed.evaluate(&[])));
+    }
+
+    #[test]
+    fn short_circuit() {
+        let error = binary(BinaryOp::Div, number(1), number(0));
+        assert_eq!(binary(BinaryOp::And, truth(false), error.clone()).evaluate(&[]), Value::Bool(false));
+        assert_eq!(binary(BinaryOp::Or, truth(true), error.clone()).evaluate(&[]), Value::Bool(true));
+        assert!(is_error(binary(BinaryOp::And, truth(true), error.clone()).evaluate(&[])));
+        assert!(is_error(binary(BinaryOp::Or, truth(false), error.clone()).evaluate(&[])));
+        assert!(is_error(binary(BinaryOp::And, error.clone(), truth(false)).evaluate(&[])));
+        assert_eq!(unary(UnaryOp::Not, binary(BinaryOp::Or, truth(false), truth(false))).evaluate(&[]), Value::Bool(true));
+    }
+
+    #[test]
+    fn type_of() {
+        let types = vec![Type::Usize, Type::String, Type::Bool];
+        assert_eq!(binary(BinaryOp::Add, Column(0), number(1)).type_of(&types), Ok(Type::Usize));
+        assert_eq!(binary(BinaryOp::Concat, Column(1), text("s")).type_of(&types), Ok(Type::String));
+        assert_eq!(binary(BinaryOp::And, Column(2), binary(BinaryOp::Ge, Column(0), number(1))).type_of(&types), Ok(Type::Bool));
+        assert_eq!(unary(UnaryOp::Length, Column(1)).type_of(&types), Ok(Type::Usize));
+        assert_eq!(cast(Column(1), Type::Usize).type_of(&types), Ok(Type::Usize));
+        assert_eq!(cast(Column(0), Type::Duration).type_of(&types), Ok(Type::Duration));
+        assert_eq!(Expression::Case(vec![(Column(2), number(0))], Box::new(Column(0))).type_of(&types), Ok(Type::Usize));
+
+        assert!(binary(BinaryOp::Add, Column(0), Column(1)).type_of(&types).is_err());
+        assert!(binary(BinaryOp::Eq, Column(0), Column(1)).type_of(&types).is_err());
+        assert!(unary(UnaryOp::Not, Column(0)).type_of(&types).is_err());
+        assert!(cast(Column(2), Type::Duration).type_of(&types).is_err());
+        assert!(Column(3).type_of(&types).is_err());
+        assert!(Literal(Value::Error("bad".to_string())).type_of(&types).is_err());
+        assert!(Expression::Case(vec![(Column(0), number(0))], Box::new(number(1))).type_of(&types).is_err());
+        assert!(Expression::Case(vec![(Column(2), text("x"))], Box::new(number(1))).type_of(&types).is_err());
+    }
+}
@@ -27,6 +27,18 @@ pub mod logging;
 
 pub mod concrete;
 
+pub mod response;
+pub use response::Response;
+
+pub mod sql;
+
+pub mod schema;
+pub use schema::Schema;
+
+pub mod server;
+
+pub mod wal;
+
 /// System-wide notion of time.
 pub type Time = ::std::time::Duration;
 /// System-wide update type.
@@ -41,9 +53,33 @@ pub trait Datum : Hash+Sized+Debug {
     /// A type that can act on slices of data.
     type Expression : Clone+Debug+Eq+Ord+Hash+Serialize+for<'a>Deserialize<'a>;
     /// Applies an expression to a slice of data.
+    ///
+    /// Expressions may be ill-typed for the data they are applied to. Implementors should not
+    /// panic in this case, but should produce a value representing the error, so that it can
+    /// be reported back with the results.
     fn subject_to(data: &[Self], expr: &Self::Expression) -> Self;
+    /// Indicates if an expression applied to a slice of data evaluates to true or false.
+    ///
+    /// The result is `None` if the expression produces an error, or a value that is not a truth value.
+    fn satisfies(data: &[Self], expr: &Self::Expression) -> Option<bool>;
+    /// A type describing values.
+    type Type : Clone+Debug+Eq+Ord+Hash+Serialize+for<'a>Deserialize<'a>;
+    /// Indicates if the value has a type.
+    fn has_type(&self, typ: &Self::Type) -> bool;
+    /// The type of an expression applied to slices of data with `types`, or an error if it is ill-typed.
+    fn type_of(types: &[Self::Type], expr: &Self::Expression) -> Result<Self::Type, String>;
+    /// The type of expressions that can be used as predicates.
+    fn predicate_type() -> Self::Type;
+    /// The type of values created by `from_count`.
+    fn count_type() -> Self::Type;
+    /// The type of sums of values of a type, or an error if they cannot be summed.
+    fn sum_type(typ: &Self::Type) -> Result<Self::Type, String>;
     /// Creates a expression that implements projection.
     fn projection(index: usize) -> Self::Expression;
+    /// Creates a value representing a number of records.
+    fn from_count(count: Diff) -> Self;
+    /// Sums values, each multiplied by its multiplicity.
+    fn sum(values: &[(&Self, Diff)]) -> Self;
 }
 
 /// A type that can be converted to a vector of another type.
@@ -53,6 +89,10 @@ pub trait VectorFrom<T> : Sized {
 }
 
 /// Multiple related collection definitions.
+///
+/// Rules may reference rules defined before them in the same query. If any rule references
+/// itself or a rule defined after it, the rules are mutually recursive and are rendered together
+/// as an iterative fixed point, and published once the iteration has converged.
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Query<V: Datum> {
     /// A list of bindings of names to plans.
@@ -69,6 +109,14 @@ impl<V: Datum> Query<V> {
         self.rules.push(rule);
         self
     }
+    /// Indicates if some rule references itself or a later rule, requiring iterative rendering.
+    pub fn is_recursive(&self) -> bool {
+        let names = self.rules.iter().map(|rule| rule.name.clone()).collect::<Vec<_>>();
+        self.rules
+            .iter()
+            .enumerate()
+            .any(|(index, rule)| rule.plan.references(&names[index..]))
+    }
 }
 
 impl<V: Datum> Query<V> {
@@ -2,9 +2,12 @@
 
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::rc::Rc;
+use std::cell::RefCell;
 // use std::time::Duration;
 
-use timely::dataflow::ProbeHandle;
+use timely::dataflow::{Scope, ProbeHandle};
+use timely::dataflow::operators::CapabilitySet;
 use timely::communication::Allocate;
 use timely::worker::Worker;
 use timely::logging::TimelyEvent;
@@ -13,12 +16,16 @@ use timely::logging::TimelyEvent;
 
 use differential_dataflow::ExchangeData;
 use differential_dataflow::trace::implementations::ord::{OrdKeySpine, OrdValSpine};
-use differential_dataflow::operators::arrange::TraceAgent;
+use differential_dataflow::trace::TraceReader;
+use differential_dataflow::operators::arrange::{TraceAgent, Arranged, ShutdownButton};
 use differential_dataflow::input::InputSession;
 
 use differential_dataflow::logging::DifferentialEvent;
 
-use crate::{Time, Diff, Plan, Datum};
+use crate::{Time, Diff, Plan, Datum, Schema};
+use crate::sql::Catalog;
+use crate::response::Clients;
+use crate::wal::CommandLog;
 
 /// A trace handle for key-only data.
 pub type TraceKeyHandle<K, T, R> = TraceAgent<OrdKeySpine<K, T, R>>;
@@ -28,6 +35,10 @@ pub type TraceValHandle<K, V, T, R> = TraceAgent<OrdValSpine<K, V, T, R>>;
 pub type KeysOnlyHandle<V> = TraceKeyHandle<Vec<V>, Time, Diff>;
 /// A key-value trace handle binding `Time` and `Diff` using `Vec<V>` as data.
 pub type KeysValsHandle<V> = TraceValHandle<Vec<V>, Vec<V>, Time, Diff>;
+/// The shutdown button of the dataflow of a subscription, shared by the dataflow and the manager.
+///
+/// The button is taken and pressed by whichever first finds the client gone.
+pub type Subscription = Rc<RefCell<Option<ShutdownButton<CapabilitySet<Time>>>>>;
 
 /// Manages inputs and traces.
 pub struct Manager<V: ExchangeData+Datum> {
@@ -37,6 +48,16 @@ pub struct Manager<V: ExchangeData+Datum> {
     pub traces: TraceManager<V>,
     /// Probes all computations.
     pub probe: ProbeHandle<Time>,
+    /// Schemas of inputs and published rules, where known.
+    pub schemas: HashMap<String, Schema<V>>,
+    /// Column names of relations created by SQL statements.
+    pub catalog: Catalog,
+    /// Connections to clients, for responses.
+    pub clients: Clients,
+    /// A log recording executed commands, if they should be durable.
+    pub log: Option<CommandLog<V>>,
+    /// The subscriptions and pending peeks of each client, with the names of the rules they read.
+    pub subscriptions: HashMap<usize, Vec<(String, Subscription)>>,
 }
 
 impl<V: ExchangeData+Datum> Manager<V>
@@ -50,6 +71,11 @@ impl<V: ExchangeData+Datum> Manager<V>
             inputs: InputManager::new(),
             traces: TraceManager::new(),
             probe: ProbeHandle::new(),
+            schemas: HashMap::new(),
+            catalog: Catalog::new(),
+            clients: Clients::new(),
+            log: None,
+            subscriptions: HashMap::new(),
         }
     }
 
@@ -80,9 +106,14 @@ impl<V: ExchangeData+Datum> Manager<V>
 
     /// Clear the managed inputs and traces.
     pub fn shutdown<A: Allocate>(&mut self, worker: &mut Worker<A>) {
+        let clients = self.subscriptions.keys().cloned().collect::<Vec<_>>();
+        for client in clients {
+            self.disconnect(client);
+        }
         self.inputs.sessions.clear();
         self.traces.inputs.clear();
         self.traces.arrangements.clear();
+        self.schemas.clear();
 
         // Deregister loggers, so that the logging dataflows can shut down.
         worker
@@ -94,17 +125,70 @@ impl<V: ExchangeData+Datum> Manager<V>
             .insert::<DifferentialEvent,_>("differential/arrange", move |_time, _data| { });
     }
 
-    /// Inserts a new input session by name.
+    /// Records a subscription of a client to a rule, forgetting those that have shut down.
+    pub fn subscribe(&mut self, client: usize, name: String, subscription: Subscription) {
+        let subscriptions = self.subscriptions.entry(client).or_insert_with(Vec::new);
+        subscriptions.retain(|(_name, subscription)| subscription.borrow().is_some());
+        subscriptions.push((name, subscription));
+    }
+
+    /// Shuts down the dataflows of the subscriptions of a client.
+    pub fn disconnect(&mut self, client: usize) {
+        for (_name, subscription) in self.subscriptions.remove(&client).unwrap_or_default() {
+            if let Some(mut button) = subscription.borrow_mut().take() {
+                button.press();
+            }
+        }
+    }
+
+    /// Shuts down the dataflows of subscriptions to rules that are no longer published.
+    ///
+    /// Subscriptions import the traces of rules without using them as queries do, and so do not
+    /// keep dropped queries from being released. They are instead shut down with their rules.
+    pub fn unsubscribe_unpublished(&mut self)
+    where
+        V: Hash,
+    {
+        let traces = &self.traces;
+        for subscriptions in self.subscriptions.values_mut() {
+            for (name, subscription) in subscriptions.iter() {
+                if !traces.is_published(name) {
+                    if let Some(mut button) = subscription.borrow_mut().take() {
+                        button.press();
+                    }
+                }
+            }
+            subscriptions.retain(|(_name, subscription)| subscription.borrow().is_some());
+        }
+        self.subscriptions.retain(|_client, subscriptions| !subscriptions.is_empty());
+    }
+
+    /// Inserts a new input session by name, with the schema of its tuples.
     pub fn insert_input(
         &mut self,
         name: String,
+        schema: Schema<V>,
         input: InputSession<Time, Vec<V>, Diff>,
         trace: KeysOnlyHandle<V>)
     {
         self.inputs.sessions.insert(name.clone(), input);
+        self.schemas.insert(name.clone(), schema);
         self.traces.set_unkeyed(&Plan::Source(name), &trace);
     }
 
+    /// The schema of a published relation, `None` if it has no schema, or an error if there is no such relation.
+    pub fn schema(&self, name: &str) -> Result<Option<Schema<V>>, String>
+    where
+        V: Hash,
+    {
+        if self.traces.is_published(name) {
+            Ok(self.schemas.get(name).cloned())
+        }
+        else {
+            Err(format!("relation not found: {}", name))
+        }
+    }
+
     /// Advances inputs and traces to `time`.
     pub fn advance_time(&mut self, time: &Time) {
         self.inputs.advance_time(time);
@@ -136,12 +220,14 @@ impl<V: ExchangeData+Datum> Manager<V>
 pub struct InputManager<V: ExchangeData> {
     /// Input sessions by name.
     pub sessions: HashMap<String, InputSession<Time, Vec<V>, Diff>>,
+    /// The time to which inputs were last advanced.
+    pub time: Time,
 }
 
 impl<V: ExchangeData> InputManager<V> {
 
     /// Creates a new empty input manager.
-    pub fn new() -> Self { Self { sessions: HashMap::new() } }
+    pub fn new() -> Self { Self { sessions: HashMap::new(), time: Time::default() } }
 
     /// Advances the times of all managed inputs.
     pub fn advance_time(&mut self, time: &Time) {
@@ -149,14 +235,29 @@ impl<V: ExchangeData> InputManager<V> {
             session.advance_to(time.clone());
             session.flush();
         }
+        self.time = time.clone();
     }
 
 }
 
+/// Sizes of a collection, for estimating the costs of using it.
+///
+/// Statistics are derived from the commands that update inputs, rather than from the contents
+/// of a worker's arrangements, so that all workers agree on them and plan the same dataflows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Statistics {
+    /// The number of records: the sum of the multiplicities of all updates.
+    pub records: Diff,
+}
+
 /// Root handles to maintained collections.
 ///
 /// Manages a map from plan (describing a collection)
 /// to various arranged forms of that collection.
+///
+/// The trace manager also records, for each installed query, the arrangements its dataflow
+/// produces and the queries whose arrangements it uses, so that a dropped query can be shut
+/// down and its arrangements released once no other query uses them.
 pub struct TraceManager<V: ExchangeData+Datum> {
 
     /// Arrangements where the record itself is they key.
@@ -169,6 +270,37 @@ pub struct TraceManager<V: ExchangeData+Datum> {
     /// Arrangements of collections by key.
     arrangements: HashMap<Plan<V>, HashMap<Vec<usize>, KeysValsHandle<V>>>,
 
+    /// The query whose dataflow produces each arrangement, if it is produced by a query.
+    producers: HashMap<(Plan<V>, Option<Vec<usize>>), usize>,
+
+    /// The query being installed, if any.
+    installing: Option<InstalledQuery>,
+
+    /// Installed queries that have not been released, by identifier.
+    queries: HashMap<usize, InstalledQuery>,
+
+    /// The number of other installed queries using arrangements of each query.
+    references: HashMap<usize, usize>,
+
+    /// The identifier of the next query to be installed.
+    next_query: usize,
+
+    /// Statistics of collections, where known.
+    statistics: HashMap<Plan<V>, Statistics>,
+}
+
+/// Resources of an installed query.
+struct InstalledQuery {
+    /// Identifier of the query.
+    id: usize,
+    /// Names of the rules of the query.
+    names: Vec<String>,
+    /// Shutdown buttons for each trace imported into the query's dataflow.
+    buttons: Vec<ShutdownButton<CapabilitySet<Time>>>,
+    /// Queries whose arrangements the query uses.
+    uses: Vec<usize>,
+    /// Indicates that the query has been dropped, and should be released once unused.
+    dropped: bool,
 }
 
 impl<V: ExchangeData+Hash+Datum> TraceManager<V> {
@@ -177,14 +309,18 @@ impl<V: ExchangeData+Hash+Datum> TraceManager<V> {
     pub fn new() -> Self {
         Self {
             inputs: HashMap::new(),
-            arrangements: HashMap::new()
+            arrangements: HashMap::new(),
+            producers: HashMap::new(),
+            installing: None,
+            queries: HashMap::new(),
+            references: HashMap::new(),
+            next_query: 0,
+            statistics: HashMap::new(),
         }
     }
 
     /// Advances the frontier of each maintained trace.
     pub fn advance_time(&mut self, time: &Time) {
-        use differential_dataflow::trace::TraceReader;
-
         let frontier = &[time.clone()];
         for trace in self.inputs.values_mut() {
             trace.advance_by(frontier);
@@ -198,24 +334,73 @@ impl<V: ExchangeData+Hash+Datum> TraceManager<V> {
         }
     }
 
+    /// Indicates if a dropped query defining `name` still runs, for other queries that use it.
+    ///
+    /// Rules of this name cannot be defined until the dropped query is released.
+    pub fn is_retained(&self, name: &str) -> bool {
+        self.queries
+            .values()
+            .any(|query| query.dropped && query.names.iter().any(|n| n == name))
+    }
+
+    /// Indicates if a relation is published by name.
+    pub fn is_published(&self, name: &str) -> bool {
+        self.inputs.contains_key(&Plan::Source(name.to_string()))
+    }
+
     /// Recover an arrangement by plan and keys, if it is cached.
-    pub fn get_unkeyed(&self, plan: &Plan<V>) -> Option<KeysOnlyHandle<V>> {
-        self.inputs
-            .get(plan)
-            .map(|x| x.clone())
+    pub fn get_unkeyed(&mut self, plan: &Plan<V>) -> Option<KeysOnlyHandle<V>> {
+        let handle = self.inputs.get(plan).map(|x| x.clone());
+        if handle.is_some() {
+            self.record_use(plan, None);
+        }
+        handle
     }
 
     /// Installs an unkeyed arrangement for a specified plan.
     pub fn set_unkeyed(&mut self, plan: &Plan<V>, handle: &KeysOnlyHandle<V>) {
         self.inputs
             .insert(plan.clone(), handle.clone());
+        self.record_production(plan, None);
     }
 
     /// Recover an arrangement by plan and keys, if it is cached.
-    pub fn get_keyed(&self, plan: &Plan<V>, keys: &[usize]) -> Option<KeysValsHandle<V>> {
+    pub fn get_keyed(&mut self, plan: &Plan<V>, keys: &[usize]) -> Option<KeysValsHandle<V>> {
+        let handle =
         self.arrangements
             .get(plan)
-            .and_then(|map| map.get(keys).map(|x| x.clone()))
+            .and_then(|map| map.get(keys).map(|x| x.clone()));
+        if handle.is_some() {
+            self.record_use(plan, Some(keys));
+        }
+        handle
+    }
+
+    /// Indicates if an arrangement of a plan by `keys` is cached.
+    ///
+    /// Unlike `get_keyed`, this does not record that a query uses the arrangement.
+    pub fn is_keyed(&self, plan: &Plan<V>, keys: &[usize]) -> bool {
+        self.arrangements
+            .get(plan)
+            .map(|map| map.contains_key(keys))
+            .unwrap_or(false)
+    }
+
+    /// Statistics of the collection described by a plan, if they are known.
+    pub fn statistics(&self, plan: &Plan<V>) -> Option<Statistics> {
+        self.statistics.get(plan).cloned()
+    }
+
+    /// Replaces the statistics of the collection described by a plan.
+    pub fn set_statistics(&mut self, plan: &Plan<V>, statistics: Statistics) {
+        self.statistics.insert(plan.clone(), statistics);
+    }
+
+    /// Accounts for updates to the collection described by a plan, whose multiplicities sum to `records`.
+    pub fn count_records(&mut self, plan: &Plan<V>, records: Diff) {
+        if let Some(statistics) = self.statistics.get_mut(plan) {
+            statistics.records += records;
+        }
     }
 
     /// Installs a keyed arrangement for a specified plan and sequence of keys.
@@ -224,6 +409,132 @@ impl<V: ExchangeData+Hash+Datum> TraceManager<V> {
             .entry(plan.clone())
             .or_insert(HashMap::new())
             .insert(keys.to_vec(), handle.clone());
+        self.record_production(plan, Some(keys));
+    }
+
+    /// Imports a trace into a scope.
+    ///
+    /// If a query is being installed, the import is shut down when the query is released.
+    pub fn import<G, Tr>(&mut self, trace: &mut TraceAgent<Tr>, scope: &G) -> Arranged<G, TraceAgent<Tr>>
+    where
+        G: Scope<Timestamp=Time>,
+        Tr: TraceReader<Time=Time>+'static,
+    {
+        let (arranged, button) = trace.import_core(scope, "ArrangedSource");
+        if let Some(query) = self.installing.as_mut() {
+            query.buttons.push(button);
+        }
+        arranged
+    }
+
+    /// Starts recording the arrangements used and produced by a query being installed.
+    pub fn begin_query(&mut self) {
+        let id = self.next_query;
+        self.next_query += 1;
+        self.installing = Some(InstalledQuery {
+            id,
+            names: Vec::new(),
+            buttons: Vec::new(),
+            uses: Vec::new(),
+            dropped: false,
+        });
     }
 
-}
\ No newline at end of file
+    /// Completes the installation of a query with rules named `names`.
+    pub fn end_query(&mut self, names: Vec<String>) {
+        let mut query = self.installing.take().expect("no query being installed");
+        query.names = names;
+        query.uses.sort();
+        query.uses.dedup();
+        query.uses.retain(|id| id != &query.id);
+        for id in query.uses.iter() {
+            *self.references.entry(*id).or_insert(0) += 1;
+        }
+        self.queries.insert(query.id, query);
+    }
+
+    /// Drops the installed query with a rule named `name`, returning false if there is none.
+    ///
+    /// The rules of the query are no longer published. The query's dataflow is shut down, and
+    /// the arrangements it produces released, once no other installed query uses them.
+    ///
+    /// Arrangements are cached by plan, and plans refer to rules by name. Cached arrangements of
+    /// plans referencing the dropped rules, including those produced by other queries, are forgotten
+    /// so that they cannot be confused with those of later rules of the same names.
+    pub fn drop_query(&mut self, name: &str) -> bool {
+
+        let names =
+        match self.queries.values_mut().find(|query| !query.dropped && query.names.iter().any(|n| n == name)) {
+            Some(query) => {
+                query.dropped = true;
+                query.names.clone()
+            },
+            None => { return false; },
+        };
+
+        self.inputs.retain(|plan, _| !plan.references(&names));
+        self.arrangements.retain(|plan, _| !plan.references(&names));
+        self.producers.retain(|(plan, _keys), _| !plan.references(&names));
+        self.statistics.retain(|plan, _| !plan.references(&names));
+
+        // Release dropped queries whose arrangements are not used by other queries.
+        while let Some(id) =
+            self.queries
+                .values()
+                .find(|query| query.dropped && self.references.get(&query.id).map(|count| *count == 0).unwrap_or(true))
+                .map(|query| query.id)
+        {
+            let mut query = self.queries.remove(&id).expect("query not found");
+            for button in query.buttons.iter_mut() {
+                button.press();
+            }
+            for used in query.uses.iter() {
+                *self.references.get_mut(used).expect("reference not counted") -= 1;
+            }
+            self.references.remove(&id);
+
+            // Forget arrangements produced by the query, dropping our handles to them.
+            let produced =
+            self.producers
+                .iter()
+                .filter(|(_entry, producer)| **producer == id)
+                .map(|(entry, _producer)| entry.clone())
+                .collect::<Vec<_>>();
+
+            for (plan, keys) in produced.into_iter() {
+                match &keys {
+                    None => { self.inputs.remove(&plan); },
+                    Some(keys) => {
+                        if let Some(map) = self.arrangements.get_mut(&plan) {
+                            map.remove(keys);
+                            if map.is_empty() {
+                                self.arrangements.remove(&plan);
+                            }
+                        }
+                    },
+                }
+                self.producers.remove(&(plan, keys));
+            }
+        }
+
+        true
+    }
+
+    /// Records that the query being installed uses an arrangement.
+    fn record_use(&mut self, plan: &Plan<V>, keys: Option<&[usize]>) {
+        if let Some(query) = self.installing.as_mut() {
+            if let Some(producer) = self.producers.get(&(plan.clone(), keys.map(|k| k.to_vec()))) {
+                query.uses.push(*producer);
+            }
+        }
+    }
+
+    /// Records the production of an arrangement by the query being installed, if any.
+    fn record_production(&mut self, plan: &Plan<V>, keys: Option<&[usize]>) {
+        let entry = (plan.clone(), keys.map(|k| k.to_vec()));
+        match self.installing.as_ref() {
+            Some(query) => { self.producers.insert(entry, query.id); },
+            None => { self.producers.remove(&entry); },
+        }
+    }
+}
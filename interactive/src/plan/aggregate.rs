@@ -0,0 +1,136 @@
+//! Grouped aggregation expression plan.
+
+use std::hash::Hash;
+
+use timely::dataflow::Scope;
+
+use differential_dataflow::{Collection, ExchangeData};
+use differential_dataflow::lattice::Lattice;
+use plan::{Plan, Render, Arrangements, arrange_keyed};
+use {Diff, Datum};
+
+/// An aggregate function of the records in a group.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Aggregation {
+    /// The number of records.
+    Count,
+    /// The sum of the values at an index.
+    Sum(usize),
+    /// The least of the values at an index.
+    Min(usize),
+    /// The greatest of the values at an index.
+    Max(usize),
+}
+
+impl Aggregation {
+    /// The index of the aggregated values, if any.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Aggregation::Count => None,
+            Aggregation::Sum(index) => Some(*index),
+            Aggregation::Min(index) => Some(*index),
+            Aggregation::Max(index) => Some(*index),
+        }
+    }
+}
+
+/// A plan stage grouping source tuples by the values at `keys`, and
+/// producing for each group its keys followed by each of `aggregates`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Aggregate<V: Datum> {
+    /// Indices whose values form the group key.
+    pub keys: Vec<usize>,
+    /// Aggregates to produce for each group.
+    pub aggregates: Vec<Aggregation>,
+    /// Plan for the data source.
+    pub plan: Box<Plan<V>>,
+}
+
+/// The location of an aggregated value, among the key and value tuples.
+#[derive(Clone, Copy)]
+enum Location {
+    Key(usize),
+    Val(usize),
+}
+
+/// Produces logic that aggregates groups of `(key, vals)` tuples, as arranged by `keys`.
+///
+/// The logic produces for each group the aggregates, in order.
+pub(crate) fn aggregator<V: Datum+Clone+Ord>(keys: &[usize], aggregates: &[Aggregation])
+    -> impl FnMut(&Vec<V>, &[(&Vec<V>, Diff)], &mut Vec<(Vec<V>, Diff)>)+'static
+{
+    // Values at key indices are found in the key, and other values are found in
+    // the tuple of values, which omits the key indices.
+    let locations =
+    aggregates
+        .iter()
+        .map(|aggregate| aggregate.index().map(|index|
+            match keys.iter().position(|key| key == &index) {
+                Some(position) => Location::Key(position),
+                None => Location::Val(index - keys.iter().filter(|key| **key < index).count()),
+            }
+        ))
+        .collect::<Vec<_>>();
+
+    let aggregates = aggregates.to_vec();
+
+    move |key, input, output| {
+        let result =
+        aggregates
+            .iter()
+            .zip(locations.iter())
+            .map(|(aggregate, location)| {
+                let values =
+                input
+                    .iter()
+                    .map(|(vals, diff)| match location {
+                        Some(Location::Key(position)) => (&key[*position], *diff),
+                        Some(Location::Val(position)) => (&vals[*position], *diff),
+                        None => unreachable!("counts do not reference values"),
+                    });
+
+                match aggregate {
+                    Aggregation::Count => V::from_count(input.iter().map(|(_, diff)| *diff).sum()),
+                    Aggregation::Sum(_) => V::sum(&values.collect::<Vec<_>>()[..]),
+                    Aggregation::Min(_) => values.map(|(value, _)| value).min().expect("empty group").clone(),
+                    Aggregation::Max(_) => values.map(|(value, _)| value).max().expect("empty group").clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        output.push((result, 1));
+    }
+}
+
+impl<V: ExchangeData+Hash+Datum> Render for Aggregate<V> {
+
+    type Value = V;
+
+    fn render<S, A>(
+        &self,
+        scope: &mut S,
+        collections: &mut std::collections::HashMap<Plan<Self::Value>, Collection<S, Vec<Self::Value>, Diff>>,
+        arrangements: &mut A,
+    ) -> Collection<S, Vec<Self::Value>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, Self::Value>,
+    {
+        use differential_dataflow::operators::reduce::ReduceCore;
+        use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+        // acquire an arrangement by keys, shared with any other use of the same keys.
+        let input = arrange_keyed(&self.plan, &self.keys[..], scope, collections, arrangements);
+
+        let output =
+        input
+            .reduce_abelian::<_,OrdValSpine<_,_,_,_>>(aggregator(&self.keys[..], &self.aggregates[..]));
+
+        // The output is itself arranged by its leading key values.
+        let output_keys = (0 .. self.keys.len()).collect::<Vec<_>>();
+        arrangements.add_keyed(&Plan::Aggregate(self.clone()), &output_keys[..], &output);
+
+        output.as_collection(|keys, aggregates| keys.iter().cloned().chain(aggregates.iter().cloned()).collect())
+    }
+}
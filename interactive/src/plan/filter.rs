@@ -5,8 +5,9 @@ use std::hash::Hash;
 use timely::dataflow::Scope;
 
 use differential_dataflow::{Collection, ExchangeData};
-use plan::{Plan, Render};
-use {TraceManager, Time, Diff, Datum};
+use differential_dataflow::lattice::Lattice;
+use plan::{Plan, Render, Arrangements};
+use {Diff, Datum};
 
 /// What to compare against.
 ///
@@ -31,7 +32,7 @@ impl<Value> SecondArgument<Value> {
 
 /// Possible predicates to apply.
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub enum Predicate<Value> {
+pub enum Predicate<Value: Datum> {
     /// Strictly less than.
     LessThan(usize, SecondArgument<Value>),
     /// Less than or equal.
@@ -50,21 +51,81 @@ pub enum Predicate<Value> {
     All(Vec<Predicate<Value>>),
     /// The complement of a predicate.
     Not(Box<Predicate<Value>>),
+    /// An expression evaluating to true.
+    ///
+    /// An expression that produces an error is neither true nor false, and its tuples are
+    /// not retained by the predicate or by its complement. Such tuples are dropped silently,
+    /// as are those with nulls in SQL `WHERE` clauses.
+    Expression(Value::Expression),
 }
 
-impl<Value: Ord> Predicate<Value> {
+impl<Value: Datum+Ord> Predicate<Value> {
     /// Indicates if the predicate is satisfied.
+    ///
+    /// The predicate is not satisfied if its truth is unknown, due to an error in an expression.
     pub fn satisfied(&self, values: &[Value]) -> bool {
+        self.truth(values) == Some(true)
+    }
+    /// Indicates if the predicate is true or false, or `None` if an expression produces an error.
+    fn truth(&self, values: &[Value]) -> Option<bool> {
+        match self {
+            Predicate::LessThan(index, other) => Some(values[*index].lt(other.value(values))),
+            Predicate::LessEqual(index, other) => Some(values[*index].le(other.value(values))),
+            Predicate::GreaterThan(index, other) => Some(values[*index].gt(other.value(values))),
+            Predicate::GreaterEqual(index, other) => Some(values[*index].ge(other.value(values))),
+            Predicate::Equal(index, other) => Some(values[*index].eq(other.value(values))),
+            Predicate::NotEqual(index, other) => Some(values[*index].ne(other.value(values))),
+            Predicate::Any(predicates) => {
+                let truths = predicates.iter().map(|p| p.truth(values)).collect::<Vec<_>>();
+                if truths.contains(&Some(true)) { Some(true) }
+                else if truths.contains(&None) { None }
+                else { Some(false) }
+            },
+            Predicate::All(predicates) => {
+                let truths = predicates.iter().map(|p| p.truth(values)).collect::<Vec<_>>();
+                if truths.contains(&Some(false)) { Some(false) }
+                else if truths.contains(&None) { None }
+                else { Some(true) }
+            },
+            Predicate::Not(predicate) => predicate.truth(values).map(|truth| !truth),
+            Predicate::Expression(expr) => Value::satisfies(values, expr),
+        }
+    }
+    /// Checks that the predicate compares values of the same type, for tuples with `types`.
+    pub fn check(&self, types: &[Value::Type]) -> Result<(), String> {
         match self {
-            Predicate::LessThan(index, other) => values[*index].lt(other.value(values)),
-            Predicate::LessEqual(index, other) => values[*index].le(other.value(values)),
-            Predicate::GreaterThan(index, other) => values[*index].gt(other.value(values)),
-            Predicate::GreaterEqual(index, other) => values[*index].ge(other.value(values)),
-            Predicate::Equal(index, other) => values[*index].eq(other.value(values)),
-            Predicate::NotEqual(index, other) => values[*index].ne(other.value(values)),
-            Predicate::Any(predicates) => predicates.iter().any(|p| p.satisfied(values)),
-            Predicate::All(predicates) => predicates.iter().all(|p| p.satisfied(values)),
-            Predicate::Not(predicate) => !predicate.satisfied(values),
+            Predicate::LessThan(index, other) |
+            Predicate::LessEqual(index, other) |
+            Predicate::GreaterThan(index, other) |
+            Predicate::GreaterEqual(index, other) |
+            Predicate::Equal(index, other) |
+            Predicate::NotEqual(index, other) => {
+                let typ = types.get(*index).ok_or_else(|| format!("column {} out of range for {} columns", index, types.len()))?;
+                match other {
+                    SecondArgument::Constant(value) => {
+                        if !value.has_type(typ) {
+                            return Err(format!("cannot compare {:?} with {:?}", typ, value));
+                        }
+                    },
+                    SecondArgument::Position(other) => {
+                        let other = types.get(*other).ok_or_else(|| format!("column {} out of range for {} columns", other, types.len()))?;
+                        if typ != other {
+                            return Err(format!("cannot compare {:?} with {:?}", typ, other));
+                        }
+                    },
+                }
+                Ok(())
+            },
+            Predicate::Any(predicates) => predicates.iter().map(|p| p.check(types)).collect(),
+            Predicate::All(predicates) => predicates.iter().map(|p| p.check(types)).collect(),
+            Predicate::Not(predicate) => predicate.check(types),
+            Predicate::Expression(expr) => {
+                let typ = Value::type_of(types, expr)?;
+                if typ != Value::predicate_type() {
+                    return Err(format!("predicate has type {:?}", typ));
+                }
+                Ok(())
+            },
         }
     }
 }
@@ -84,12 +145,16 @@ impl<V: ExchangeData+Hash+Datum> Render for Filter<V> {
 
     type Value = V;
 
-    fn render<S: Scope<Timestamp = Time>>(
+    fn render<S, A>(
         &self,
         scope: &mut S,
         collections: &mut std::collections::HashMap<Plan<Self::Value>, Collection<S, Vec<Self::Value>, Diff>>,
-        arrangements: &mut TraceManager<Self::Value>,
+        arrangements: &mut A,
     ) -> Collection<S, Vec<Self::Value>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, Self::Value>,
     {
         let predicate = self.predicate.clone();
         self.plan
@@ -0,0 +1,153 @@
+//! Rendering of mutually recursive rules.
+//!
+//! The rules of a query may reference each other, including themselves and rules that follow
+//! them, in which case the rules are rendered together in an iterative scope. Each rule becomes
+//! a relation in a `RecursiveGroup`, whose value in each iteration is available to all rules,
+//! and which is consolidated from the rule's plan. Once the iteration converges, the final
+//! values of the rules are returned to the outer scope, where they can be arranged and published.
+//!
+//! Sub-plans that do not reference any of the rules are the same in each iteration, and are
+//! rendered outside the iterative scope, where they can use and share the maintained arrangements.
+//! Sub-plans that do reference the rules are rendered inside the iterative scope by the same
+//! renderer, using arrangements private to the scope and shared by the rules.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use timely::order::Product;
+use timely::dataflow::Scope;
+
+use differential_dataflow::{Collection, ExchangeData};
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::iterate::RecursiveGroup;
+
+use plan::{Plan, Render, Arrangements, Inventory, UnkeyedArrangement, KeyedArrangement};
+use manager::Statistics;
+use {TraceManager, Time, Diff, Datum, Rule};
+
+/// Renders mutually recursive rules, returning the final collection for each rule.
+pub fn render_fixpoint<S, V>(
+    rules: &[Rule<V>],
+    scope: &mut S,
+    collections: &mut HashMap<Plan<V>, Collection<S, Vec<V>, Diff>>,
+    arrangements: &mut TraceManager<V>,
+) -> Vec<Collection<S, Vec<V>, Diff>>
+where
+    S: Scope<Timestamp = Time>,
+    V: ExchangeData+Hash+Datum,
+{
+    let names = rules.iter().map(|rule| rule.name.clone()).collect::<Vec<_>>();
+
+    // Render loop-invariant sub-plans in the outer scope.
+    let mut invariant = Vec::new();
+    for rule in rules.iter() {
+        collect_invariant(&rule.plan, &names[..], &mut invariant);
+    }
+    let invariant =
+    invariant
+        .into_iter()
+        .map(|plan| {
+            let collection = plan.render(scope, collections, arrangements);
+            (plan, collection)
+        })
+        .collect::<Vec<_>>();
+
+    scope.iterative::<u64,_,_>(|inner| {
+
+        let mut rendered = HashMap::new();
+        let mut local = LocalArrangements::new();
+        for (plan, collection) in invariant.into_iter() {
+            rendered.insert(plan, collection.enter(inner));
+        }
+
+        let mut group = RecursiveGroup::new(inner, Product::new(Default::default(), 1));
+        let relations =
+        names
+            .iter()
+            .map(|name| {
+                let relation = group.relation::<Vec<V>, Diff>(name);
+                rendered.insert(Plan::Source(name.clone()), (*relation).clone());
+                relation
+            })
+            .collect::<Vec<_>>();
+
+        for (rule, relation) in rules.iter().zip(relations.iter()) {
+            relation.add_production(&rule.plan.render(inner, &mut rendered, &mut local));
+        }
+
+        group.complete();
+
+        relations
+            .iter()
+            .map(|relation| relation.leave())
+            .collect()
+    })
+}
+
+/// Collects the maximal sub-plans of `plan` that do not reference any of `names`.
+fn collect_invariant<V: ExchangeData+Hash+Datum>(plan: &Plan<V>, names: &[String], invariant: &mut Vec<Plan<V>>) {
+    if !plan.references(names) {
+        if !invariant.contains(plan) {
+            invariant.push(plan.clone());
+        }
+    }
+    else {
+        for input in plan.inputs() {
+            collect_invariant(input, names, invariant);
+        }
+    }
+}
+
+/// Arrangements private to a nested scope, shared by the plans rendered in it.
+struct LocalArrangements<S: Scope, V: ExchangeData+Datum>
+where
+    S::Timestamp: Lattice+Ord,
+{
+    unkeyed: HashMap<Plan<V>, UnkeyedArrangement<S, V>>,
+    keyed: HashMap<(Plan<V>, Vec<usize>), KeyedArrangement<S, V>>,
+}
+
+impl<S: Scope, V: ExchangeData+Hash+Datum> LocalArrangements<S, V>
+where
+    S::Timestamp: Lattice+Ord,
+{
+    fn new() -> Self {
+        LocalArrangements {
+            unkeyed: HashMap::new(),
+            keyed: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Scope, V: ExchangeData+Hash+Datum> Arrangements<S, V> for LocalArrangements<S, V>
+where
+    S::Timestamp: Lattice+Ord,
+{
+    fn unkeyed(&mut self, plan: &Plan<V>, _scope: &mut S) -> Option<UnkeyedArrangement<S, V>> {
+        self.unkeyed.get(plan).cloned()
+    }
+    fn add_unkeyed(&mut self, plan: &Plan<V>, arrangement: &UnkeyedArrangement<S, V>) {
+        self.unkeyed.insert(plan.clone(), arrangement.clone());
+    }
+    fn keyed(&mut self, plan: &Plan<V>, keys: &[usize], _scope: &mut S) -> Option<KeyedArrangement<S, V>> {
+        self.keyed.get(&(plan.clone(), keys.to_vec())).cloned()
+    }
+    fn add_keyed(&mut self, plan: &Plan<V>, keys: &[usize], arrangement: &KeyedArrangement<S, V>) {
+        self.keyed.insert((plan.clone(), keys.to_vec()), arrangement.clone());
+    }
+    /// Iterations are partially ordered times, for which delta queries are incorrect.
+    fn totally_ordered(&self) -> bool { false }
+}
+
+impl<S: Scope, V: ExchangeData+Hash+Datum> Inventory<V> for LocalArrangements<S, V>
+where
+    S::Timestamp: Lattice+Ord,
+{
+    fn has_keyed(&self, plan: &Plan<V>, keys: &[usize]) -> bool {
+        self.keyed.contains_key(&(plan.clone(), keys.to_vec()))
+    }
+    /// Private arrangements are built along with the plans that use them, and have no statistics.
+    fn statistics(&self, _plan: &Plan<V>) -> Option<Statistics> {
+        None
+    }
+}
@@ -5,17 +5,20 @@ use std::hash::Hash;
 use timely::dataflow::Scope;
 
 use differential_dataflow::{Collection, ExchangeData};
-use plan::{Plan, Render};
-use {TraceManager, Time, Diff, Datum};
+use differential_dataflow::lattice::Lattice;
+use plan::{Plan, Render, Arrangements};
+use {Diff, Datum};
 
-/// A plan which retains values at specified locations.
+/// A plan which produces the values of expressions applied to each tuple.
 ///
 /// The plan does not ascribe meaning to specific locations (e.g. bindings)
-/// to variable names, and simply selects out the indicated sequence of values,
-/// panicking if some input record is insufficiently long.
+/// to variable names, and simply produces the indicated sequence of values.
+/// Projections are expressed by `Datum::projection`. Expressions that fail
+/// to evaluate produce values representing their errors, which are retained
+/// in the results and so reported back to clients that read them.
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Map<V: Datum> {
-    /// Sequence (and order) of indices to be retained.
+    /// Sequence (and order) of expressions to produce.
     pub expressions: Vec<V::Expression>,
     /// Plan for the data source.
     pub plan: Box<Plan<V>>,
@@ -24,12 +27,16 @@ pub struct Map<V: Datum> {
 impl<V: ExchangeData+Hash+Datum> Render for Map<V> {
     type Value = V;
 
-    fn render<S: Scope<Timestamp = Time>>(
+    fn render<S, A>(
         &self,
         scope: &mut S,
         collections: &mut std::collections::HashMap<Plan<Self::Value>, Collection<S, Vec<Self::Value>, Diff>>,
-        arrangements: &mut TraceManager<Self::Value>,
+        arrangements: &mut A,
     ) -> Collection<S, Vec<Self::Value>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, Self::Value>,
     {
         let expressions = self.expressions.clone();
 
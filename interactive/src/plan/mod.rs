@@ -3,24 +3,143 @@
 use std::hash::Hash;
 
 use timely::dataflow::Scope;
+use timely::dataflow::scopes::ScopeParent;
 use differential_dataflow::{Collection, ExchangeData};
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::arrange::Arranged;
 
 use {TraceManager, Time, Diff};
+use manager::{Statistics, TraceKeyHandle, TraceValHandle};
 
-// pub mod count;
+pub mod aggregate;
 pub mod filter;
+pub mod fixpoint;
 pub mod join;
 pub mod map;
 pub mod sfw;
 
 use crate::Datum;
 
-// pub use self::count::Count;
+pub use self::aggregate::{Aggregate, Aggregation};
 pub use self::filter::{Filter, Predicate};
 pub use self::join::Join;
 pub use self::sfw::MultiwayJoin;
 pub use self::map::Map;
 
+/// An arrangement of tuples by the whole tuple, in a scope.
+pub type UnkeyedArrangement<S, V> = Arranged<S, TraceKeyHandle<Vec<V>, <S as ScopeParent>::Timestamp, Diff>>;
+/// An arrangement of tuples by the values at some keys, in a scope.
+pub type KeyedArrangement<S, V> = Arranged<S, TraceValHandle<Vec<V>, Vec<V>, <S as ScopeParent>::Timestamp, Diff>>;
+
+/// Maintained collections known to all workers, which inform how plans are rendered.
+///
+/// As each worker renders the same dataflow, the answers must agree across workers; they are
+/// derived from the sequence of commands rather than from the contents of a worker's traces.
+pub trait Inventory<V: Datum> {
+    /// Indicates if an arrangement of `plan` by the values at `keys` is available, without using it.
+    fn has_keyed(&self, plan: &Plan<V>, keys: &[usize]) -> bool;
+    /// Statistics of the collection of `plan`, if they are known.
+    fn statistics(&self, plan: &Plan<V>) -> Option<Statistics>;
+}
+
+/// Arrangements of plans available to rendering in a scope.
+///
+/// Rendering a query's dataflow uses the arrangements maintained by the `TraceManager`, and
+/// records those it builds there for other queries to share. Rendering in a scope nested in
+/// the dataflow, as for mutually recursive rules, uses arrangements private to that scope.
+pub trait Arrangements<S: Scope, V: ExchangeData+Datum> : Inventory<V>
+where
+    S::Timestamp: Lattice+Ord,
+{
+    /// An arrangement of `plan` by its tuples, if one is available.
+    fn unkeyed(&mut self, plan: &Plan<V>, scope: &mut S) -> Option<UnkeyedArrangement<S, V>>;
+    /// Records an arrangement of `plan` by its tuples.
+    fn add_unkeyed(&mut self, plan: &Plan<V>, arrangement: &UnkeyedArrangement<S, V>);
+    /// An arrangement of `plan` by the values at `keys`, if one is available.
+    fn keyed(&mut self, plan: &Plan<V>, keys: &[usize], scope: &mut S) -> Option<KeyedArrangement<S, V>>;
+    /// Records an arrangement of `plan` by the values at `keys`.
+    fn add_keyed(&mut self, plan: &Plan<V>, keys: &[usize], arrangement: &KeyedArrangement<S, V>);
+    /// Indicates whether the times of the scope are totally ordered.
+    ///
+    /// Delta queries join each change with other relations as of its time, which only
+    /// accounts for all pairs of updates when times are totally ordered.
+    fn totally_ordered(&self) -> bool;
+}
+
+impl<S, V> Arrangements<S, V> for TraceManager<V>
+where
+    S: Scope<Timestamp = Time>,
+    V: ExchangeData+Hash+Datum,
+{
+    fn unkeyed(&mut self, plan: &Plan<V>, scope: &mut S) -> Option<UnkeyedArrangement<S, V>> {
+        self.get_unkeyed(plan).map(|mut trace| self.import(&mut trace, scope))
+    }
+    fn add_unkeyed(&mut self, plan: &Plan<V>, arrangement: &UnkeyedArrangement<S, V>) {
+        self.set_unkeyed(plan, &arrangement.trace);
+    }
+    fn keyed(&mut self, plan: &Plan<V>, keys: &[usize], scope: &mut S) -> Option<KeyedArrangement<S, V>> {
+        self.get_keyed(plan, keys).map(|mut trace| self.import(&mut trace, scope))
+    }
+    fn add_keyed(&mut self, plan: &Plan<V>, keys: &[usize], arrangement: &KeyedArrangement<S, V>) {
+        self.set_keyed(plan, keys, &arrangement.trace);
+    }
+    fn totally_ordered(&self) -> bool { true }
+}
+
+impl<V: ExchangeData+Hash+Datum> Inventory<V> for TraceManager<V> {
+    fn has_keyed(&self, plan: &Plan<V>, keys: &[usize]) -> bool {
+        self.is_keyed(plan, keys)
+    }
+    fn statistics(&self, plan: &Plan<V>) -> Option<Statistics> {
+        TraceManager::statistics(self, plan)
+    }
+}
+
+/// An arrangement of `plan` by the values at `keys`, with the other values as values.
+///
+/// The arrangement is found in `arrangements` if available, and otherwise rendered and
+/// recorded there for other uses.
+pub(crate) fn arrange_keyed<S, V, A>(
+    plan: &Plan<V>,
+    keys: &[usize],
+    scope: &mut S,
+    collections: &mut std::collections::HashMap<Plan<V>, Collection<S, Vec<V>, Diff>>,
+    arrangements: &mut A,
+) -> KeyedArrangement<S, V>
+where
+    S: Scope,
+    S::Timestamp: Lattice+Ord,
+    V: ExchangeData+Hash+Datum,
+    A: Arrangements<S, V>,
+{
+    use differential_dataflow::operators::arrange::ArrangeByKey;
+
+    if let Some(arrangement) = arrangements.keyed(plan, keys, scope) {
+        arrangement
+    }
+    else {
+        let keys_clone = keys.to_vec();
+        let arrangement =
+        plan.render(scope, collections, arrangements)
+            .map(move |tuple|
+                (
+                    // TODO: Re-use `tuple` for values.
+                    keys_clone.iter().map(|index| tuple[*index].clone()).collect::<Vec<_>>(),
+                    tuple
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(index,_value)| !keys_clone.contains(index))
+                        .map(|(_index,value)| value)
+                        .collect::<Vec<_>>(),
+                )
+            )
+            .arrange_by_key();
+
+        arrangements.add_keyed(plan, keys, &arrangement);
+        arrangement
+    }
+}
+
 /// A type that can be rendered as a collection.
 pub trait Render : Sized {
 
@@ -31,12 +150,16 @@ pub trait Render : Sized {
     ///
     /// This method has access to arranged data, and may rely on and update the set
     /// of arrangements based on the needs and offerings of the rendering process.
-    fn render<S: Scope<Timestamp = Time>>(
+    fn render<S, A>(
         &self,
         scope: &mut S,
         collections: &mut std::collections::HashMap<Plan<Self::Value>, Collection<S, Vec<Self::Value>, Diff>>,
-        arrangements: &mut TraceManager<Self::Value>,
-    ) -> Collection<S, Vec<Self::Value>, Diff>;
+        arrangements: &mut A,
+    ) -> Collection<S, Vec<Self::Value>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, Self::Value>;
 }
 
 /// Possible query plan types.
@@ -46,6 +169,8 @@ pub enum Plan<V: Datum> {
     Map(Map<V>),
     /// Distinct
     Distinct(Box<Plan<V>>),
+    /// Grouped aggregation
+    Aggregate(Aggregate<V>),
     /// Concat
     Concat(Vec<Plan<V>>),
     /// Consolidate
@@ -72,10 +197,25 @@ impl<V: ExchangeData+Hash+Datum> Plan<V> {
             plan: Box::new(self),
         })
     }
+    /// Produces the values of expressions applied to each tuple.
+    pub fn map(self, expressions: Vec<V::Expression>) -> Self {
+        Plan::Map(Map {
+            expressions,
+            plan: Box::new(self),
+        })
+    }
     /// Reduces a collection to distinct tuples.
     pub fn distinct(self) -> Self {
         Plan::Distinct(Box::new(self))
     }
+    /// Groups tuples by the values at `keys`, and produces the keys followed by `aggregates`.
+    pub fn aggregate(self, keys: Vec<usize>, aggregates: Vec<Aggregation>) -> Self {
+        Plan::Aggregate(Aggregate {
+            keys,
+            aggregates,
+            plan: Box::new(self),
+        })
+    }
     /// Merges two collections.
     pub fn concat(self, other: Self) -> Self {
         Plan::Concat(vec![self, other])
@@ -128,6 +268,29 @@ impl<V: ExchangeData+Hash+Datum> Plan<V> {
     pub fn inspect(self, text: &str) -> Self {
         Plan::Inspect(text.to_string(), Box::new(self))
     }
+    /// The plans whose collections this plan directly uses.
+    pub fn inputs(&self) -> Vec<&Plan<V>> {
+        match self {
+            Plan::Map(map) => vec![&*map.plan],
+            Plan::Distinct(plan) => vec![&**plan],
+            Plan::Aggregate(aggregate) => vec![&*aggregate.plan],
+            Plan::Concat(plans) => plans.iter().collect(),
+            Plan::Consolidate(plan) => vec![&**plan],
+            Plan::Join(join) => vec![&*join.plan1, &*join.plan2],
+            Plan::MultiwayJoin(join) => join.sources.iter().collect(),
+            Plan::Negate(plan) => vec![&**plan],
+            Plan::Filter(filter) => vec![&*filter.plan],
+            Plan::Source(_) => vec![],
+            Plan::Inspect(_, plan) => vec![&**plan],
+        }
+    }
+    /// Indicates if the plan sources any of the named collections.
+    pub fn references(&self, names: &[String]) -> bool {
+        match self {
+            Plan::Source(name) => names.contains(name),
+            _ => self.inputs().into_iter().any(|plan| plan.references(names)),
+        }
+    }
     /// Convert the plan into a named rule.
     pub fn into_rule(self, name: &str) -> crate::Rule<V> {
         crate::Rule {
@@ -141,12 +304,16 @@ impl<V: ExchangeData+Hash+Datum> Render for Plan<V> {
 
     type Value = V;
 
-    fn render<S: Scope<Timestamp = Time>>(
+    fn render<S, A>(
         &self,
         scope: &mut S,
         collections: &mut std::collections::HashMap<Plan<Self::Value>, Collection<S, Vec<Self::Value>, Diff>>,
-        arrangements: &mut TraceManager<Self::Value>,
+        arrangements: &mut A,
     ) -> Collection<S, Vec<Self::Value>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, Self::Value>,
     {
         if collections.get(self).is_none() {
 
@@ -161,21 +328,22 @@ impl<V: ExchangeData+Hash+Datum> Render for Plan<V> {
                     use differential_dataflow::trace::implementations::ord::OrdKeySpine;
 
                     let input =
-                    if let Some(mut trace) = arrangements.get_unkeyed(&self) {
-                        trace.import(scope)
+                    if let Some(arrangement) = arrangements.unkeyed(&distinct, scope) {
+                        arrangement
                     }
                     else {
                         let input_arrangement = distinct.render(scope, collections, arrangements).arrange_by_self();
-                        arrangements.set_unkeyed(&distinct, &input_arrangement.trace);
+                        arrangements.add_unkeyed(&distinct, &input_arrangement);
                         input_arrangement
                     };
 
                     let output = input.reduce_abelian::<_,OrdKeySpine<_,_,_>>(move |_,_,t| t.push(((), 1)));
 
-                    arrangements.set_unkeyed(&self, &output.trace);
+                    arrangements.add_unkeyed(&self, &output);
                     output.as_collection(|k,&()| k.clone())
 
                 },
+                Plan::Aggregate(aggregate) => aggregate.render(scope, collections, arrangements),
                 Plan::Concat(concat) => {
 
                     use timely::dataflow::operators::Concatenate;
@@ -192,8 +360,8 @@ impl<V: ExchangeData+Hash+Datum> Render for Plan<V> {
                         .as_collection()
                 }
                 Plan::Consolidate(consolidate) => {
-                    if let Some(mut trace) = arrangements.get_unkeyed(&self) {
-                        trace.import(scope).as_collection(|k,&()| k.clone())
+                    if let Some(arrangement) = arrangements.unkeyed(&self, scope) {
+                        arrangement.as_collection(|k,&()| k.clone())
                     }
                     else {
                         use differential_dataflow::operators::Consolidate;
@@ -208,9 +376,8 @@ impl<V: ExchangeData+Hash+Datum> Render for Plan<V> {
                 Plan::Filter(filter) => filter.render(scope, collections, arrangements),
                 Plan::Source(source) => {
                     arrangements
-                        .get_unkeyed(self)
+                        .unkeyed(self, scope)
                         .expect(&format!("Failed to find source collection: {:?}", source))
-                        .import(scope)
                         .as_collection(|k,()| k.to_vec())
                 },
                 Plan::Inspect(text, plan) => {
@@ -23,6 +23,15 @@
 //! A further implementation could develop the results attribute-by-attribute, as
 //! opposed to collection-by-collection, which gives us the ability to use column
 //! indices rather than whole-collection indices.
+//!
+//! We render delta queries, and choose the join order for each input collection
+//! using state that all workers share, so that they build the same dataflows: the
+//! next collection joined is preferably one with an existing arrangement by the
+//! keys it would be joined on, and otherwise the smallest, by the statistics the
+//! `TraceManager` keeps of inputs. Delta queries require totally
+//! ordered times, and in other scopes, such as those of recursive rules, we render
+//! the naive sequence of binary joins, in the same order and with the same shared
+//! arrangements.
 
 use std::hash::Hash;
 
@@ -32,8 +41,9 @@ use differential_dataflow::operators::Consolidate;
 use differential_dataflow::operators::arrange::{ArrangeBySelf, ArrangeByKey};
 
 use differential_dataflow::{Collection, ExchangeData};
-use plan::{Plan, Render};
-use {TraceManager, Time, Diff, Datum};
+use differential_dataflow::lattice::Lattice;
+use plan::{Plan, Render, Arrangements, Inventory, arrange_keyed};
+use {Diff, Datum};
 
 /// A multiway join of muliple relations.
 ///
@@ -67,12 +77,16 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
 
     type Value = V;
 
-    fn render<S: Scope<Timestamp = Time>>(
+    fn render<S, A>(
         &self,
         scope: &mut S,
         collections: &mut std::collections::HashMap<Plan<Self::Value>, Collection<S, Vec<Self::Value>, Diff>>,
-        arrangements: &mut TraceManager<Self::Value>,
+        arrangements: &mut A,
     ) -> Collection<S, Vec<Self::Value>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, Self::Value>,
     {
         // The idea here is the following:
         //
@@ -98,23 +112,47 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
         //
         // This is done to avoid double counting updates; any concurrent changes will be
         // accounted for by the last relation for which there is a concurrent update.
+        //
+        // Joining each change with relations as of its time misses pairs of updates at
+        // incomparable times, and so delta queries require totally ordered times. In
+        // other scopes we join the relations with a sequence of binary joins.
 
         // println!("{:?}", self);
 
         // Attributes we may need from any and all relations.
-        let mut relevant_attributes = Vec::new();
-        relevant_attributes.extend(self.results.iter().cloned());
-        relevant_attributes.extend(self.equalities.iter().flat_map(|list| list.iter().cloned()));
-        relevant_attributes.sort();
-        relevant_attributes.dedup();
+        let relevant_attributes = self.relevant_attributes();
 
         // println!("Relevant attributes: {:?}", relevant_attributes);
 
+        if !arrangements.totally_ordered() {
+            return self.render_binary(scope, collections, arrangements, &relevant_attributes[..]);
+        }
+
+        // Plan join orders for each delta query before building any arrangements,
+        // so that only maintained arrangements inform them.
+        let join_orders = self.delta_orders(&*arrangements);
+
+        // Ensure the source plans are rendered and arranged.
+        let sources =
+        self.sources
+            .iter()
+            .map(|plan| {
+                if let Some(arrangement) = arrangements.unkeyed(plan, scope) {
+                    arrangement
+                }
+                else {
+                    let arrangement = plan.render(scope, collections, arrangements).arrange_by_self();
+                    arrangements.add_unkeyed(plan, &arrangement);
+                    arrangement
+                }
+            })
+            .collect::<Vec<_>>();
+
         // Into which we accumulate change streams.
         let mut accumulated_changes = Vec::new();
 
         // For each participating relation, we build a delta query dataflow.
-        for (index, plan) in self.sources.iter().enumerate() {
+        for (index, source) in sources.iter().enumerate() {
 
             // println!("building dataflow for relation {}", index);
 
@@ -129,20 +167,8 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
             let attributes_init = attributes.clone();
             // println!("\tinitial attributes: {:?}", attributes);
 
-            // Ensure the plan is rendered and cached.
-            if arrangements.get_unkeyed(&plan).is_none() {
-                // println!("\tbuilding/caching source plan");
-                let collection = plan.render(scope, collections, arrangements);
-                arrangements.set_unkeyed(plan, &collection.arrange_by_self().trace);
-            }
-            else {
-                // println!("\tsource plan found");
-            }
             let changes =
-            arrangements
-                .get_unkeyed(&plan)
-                .expect("Surely we just ensured this")
-                .import(scope)
+            source
                 .as_collection(|val,&()| val.clone())
                 .map(move |tuple| attributes_init.iter().map(|&(attr,_)|
                     tuple[attr].clone()).collect::<Vec<_>>()
@@ -157,7 +183,7 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
             // This is a sequence of relation identifiers, starting with `index`,
             // such that each has at least one attribute in common with a prior
             // relation, and so can be effectively joined.
-            let join_order = plan_join_order(index, &self.equalities);
+            let join_order = join_orders[index].clone();
             let mut join_plan = Vec::new();
 
             // println!("\tjoin order: {:?}", join_order);
@@ -169,54 +195,8 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
                 // attributes in common with prior relations. Any other values
                 // should be appended to tuples in `changes` with care taken to
                 // update `attributes`.
-                let (keys, priors) = determine_keys_priors(join_idx, &self.equalities, &attributes[..]);
-
-                // The fields in `sources[join_idx]` that should be values are those
-                // that are required output or participate in an equality constraint,
-                // but *WHICH ARE NOT* in `keys`.
-                let vals =
-                relevant_attributes
-                    .iter()
-                    .filter(|&(attr,index)| index == &join_idx && !keys.contains(&attr))
-                    .cloned()
-                    .collect::<Vec<_>>();
-
-                // println!("\tkeys: {:?}, priors: {:?}, vals: {:?}", keys, priors, vals);
-
-                let mut projection = Vec::new();
-                for &attr in keys.iter() {
-                    projection.push(attr);
-                }
-                for &(attr, _index) in vals.iter() {
-                    projection.push(attr);
-                }
-                // TODO: Sort, to improve chances of re-use opportunities.
-                //       Requires understanding how attributes move to get the right
-                //       key selectors out though.
-                // projection.sort();
-                // projection.dedup(); // Should already be deduplicated, probably?
-
-                // Get a plan for the projection on to these few attributes.
-                let plan = self.sources[join_idx].clone().project(projection);
-
-                if arrangements.get_keyed(&plan, &keys[..]).is_none() {
-                    // println!("\tbuilding key: {:?}, plan: {:?}", keys, plan);
-                    let keys_clone = keys.clone();
-                    let arrangement =
-                    plan.render(scope, collections, arrangements)
-                        .map(move |tuple| (keys_clone.iter().map(|&i| tuple[i].clone()).collect::<Vec<_>>(), tuple))
-                        .arrange_by_key();
-
-                    arrangements.set_keyed(&plan, &keys[..], &arrangement.trace);
-                }
-                else {
-                    // println!("\tplan found: {:?}, {:?}", keys, plan);
-                }
-
-                let arrangement =
-                arrangements
-                    .get_keyed(&plan, &keys[..])
-                    .expect("Surely we just ensured this");
+                let (keys, priors, vals, plan) = self.extension(join_idx, &relevant_attributes[..], &attributes[..]);
+                let arrangement = arrange_keyed(&plan, &keys[..], scope, collections, arrangements);
 
                 let key_selector = std::rc::Rc::new(move |change: &Vec<V>|
                     priors.iter().map(|&p| change[p].clone()).collect::<Vec<_>>()
@@ -224,13 +204,12 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
 
                 join_plan.push((join_idx, key_selector, arrangement));
 
-                attributes.extend(keys.into_iter().map(|x| (x, join_idx)));
                 attributes.extend(vals.into_iter());
                 // println!("\tattributes: {:?}", attributes);
             }
 
             // Build the dataflow.
-            use dogsdogsdogs::altneu::AltNeu;
+            use differential_dataflow::lattice::AltNeu;
 
             let scope_name = format!("DeltaRule: {}/{}", index, self.sources.len());
             let changes = scope.clone().scoped::<AltNeu<_>,_,_>(&scope_name, |inner| {
@@ -241,7 +220,7 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
                     .enter(inner)
                     ;
 
-                for (join_idx, key_selector, mut trace) in join_plan.into_iter() {
+                for (join_idx, key_selector, arrangement) in join_plan.into_iter() {
 
                     // Use alt or neu timestamps based on relative indices.
                     // Must have an `if` statement here as the two arrangement have different
@@ -249,11 +228,11 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
                     // tuple in the cursor.
                     changes =
                     if join_idx < index {
-                        let arrangement = trace.import(scope).enter_at(inner, |_,_,t| AltNeu::alt(t.clone()));
+                        let arrangement = arrangement.enter_at(inner, |_,_,t| AltNeu::alt(t.clone()));
                         dogsdogsdogs::operators::propose(&changes, arrangement, key_selector)
                     }
                     else {
-                        let arrangement = trace.import(scope).enter_at(inner, |_,_,t| AltNeu::neu(t.clone()));
+                        let arrangement = arrangement.enter_at(inner, |_,_,t| AltNeu::neu(t.clone()));
                         dogsdogsdogs::operators::propose(&changes, arrangement, key_selector)
                     }
                     .map(|(mut prefix, extensions)| { prefix.extend(extensions.into_iter()); prefix })
@@ -265,28 +244,7 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
                 }
 
                 // Extract `self.results` in order, using `attributes`.
-                //
-                // The specific attribute requested in `self.results` may not be present in
-                // `attributes` when it is equal to another present attribute. So, we should
-                // look around in `self.equalities` also.
-                let mut extract_map = Vec::new();
-                for result in self.results.iter() {
-                    if let Some(position) = attributes.iter().position(|i| i == result) {
-                        extract_map.push(position);
-                    }
-                    else {
-                        for constraint in self.equalities.iter() {
-                            if constraint.contains(result) {
-                                if let Some(position) = constraint.iter().flat_map(|x| attributes.iter().position(|i| i == x)).next() {
-                                    extract_map.push(position);
-                                }
-                                else {
-                                    println!("WTF NOTHING FOUND NOOOOO!!!");
-                                }
-                            }
-                        }
-                    }
-                }
+                let extract_map = extraction(&self.results, &self.equalities, &attributes[..]);
 
                 changes
                     .map(move |tuple| extract_map.iter().map(|&i| tuple[i].clone()).collect::<Vec<_>>())
@@ -301,11 +259,183 @@ impl<V: ExchangeData+Hash+Datum> Render for MultiwayJoin<V> {
     }
 }
 
+impl<V: ExchangeData+Hash+Datum> MultiwayJoin<V> {
+
+    /// Attributes required from any relation, either as results or by equality constraints.
+    fn relevant_attributes(&self) -> Vec<(usize, usize)> {
+        let mut relevant_attributes = Vec::new();
+        relevant_attributes.extend(self.results.iter().cloned());
+        relevant_attributes.extend(self.equalities.iter().flat_map(|list| list.iter().cloned()));
+        relevant_attributes.sort();
+        relevant_attributes.dedup();
+        relevant_attributes
+    }
+
+    /// Renders the join as a sequence of binary joins, starting from the first relation.
+    ///
+    /// Each binary join arranges the accumulated tuples, and uses an arrangement of the next
+    /// relation by its keys, shared through `arrangements` as for delta queries.
+    fn render_binary<S, A>(
+        &self,
+        scope: &mut S,
+        collections: &mut std::collections::HashMap<Plan<V>, Collection<S, Vec<V>, Diff>>,
+        arrangements: &mut A,
+        relevant_attributes: &[(usize, usize)],
+    ) -> Collection<S, Vec<V>, Diff>
+    where
+        S: Scope,
+        S::Timestamp: Lattice+Ord,
+        A: Arrangements<S, V>,
+    {
+        use differential_dataflow::operators::JoinCore;
+
+        let mut attributes =
+        relevant_attributes
+            .iter()
+            .filter(|(_attr, input)| input == &0)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let projection = attributes.iter().map(|&(attr, _)| attr).collect::<Vec<_>>();
+        let mut changes =
+        self.sources[0]
+            .render(scope, collections, arrangements)
+            .map(move |tuple| projection.iter().map(|&attr| tuple[attr].clone()).collect::<Vec<_>>());
+
+        for join_idx in self.plan_delta_order(0, relevant_attributes, &*arrangements).into_iter().skip(1) {
+
+            let (keys, priors, vals, plan) = self.extension(join_idx, relevant_attributes, &attributes[..]);
+            let arrangement = arrange_keyed(&plan, &keys[..], scope, collections, arrangements);
+
+            changes =
+            changes
+                .map(move |prefix| (priors.iter().map(|&prior| prefix[prior].clone()).collect::<Vec<_>>(), prefix))
+                .arrange_by_key()
+                .join_core(&arrangement, |_keys, prefix, extension| {
+                    Some(prefix.iter().cloned().chain(extension.iter().cloned()).collect::<Vec<_>>())
+                });
+
+            attributes.extend(vals.into_iter());
+        }
+
+        let extract_map = extraction(&self.results, &self.equalities, &attributes[..]);
+        changes.map(move |tuple| extract_map.iter().map(|&i| tuple[i].clone()).collect::<Vec<_>>())
+    }
+
+    /// Determines how to join `sources[join_idx]` to tuples of `attributes`.
+    ///
+    /// Returns the positions of keys in the plan for the relation, the positions in `attributes`
+    /// of their values, the other relevant attributes of the relation, and the plan for the
+    /// relation projected on to its keys and then those other attributes. Arrangements of the
+    /// plan by its keys have the other attributes as values, which extend joined tuples.
+    fn extension(
+        &self,
+        join_idx: usize,
+        relevant_attributes: &[(usize, usize)],
+        attributes: &[(usize, usize)],
+    )
+    -> (Vec<usize>, Vec<usize>, Vec<(usize, usize)>, Plan<V>)
+    {
+        let (keys, priors) = determine_keys_priors(join_idx, &self.equalities, attributes);
+
+        // The fields in `sources[join_idx]` that should be values are those
+        // that are required output or participate in an equality constraint,
+        // but *WHICH ARE NOT* in `keys`.
+        let vals =
+        relevant_attributes
+            .iter()
+            .filter(|&(attr,index)| index == &join_idx && !keys.contains(&attr))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut projection = Vec::new();
+        for &attr in keys.iter() {
+            projection.push(attr);
+        }
+        for &(attr, _index) in vals.iter() {
+            projection.push(attr);
+        }
+        // TODO: Sort, to improve chances of re-use opportunities.
+        //       Requires understanding how attributes move to get the right
+        //       key selectors out though.
+        // projection.sort();
+        // projection.dedup(); // Should already be deduplicated, probably?
+
+        // Get a plan for the projection on to these few attributes.
+        let plan = self.sources[join_idx].clone().project(projection);
+
+        ((0 .. keys.len()).collect(), priors, vals, plan)
+    }
+
+    /// The order in which the delta query of each relation joins the other relations.
+    ///
+    /// These are the orders rendering chooses, given the arrangements and statistics of `inventory`.
+    pub fn delta_orders<I: Inventory<V>>(&self, inventory: &I) -> Vec<Vec<usize>> {
+        let relevant_attributes = self.relevant_attributes();
+        (0 .. self.sources.len())
+            .map(|index| self.plan_delta_order(index, &relevant_attributes[..], inventory))
+            .collect()
+    }
+
+    /// Sequences relations for the delta query of `source`, using maintained arrangements and statistics.
+    ///
+    /// As in `plan_join_order`, relations become available for sequencing once they share
+    /// a constraint with a sequenced relation. Of the available relations, those with an
+    /// existing arrangement by the keys they would be joined on come first, then those of
+    /// known size, and then the others; within each, smaller relations come first, and ties
+    /// are broken by the listed order of the relations.
+    pub(crate) fn plan_delta_order<I: Inventory<V>>(
+        &self,
+        source: usize,
+        relevant_attributes: &[(usize, usize)],
+        inventory: &I,
+    )
+    -> Vec<usize>
+    {
+        let mut result = vec![source];
+        let mut attributes =
+        relevant_attributes
+            .iter()
+            .filter(|(_attr, input)| input == &source)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        loop {
+
+            let mut best: Option<((bool, bool, Diff), usize, Vec<usize>, Vec<(usize, usize)>)> = None;
+            for candidate in 0 .. self.sources.len() {
+                let available =
+                !result.contains(&candidate) &&
+                self.equalities.iter().any(|constraint|
+                    constraint.iter().any(|(_,index)| index == &candidate) &&
+                    constraint.iter().any(|(_,index)| result.contains(index))
+                );
+                if available {
+                    let (keys, _priors, vals, plan) = self.extension(candidate, relevant_attributes, &attributes[..]);
+                    let records = inventory.statistics(&self.sources[candidate]).map(|statistics| statistics.records);
+                    let cost = (!inventory.has_keyed(&plan, &keys[..]), records.is_none(), records.unwrap_or(0));
+                    if best.as_ref().map(|(best_cost, _, _, _)| &cost < best_cost).unwrap_or(true) {
+                        best = Some((cost, candidate, keys, vals));
+                    }
+                }
+            }
+
+            match best {
+                Some((_cost, candidate, _keys, vals)) => {
+                    result.push(candidate);
+                    attributes.extend(vals.into_iter());
+                },
+                None => { return result; },
+            }
+        }
+    }
+}
+
 /// Sequences relations in `constraints`.
 ///
 /// Relations become available for sequencing as soon as they share a constraint with
 /// either `source` or another sequenced relation.
-fn plan_join_order(source: usize, constraints: &[Vec<(usize, usize)>]) -> Vec<usize> {
+pub(crate) fn plan_join_order(source: usize, constraints: &[Vec<(usize, usize)>]) -> Vec<usize> {
 
     let mut result = vec![source];
     let mut active = true;
@@ -331,7 +461,7 @@ fn plan_join_order(source: usize, constraints: &[Vec<(usize, usize)>]) -> Vec<us
 /// Identifies keys and values for a join.
 ///
 /// The result is a sequence, for each
-fn determine_keys_priors(
+pub(crate) fn determine_keys_priors(
     relation: usize,
     constraints: &[Vec<(usize, usize)>],
     current_attributes: &[(usize, usize)],
@@ -361,3 +491,36 @@ fn determine_keys_priors(
 
     (keys, priors)
 }
+
+/// Positions in `attributes` of each of `results`.
+///
+/// The specific attribute requested in `results` may not be present in `attributes`
+/// when it is equal to another present attribute. So, we should look around in
+/// `equalities` also.
+pub(crate) fn extraction(
+    results: &[(usize, usize)],
+    equalities: &[Vec<(usize, usize)>],
+    attributes: &[(usize, usize)],
+)
+-> Vec<usize>
+{
+    let mut extract_map = Vec::new();
+    for result in results.iter() {
+        if let Some(position) = attributes.iter().position(|i| i == result) {
+            extract_map.push(position);
+        }
+        else {
+            for constraint in equalities.iter() {
+                if constraint.contains(result) {
+                    if let Some(position) = constraint.iter().flat_map(|x| attributes.iter().position(|i| i == x)).next() {
+                        extract_map.push(position);
+                    }
+                    else {
+                        println!("WTF NOTHING FOUND NOOOOO!!!");
+                    }
+                }
+            }
+        }
+    }
+    extract_map
+}
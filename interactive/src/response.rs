@@ -0,0 +1,94 @@
+//! Responses sent back to clients.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use {Time, Diff};
+
+/// Responses sent back to clients.
+///
+/// Responses are framed on the client connection as bincode-serialized values, as are commands.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub enum Response<V> {
+    /// A command was executed.
+    ///
+    /// Each command from a client is answered by either an acknowledgement or an error, in
+    /// the order the commands were issued. Updates and snapshots follow the acknowledgement.
+    Ack,
+    /// Consolidated changes to a subscribed rule, at times through which the rule is complete.
+    Updates(String, Vec<(Vec<V>, Time, Diff)>),
+    /// The consolidated contents of a rule at a time.
+    Snapshot(String, Time, Vec<(Vec<V>, Diff)>),
+    /// A command could not be executed, for example because it failed to type check.
+    Error(String),
+}
+
+impl<V: Serialize+DeserializeOwned> Response<V> {
+    /// Serialize the response at a writer.
+    pub fn serialize_into<W: Write>(&self, writer: W) -> Result<(), bincode::Error> {
+        bincode::serialize_into(writer, self)
+    }
+    /// Deserialize a response from a reader.
+    pub fn deserialize_from<R: Read>(reader: R) -> Result<Self, bincode::Error> {
+        bincode::deserialize_from(reader)
+    }
+}
+
+/// Connections to clients, by client identifier.
+///
+/// The connections are shared by the workers of a process. Each client is connected to one
+/// process, and identified so that all workers agree on a worker of that process to respond.
+/// A process that accepts its `number`th client through worker `worker` of `peers` workers
+/// identifies it as `Clients::identifier(worker, peers, number)`.
+#[derive(Clone)]
+pub struct Clients {
+    connections: Arc<Mutex<HashMap<usize, Box<dyn Write+Send>>>>,
+}
+
+impl Clients {
+    /// The identifier of the `number`th client accepted through `worker` of `peers` workers.
+    pub fn identifier(worker: usize, peers: usize, number: usize) -> usize {
+        number * peers + worker
+    }
+    /// The index of the worker that responds to `client`, out of `peers` workers.
+    pub fn responder(client: usize, peers: usize) -> usize {
+        client % peers
+    }
+    /// Creates a new empty set of connections.
+    pub fn new() -> Self {
+        Clients { connections: Arc::new(Mutex::new(HashMap::new())) }
+    }
+    /// Registers a connection to a client.
+    pub fn insert<W: Write+Send+'static>(&self, client: usize, connection: W) {
+        self.connections
+            .lock()
+            .expect("lock poisoned")
+            .insert(client, Box::new(connection));
+    }
+    /// Forgets the connection to a client.
+    pub fn remove(&self, client: usize) {
+        self.connections
+            .lock()
+            .expect("lock poisoned")
+            .remove(&client);
+    }
+    /// Sends a response to a client, forgetting the client if the connection fails.
+    ///
+    /// Returns false if there is no connection to the client.
+    pub fn send<V: Serialize+DeserializeOwned>(&self, client: usize, response: &Response<V>) -> bool {
+        let mut connections = self.connections.lock().expect("lock poisoned");
+        let sent =
+        connections
+            .get_mut(&client)
+            .map(|connection| response.serialize_into(&mut *connection).and_then(|()| connection.flush().map_err(|e| e.into())).is_ok());
+
+        if sent == Some(false) {
+            connections.remove(&client);
+        }
+        sent == Some(true)
+    }
+}
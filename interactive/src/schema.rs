@@ -0,0 +1,269 @@
+//! Named and typed columns of inputs and rules.
+//!
+//! Inputs are created with a schema, against which their updates are checked. The schemas of
+//! rules are inferred from their plans when a query is submitted, which checks that each plan
+//! only refers to columns its inputs have, and that compared, joined, and aggregated columns
+//! have compatible types. Relations without a schema, for example logging sources, are not
+//! checked, and neither are plans that use them.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use differential_dataflow::ExchangeData;
+
+use plan::{Plan, Aggregation};
+use plan::sfw::plan_join_order;
+use {Datum, Query};
+
+/// Names and types of the columns of a relation.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Schema<V: Datum> {
+    /// The name and type of each column.
+    pub columns: Vec<(String, V::Type)>,
+}
+
+impl<V: Datum> Schema<V> {
+    /// Creates a schema from names and types of columns.
+    pub fn new<S: ToString>(columns: Vec<(S, V::Type)>) -> Self {
+        Schema {
+            columns: columns.into_iter().map(|(name, typ)| (name.to_string(), typ)).collect(),
+        }
+    }
+    /// The number of columns.
+    pub fn arity(&self) -> usize {
+        self.columns.len()
+    }
+    /// The types of the columns.
+    pub fn types(&self) -> Vec<V::Type> {
+        self.columns.iter().map(|(_name, typ)| typ.clone()).collect()
+    }
+    /// Checks that a tuple has a value of the corresponding type for each column.
+    pub fn check(&self, tuple: &[V]) -> Result<(), String> {
+        if tuple.len() != self.arity() {
+            return Err(format!("expected {} values, found {} in {:?}", self.arity(), tuple.len(), tuple));
+        }
+        for (value, (name, typ)) in tuple.iter().zip(self.columns.iter()) {
+            if !value.has_type(typ) {
+                return Err(format!("expected {:?} for column {}, found {:?}", typ, name, value));
+            }
+        }
+        Ok(())
+    }
+    /// The name and type of a column, or an error if there is no such column.
+    fn column(&self, index: usize) -> Result<&(String, V::Type), String> {
+        self.columns
+            .get(index)
+            .ok_or_else(|| format!("column {} out of range for {} columns", index, self.arity()))
+    }
+}
+
+impl<V: ExchangeData+Hash+Datum> Plan<V> {
+    /// Infers the schema of the plan from the schemas of the relations it sources.
+    ///
+    /// The `sources` function produces the schema of a named relation, `None` if the relation
+    /// exists but has no schema, or an error if it does not exist. The result is `None` if the
+    /// schema of the plan depends on relations without schemas, and an error if the plan would
+    /// refer to missing columns or combine values of incompatible types.
+    pub fn schema<F>(&self, sources: &F) -> Result<Option<Schema<V>>, String>
+    where
+        F: Fn(&str) -> Result<Option<Schema<V>>, String>,
+    {
+        match self {
+            Plan::Map(map) => {
+                match map.plan.schema(sources)? {
+                    Some(input) => {
+                        let types = input.types();
+                        let mut columns = Vec::new();
+                        for (index, expr) in map.expressions.iter().enumerate() {
+                            let typ = V::type_of(&types[..], expr)?;
+                            // Projected columns retain their names.
+                            let name =
+                            (0 .. input.arity())
+                                .find(|&column| &V::projection(column) == expr)
+                                .map(|column| input.columns[column].0.clone())
+                                .unwrap_or_else(|| format!("column{}", index));
+                            columns.push((name, typ));
+                        }
+                        Ok(Some(Schema { columns }))
+                    },
+                    None => Ok(None),
+                }
+            },
+            Plan::Distinct(plan) => plan.schema(sources),
+            Plan::Aggregate(aggregate) => {
+                match aggregate.plan.schema(sources)? {
+                    Some(input) => {
+                        let mut columns = Vec::new();
+                        for key in aggregate.keys.iter() {
+                            columns.push(input.column(*key)?.clone());
+                        }
+                        for aggregation in aggregate.aggregates.iter() {
+                            let column =
+                            match aggregation {
+                                Aggregation::Count => ("count".to_string(), V::count_type()),
+                                Aggregation::Sum(index) => {
+                                    let (name, typ) = input.column(*index)?;
+                                    (format!("sum_{}", name), V::sum_type(typ)?)
+                                },
+                                Aggregation::Min(index) => {
+                                    let (name, typ) = input.column(*index)?;
+                                    (format!("min_{}", name), typ.clone())
+                                },
+                                Aggregation::Max(index) => {
+                                    let (name, typ) = input.column(*index)?;
+                                    (format!("max_{}", name), typ.clone())
+                                },
+                            };
+                            columns.push(column);
+                        }
+                        Ok(Some(Schema { columns }))
+                    },
+                    None => Ok(None),
+                }
+            },
+            Plan::Concat(plans) => {
+                // Inputs without schemas are assumed to agree with those with schemas.
+                let mut result: Option<Schema<V>> = None;
+                for plan in plans.iter() {
+                    if let Some(schema) = plan.schema(sources)? {
+                        match &result {
+                            Some(result) if result.types() != schema.types() => {
+                                return Err(format!("cannot concatenate {:?} and {:?}", result.types(), schema.types()));
+                            },
+                            Some(_) => { },
+                            None => { result = Some(schema); },
+                        }
+                    }
+                }
+                Ok(result)
+            },
+            Plan::Consolidate(plan) => plan.schema(sources),
+            Plan::Join(join) => {
+                let schema1 = join.plan1.schema(sources)?;
+                let schema2 = join.plan2.schema(sources)?;
+                let mut key_columns = Vec::new();
+                for (key1, key2) in join.keys.iter() {
+                    let column1 = schema1.as_ref().map(|schema| schema.column(*key1)).transpose()?;
+                    let column2 = schema2.as_ref().map(|schema| schema.column(*key2)).transpose()?;
+                    if let (Some((name1, typ1)), Some((name2, typ2))) = (column1, column2) {
+                        if typ1 != typ2 {
+                            return Err(format!("cannot join {} of type {:?} with {} of type {:?}", name1, typ1, name2, typ2));
+                        }
+                    }
+                    key_columns.push(column1.cloned());
+                }
+                match (schema1, schema2) {
+                    (Some(schema1), Some(schema2)) => {
+                        // Joined tuples are the keys, followed by the remaining values of each input.
+                        let keys1 = join.keys.iter().map(|key| key.0).collect::<Vec<_>>();
+                        let keys2 = join.keys.iter().map(|key| key.1).collect::<Vec<_>>();
+                        let columns =
+                        key_columns
+                            .into_iter()
+                            .map(|column| column.expect("schema present"))
+                            .chain(schema1.columns.into_iter().enumerate().filter(|(index, _)| !keys1.contains(index)).map(|(_, column)| column))
+                            .chain(schema2.columns.into_iter().enumerate().filter(|(index, _)| !keys2.contains(index)).map(|(_, column)| column))
+                            .collect();
+                        Ok(Some(Schema { columns }))
+                    },
+                    _ => Ok(None),
+                }
+            },
+            Plan::MultiwayJoin(join) => {
+                let schemas =
+                join.sources
+                    .iter()
+                    .map(|plan| plan.schema(sources))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let column = |(attr, input): (usize, usize)| -> Result<Option<(String, V::Type)>, String> {
+                    match schemas.get(input) {
+                        Some(Some(schema)) => schema.column(attr).map(|column| Some(column.clone())),
+                        Some(None) => Ok(None),
+                        None => Err(format!("source {} out of range for {} sources", input, schemas.len())),
+                    }
+                };
+
+                for class in join.equalities.iter() {
+                    let mut class_type = None;
+                    for attribute in class.iter() {
+                        if let Some((name, typ)) = column(*attribute)? {
+                            match &class_type {
+                                Some((name0, typ0)) if typ0 != &typ => {
+                                    return Err(format!("cannot equate {} of type {:?} with {} of type {:?}", name0, typ0, name, typ));
+                                },
+                                Some(_) => { },
+                                None => { class_type = Some((name, typ)); },
+                            }
+                        }
+                    }
+                }
+                if !join.sources.is_empty() && plan_join_order(0, &join.equalities).len() < join.sources.len() {
+                    return Err("multiway join sources are not connected by equalities".to_string());
+                }
+
+                let mut columns = Vec::new();
+                for attribute in join.results.iter() {
+                    columns.push(column(*attribute)?);
+                }
+                Ok(columns.into_iter().collect::<Option<Vec<_>>>().map(|columns| Schema { columns }))
+            },
+            Plan::Negate(plan) => plan.schema(sources),
+            Plan::Filter(filter) => {
+                let schema = filter.plan.schema(sources)?;
+                if let Some(schema) = &schema {
+                    filter.predicate.check(&schema.types()[..])?;
+                }
+                Ok(schema)
+            },
+            Plan::Source(name) => sources(name),
+            Plan::Inspect(_text, plan) => plan.schema(sources),
+        }
+    }
+}
+
+impl<V: ExchangeData+Hash+Datum> Query<V> {
+    /// Infers the schema of each rule of the query, as for `Plan::schema`.
+    ///
+    /// Rules may source each other as well as the relations known to `sources`. The schemas of
+    /// mutually recursive rules are inferred by repeatedly inferring the schema of each rule from
+    /// those of the previous round, starting from rules without schemas, until they agree.
+    pub fn schemas<F>(&self, sources: &F) -> Result<Vec<Option<Schema<V>>>, String>
+    where
+        F: Fn(&str) -> Result<Option<Schema<V>>, String>,
+    {
+        let mut schemas: HashMap<String, Option<Schema<V>>> = HashMap::new();
+        if self.is_recursive() {
+            for rule in self.rules.iter() {
+                schemas.insert(rule.name.clone(), None);
+            }
+        }
+
+        // Each round determines the schema of at least one more rule, or the schemas agree.
+        for _round in 0 .. self.rules.len() + 2 {
+
+            let mut changed = false;
+            for rule in self.rules.iter() {
+                let schema = {
+                    let lookup = |name: &str| {
+                        match schemas.get(name) {
+                            Some(schema) => Ok(schema.clone()),
+                            None => sources(name),
+                        }
+                    };
+                    rule.plan.schema(&lookup).map_err(|error| format!("rule {}: {}", rule.name, error))?
+                };
+                if schemas.get(&rule.name) != Some(&schema) {
+                    schemas.insert(rule.name.clone(), schema);
+                    changed = true;
+                }
+            }
+
+            if !changed || !self.is_recursive() {
+                return Ok(self.rules.iter().map(|rule| schemas[&rule.name].clone()).collect());
+            }
+        }
+
+        Err("schemas of recursive rules do not agree".to_string())
+    }
+}
@@ -0,0 +1,211 @@
+//! A server accepting commands from clients over TCP.
+//!
+//! The server is a timely computation, each of whose processes listens for clients at its own
+//! address. Commands received by any process are sequenced, so that all workers execute the same
+//! commands in the same order, and responses to a command are sent back by the process connected
+//! to the client that issued it.
+//!
+//! If the server has a command log, it replays the logged commands when it starts, and then
+//! logs each command it executes, so that it can be restarted without losing its inputs and
+//! rules. Each process keeps a copy of the log, but only the log of process 0 is replayed: its
+//! commands are sequenced to all workers before any commands from clients, and each process
+//! replaces its copy with them. Logs that disagree after a crash, or that were compacted by only
+//! some processes, are therefore made to agree again.
+//!
+//! The server accepts timely's arguments (e.g. `-w`, `-n`, `-p`, `-h`), and
+//!
+//! ```text
+//! --bind <address>    address to listen for clients at (default 127.0.0.1:<8000 + process>)
+//! --log <path>        file to log commands to, and for process 0 to replay commands from
+//! --compact           compact the command log before replaying it (process 0 only)
+//! ```
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use std::thread::Thread;
+use std::net::TcpListener;
+
+use timely::synchronization::Sequencer;
+
+use {Command, Manager};
+use response::Clients;
+use concrete::Value;
+use wal::{CommandLog, compact};
+
+/// Commands sequenced to all workers.
+#[derive(Clone, Serialize, Deserialize)]
+enum Sequenced {
+    /// The commands of the log of process 0, if it has one, to replay before commands from clients.
+    Replay(Option<Vec<Command<Value>>>),
+    /// A command issued by a client.
+    Client(usize, Command<Value>),
+}
+
+/// Runs a server process with command line arguments, returning once it has shut down.
+pub fn serve<I: Iterator<Item=String>>(args: I) -> Result<(), String> {
+
+    // Separate the bind address from the arguments for timely.
+    let mut address = None;
+    let mut log_path = None;
+    let mut compaction = false;
+    let mut process = 0;
+    let mut timely_args = Vec::new();
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        if arg == "--bind" {
+            address = Some(args.next().ok_or_else(|| "--bind requires an address".to_string())?);
+        }
+        else if arg == "--log" {
+            log_path = Some(args.next().ok_or_else(|| "--log requires a path".to_string())?);
+        }
+        else if arg == "--compact" {
+            compaction = true;
+        }
+        else if arg == "-p" || arg == "--process" {
+            let value = args.next().ok_or_else(|| format!("{} requires a process index", arg))?;
+            process = value.parse().map_err(|_| format!("invalid process index: {}", value))?;
+            timely_args.push(arg);
+            timely_args.push(value);
+        }
+        else {
+            timely_args.push(arg);
+        }
+    }
+    let address = address.unwrap_or_else(|| format!("127.0.0.1:{}", 8000 + process));
+
+    // Recover the logged commands of process 0, which all workers replay before accepting commands.
+    let replay =
+    match (&log_path, process) {
+        (Some(path), 0) => {
+            let (_log, mut commands) = CommandLog::<Value>::open(path).map_err(|error| format!("failed to open log {}: {}", path, error))?;
+            if compaction {
+                commands = compact(commands);
+            }
+            Some(commands)
+        },
+        _ => None,
+    };
+    let replay = Arc::new(Mutex::new(replay));
+    let has_log = log_path.is_some();
+    // The copy of the log is written by one worker of the process.
+    let log_path = Arc::new(Mutex::new(log_path.map(PathBuf::from)));
+
+    let listener = TcpListener::bind(&address).map_err(|error| format!("failed to bind {}: {}", address, error))?;
+
+    let (root_send, root_recv) = std::sync::mpsc::channel::<(Sender<(usize, Command<Value>)>, Thread, usize, usize)>();
+    let root_send = Arc::new(Mutex::new(root_send));
+
+    // Connections to clients, shared by all workers of the process.
+    let clients = Clients::new();
+    let listener_clients = clients.clone();
+
+    std::thread::Builder::new()
+        .name("Listener".to_string())
+        .spawn(move || {
+
+            // Commands are received by the first worker of the process to report in.
+            let (send, thread, worker, peers) = root_recv.recv().expect("Did not receive channel to worker");
+
+            for (number, stream) in listener.incoming().enumerate() {
+                let client = Clients::identifier(worker, peers, number);
+                let mut stream = stream.expect("listener error");
+                listener_clients.insert(client, stream.try_clone().expect("failed to clone stream"));
+                let clients = listener_clients.clone();
+                let send = send.clone();
+                let thread = thread.clone();
+                std::thread::Builder::new()
+                    .name("Client".to_string())
+                    .spawn(move || {
+                        while let Ok(command) = bincode::deserialize_from::<_,Command<Value>>(&mut stream) {
+                            send.send((client, command)).expect("command send failed");
+                            thread.unpark();
+                        }
+                        clients.remove(client);
+                        // All workers shut down the client's subscriptions.
+                        send.send((client, Command::Disconnect)).expect("command send failed");
+                        thread.unpark();
+                    })
+                    .expect("failed to create thread");
+            }
+        })
+        .map_err(|error| format!("failed to spawn listener: {}", error))?;
+
+    // Initiate timely computation.
+    let guards = timely::execute_from_args(timely_args.into_iter(), move |worker| {
+
+        // Send an endpoint and thread handle to root.
+        let (send, recv) = std::sync::mpsc::channel();
+        root_send
+            .lock()
+            .expect("lock poisoned")
+            .send((send, std::thread::current(), worker.index(), worker.peers()))
+            .expect("send failed");
+
+        let timer = ::std::time::Instant::now();
+
+        let mut manager = Manager::<Value>::new();
+        manager.clients = clients.clone();
+
+        let mut sequencer: Option<Sequencer<Sequenced>> = Some(Sequencer::new(worker, timer));
+        if worker.index() == 0 {
+            let replay = replay.lock().expect("lock poisoned").take();
+            sequencer.as_mut().map(|s| s.push(Sequenced::Replay(replay)));
+        }
+        // Commands from clients are sequenced only after the replayed commands.
+        let mut replayed = false;
+
+        while sequencer.is_some() {
+
+            // Check out channel status.
+            if replayed {
+                while let Ok((client, command)) = recv.try_recv() {
+                    sequencer
+                        .as_mut()
+                        .map(|s| s.push(Sequenced::Client(client, command)));
+                }
+            }
+
+            // Dequeue and act on commands.
+            // Once per iteration, so that Shutdown works "immediately".
+            match sequencer.as_mut().and_then(|s| s.next()) {
+                Some(Sequenced::Replay(commands)) => {
+                    if commands.is_none() && has_log {
+                        return Err("process 0 has no command log, which the logs of other processes copy".to_string());
+                    }
+                    let commands = commands.unwrap_or_default();
+                    // Replayed commands are issued by no client, and are not logged again.
+                    for command in commands.iter() {
+                        command.clone().execute(usize::max_value(), &mut manager, worker);
+                    }
+                    if let Some(path) = log_path.lock().expect("lock poisoned").take() {
+                        let log = CommandLog::create(&path, &commands[..]).map_err(|error| format!("failed to write log {}: {}", path.display(), error))?;
+                        manager.log = Some(log);
+                    }
+                    replayed = true;
+                    worker.step();
+                },
+                Some(Sequenced::Client(client, command)) => {
+                    if command == Command::Shutdown {
+                        sequencer = None;
+                    }
+                    command.execute(client, &mut manager, worker);
+                    worker.step();
+                },
+                None => {
+                    // Only consider parking if the sequencer is empty too.
+                    worker.step_or_park(None);
+                },
+            }
+        }
+
+        println!("Shutting down");
+        Ok(())
+    })?;
+
+    for result in guards.join() {
+        result??;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,790 @@
+//! A SQL front-end, compiling statements to commands.
+//!
+//! The supported subset of SQL consists of statements
+//!
+//! ```text
+//! CREATE TABLE name (column type, ...)
+//! CREATE VIEW name AS query
+//! query
+//! ```
+//!
+//! where queries are `SELECT [DISTINCT] ... FROM ... [WHERE ...] [GROUP BY ...]` blocks
+//! combined with `UNION` and `UNION ALL`. Relations in the `FROM` clause are listed with
+//! commas or `[INNER] JOIN ... ON ...`, and are compiled to a `MultiwayJoin` using the
+//! equalities between their columns found in `ON` and `WHERE` conditions. Other conditions
+//! are applied as a filter on the joined tuples. Scalar expressions support arithmetic,
+//! string operations, comparisons, boolean logic, `CAST`, and searched `CASE`, and aggregate
+//! functions `COUNT`, `SUM`, `MIN`, and `MAX` may be used with or without `GROUP BY`.
+//! Column types are `BOOL`, `INT`, `STRING`, and `DURATION`, as for `CAST`.
+//!
+//! Statements refer to relations and their columns by name. The column names of tables and
+//! views are recorded in a catalog as they are created, and removed when views are dropped;
+//! inputs created by other commands are not available to SQL statements. A query that is not named by `CREATE VIEW` is installed
+//! as a rule named by its text, whose results are printed by the server.
+
+use std::collections::HashMap;
+
+use plan::{Plan, Predicate, Aggregation};
+use plan::sfw::plan_join_order;
+use concrete::{Value, Expression, UnaryOp, BinaryOp, Type};
+use {Command, Datum, Rule, Schema};
+
+/// Column names of the relations known to SQL statements, by relation name.
+pub type Catalog = HashMap<String, Vec<String>>;
+
+/// Values for which SQL statements can be compiled to commands.
+pub trait SqlValue : Datum {
+    /// Compiles a statement into commands, recording the columns of created relations in `catalog`.
+    fn compile(statement: &str, catalog: &mut Catalog) -> Result<Vec<Command<Self>>, String>;
+}
+
+impl SqlValue for Value {
+    fn compile(statement: &str, catalog: &mut Catalog) -> Result<Vec<Command<Self>>, String> {
+        compile(statement, catalog)
+    }
+}
+
+/// Compiles a statement into commands, recording the columns of created relations in `catalog`.
+pub fn compile(statement: &str, catalog: &mut Catalog) -> Result<Vec<Command<Value>>, String> {
+
+    let mut parser = Parser::new(statement)?;
+    let parsed = parser.statement()?;
+    parser.finish()?;
+
+    match parsed {
+        Statement::CreateTable(name, columns) => {
+            for (index, (column, _typ)) in columns.iter().enumerate() {
+                if columns[.. index].iter().any(|(other, _typ)| other == column) {
+                    return Err(format!("duplicate column: {}", column));
+                }
+            }
+            catalog.insert(name.clone(), columns.iter().map(|(column, _typ)| column.clone()).collect());
+            Ok(vec![Command::CreateInput(name, Schema::new(columns), Vec::new())])
+        },
+        Statement::CreateView(name, query) => {
+            let (plan, columns) = compile_query(&query, catalog)?;
+            catalog.insert(name.clone(), columns);
+            Ok(vec![Command::Query(Rule { name, plan }.into_query())])
+        },
+        Statement::Query(query) => {
+            let (plan, _columns) = compile_query(&query, catalog)?;
+            Ok(vec![plan.inspect(statement).into_rule(statement).into()])
+        },
+    }
+}
+
+/// A parsed statement.
+#[derive(Clone, Debug, PartialEq)]
+enum Statement {
+    CreateTable(String, Vec<(String, Type)>),
+    CreateView(String, QueryExpr),
+    Query(QueryExpr),
+}
+
+/// A parsed query, producing a relation.
+#[derive(Clone, Debug, PartialEq)]
+enum QueryExpr {
+    Select(Box<Select>),
+    /// Two queries, and whether duplicates are retained (`UNION ALL`).
+    Union(Box<QueryExpr>, Box<QueryExpr>, bool),
+}
+
+/// A parsed `SELECT` block.
+#[derive(Clone, Debug, PartialEq)]
+struct Select {
+    distinct: bool,
+    items: Vec<SelectItem>,
+    /// Relation names and aliases.
+    from: Vec<(String, String)>,
+    /// Conditions from `ON` and `WHERE` clauses, all of which must hold.
+    conditions: Vec<Expr>,
+    group_by: Vec<Expr>,
+}
+
+/// A parsed item of a `SELECT` list.
+#[derive(Clone, Debug, PartialEq)]
+enum SelectItem {
+    Wildcard,
+    Expr(Expr, Option<String>),
+}
+
+/// A parsed aggregate function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Count,
+    Sum,
+    Min,
+    Max,
+}
+
+/// A parsed scalar expression, whose columns are referenced by name.
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Column(Option<String>, String),
+    Literal(Value),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Cast(Box<Expr>, Type),
+    Case(Vec<(Expr, Expr)>, Box<Expr>),
+    Aggregate(Function, Option<Box<Expr>>),
+}
+
+/// Compiles a query into a plan and the names of its columns.
+fn compile_query(query: &QueryExpr, catalog: &Catalog) -> Result<(Plan<Value>, Vec<String>), String> {
+    match query {
+        QueryExpr::Select(select) => compile_select(select, catalog),
+        QueryExpr::Union(query1, query2, all) => {
+            let (plan1, columns1) = compile_query(query1, catalog)?;
+            let (plan2, columns2) = compile_query(query2, catalog)?;
+            if columns1.len() != columns2.len() {
+                return Err(format!("UNION of queries with {} and {} columns", columns1.len(), columns2.len()));
+            }
+            let plan = plan1.concat(plan2);
+            Ok((if *all { plan } else { plan.distinct() }, columns1))
+        },
+    }
+}
+
+/// Compiles a `SELECT` block into a plan and the names of its columns.
+fn compile_select(select: &Select, catalog: &Catalog) -> Result<(Plan<Value>, Vec<String>), String> {
+
+    // The columns of the joined tuple, as (alias, column) pairs, and the offset of each source.
+    let mut columns = Vec::new();
+    let mut offsets = Vec::new();
+    let mut sources = Vec::new();
+    for (index, (name, alias)) in select.from.iter().enumerate() {
+        if select.from[.. index].iter().any(|(_, other)| other == alias) {
+            return Err(format!("duplicate relation alias: {}", alias));
+        }
+        let names = catalog.get(name).ok_or_else(|| format!("unknown relation: {}", name))?;
+        offsets.push(columns.len());
+        columns.extend(names.iter().map(|column| (alias.clone(), column.clone())));
+        sources.push(Plan::source(name));
+    }
+
+    let resolve = |qualifier: &Option<String>, name: &String| -> Result<usize, String> {
+        let mut positions =
+        columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (alias, column))| column == name && qualifier.as_ref().map(|q| q == alias).unwrap_or(true))
+            .map(|(position, _)| position);
+        match (positions.next(), positions.next()) {
+            (Some(position), None) => Ok(position),
+            (None, _) => Err(format!("unknown column: {}", display_column(qualifier, name))),
+            (Some(_), Some(_)) => Err(format!("ambiguous column: {}", display_column(qualifier, name))),
+        }
+    };
+    let source_of = |position: usize| offsets.iter().rposition(|offset| *offset <= position).expect("position before first source");
+
+    // Equalities between columns of different relations drive the join; other conditions filter.
+    let mut conjuncts = Vec::new();
+    for condition in select.conditions.iter() {
+        conjuncts_of(condition, &mut conjuncts);
+    }
+    let mut equalities: Vec<Vec<usize>> = Vec::new();
+    let mut residual = Vec::new();
+    for conjunct in conjuncts.into_iter() {
+        if let Expr::Binary(BinaryOp::Eq, expr1, expr2) = conjunct {
+            if let (Expr::Column(qualifier1, name1), Expr::Column(qualifier2, name2)) = (&**expr1, &**expr2) {
+                let position1 = resolve(qualifier1, name1)?;
+                let position2 = resolve(qualifier2, name2)?;
+                if source_of(position1) != source_of(position2) {
+                    // Merge the equivalence classes of the two positions.
+                    let mut class = vec![position1, position2];
+                    let mut index = 0;
+                    while index < equalities.len() {
+                        if equalities[index].contains(&position1) || equalities[index].contains(&position2) {
+                            class.extend(equalities.remove(index));
+                        }
+                        else {
+                            index += 1;
+                        }
+                    }
+                    class.sort();
+                    class.dedup();
+                    equalities.push(class);
+                    continue;
+                }
+            }
+        }
+        residual.push(conjunct);
+    }
+
+    let mut plan =
+    if sources.len() == 1 {
+        sources.pop().expect("one source")
+    }
+    else {
+        let equalities =
+        equalities
+            .into_iter()
+            .map(|class| class.into_iter().map(|position| (position - offsets[source_of(position)], source_of(position))).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        if plan_join_order(0, &equalities[..]).len() < sources.len() {
+            return Err("every relation must be joined to the others by an equality of columns".to_string());
+        }
+
+        let results = (0 .. columns.len()).map(|position| (position - offsets[source_of(position)], source_of(position))).collect();
+        Plan::multiway_join(sources, equalities, results)
+    };
+
+    // Scalar expressions refer to columns of the joined tuple, and may not contain aggregates.
+    let scalar = |expr: &Expr| -> Result<Option<Expression>, String> {
+        match expr {
+            Expr::Column(qualifier, name) => Ok(Some(Expression::Column(resolve(qualifier, name)?))),
+            Expr::Aggregate(..) => Err("aggregates are not allowed here".to_string()),
+            _ => Ok(None),
+        }
+    };
+
+    if let Some(condition) = residual.into_iter().cloned().fold(None, |all, conjunct| match all {
+        None => Some(conjunct),
+        Some(all) => Some(Expr::Binary(BinaryOp::And, Box::new(all), Box::new(conjunct))),
+    }) {
+        plan = plan.filter(Predicate::Expression(compile_expr(&condition, &scalar)?));
+    }
+
+    let mut aggregates = Vec::new();
+    for item in select.items.iter() {
+        if let SelectItem::Expr(expr, _) = item {
+            aggregates_of(expr, &mut aggregates)?;
+        }
+    }
+
+    let mut names = Vec::new();
+    let mut expressions = Vec::new();
+
+    if aggregates.is_empty() && select.group_by.is_empty() {
+        for item in select.items.iter() {
+            match item {
+                SelectItem::Wildcard => {
+                    for (position, (_alias, column)) in columns.iter().enumerate() {
+                        names.push(column.clone());
+                        expressions.push(Expression::Column(position));
+                    }
+                },
+                SelectItem::Expr(expr, alias) => {
+                    names.push(name_of(expr, alias, names.len()));
+                    expressions.push(compile_expr(expr, &scalar)?);
+                },
+            }
+        }
+    }
+    else {
+        // Group keys and aggregated values are computed first, then aggregated, and the
+        // select list is evaluated over the keys followed by the aggregates.
+        let mut prepared = Vec::new();
+        for key in select.group_by.iter() {
+            prepared.push(compile_expr(key, &scalar)?);
+        }
+        let keys = (0 .. prepared.len()).collect::<Vec<_>>();
+        let mut aggregations = Vec::new();
+        for (function, argument) in aggregates.iter() {
+            let index = prepared.len();
+            if let Some(argument) = argument {
+                prepared.push(compile_expr(argument, &scalar)?);
+            }
+            aggregations.push(match (function, argument) {
+                (Function::Count, _) => Aggregation::Count,
+                (_, None) => return Err(format!("{:?} requires an argument", function)),
+                (Function::Sum, Some(_)) => Aggregation::Sum(index),
+                (Function::Min, Some(_)) => Aggregation::Min(index),
+                (Function::Max, Some(_)) => Aggregation::Max(index),
+            });
+        }
+        plan = plan.map(prepared).aggregate(keys, aggregations);
+
+        let grouped = |expr: &Expr| -> Result<Option<Expression>, String> {
+            if let Some(position) = select.group_by.iter().position(|key| key == expr) {
+                return Ok(Some(Expression::Column(position)));
+            }
+            match expr {
+                Expr::Aggregate(function, argument) => {
+                    let position = aggregates.iter().position(|(f, a)| f == function && a.as_ref() == argument.as_ref().map(|x| &**x)).expect("aggregate not found");
+                    Ok(Some(Expression::Column(select.group_by.len() + position)))
+                },
+                Expr::Column(qualifier, name) => Err(format!("column {} must appear in GROUP BY or in an aggregate", display_column(qualifier, name))),
+                _ => Ok(None),
+            }
+        };
+
+        for item in select.items.iter() {
+            match item {
+                SelectItem::Wildcard => { return Err("cannot select * with GROUP BY or aggregates".to_string()); },
+                SelectItem::Expr(expr, alias) => {
+                    names.push(name_of(expr, alias, names.len()));
+                    expressions.push(compile_expr(expr, &grouped)?);
+                },
+            }
+        }
+    }
+
+    plan = plan.map(expressions);
+    if select.distinct {
+        plan = plan.distinct();
+    }
+
+    Ok((plan, names))
+}
+
+/// Compiles an expression, substituting the compiled forms `leaf` produces for sub-expressions.
+fn compile_expr<F>(expr: &Expr, leaf: &F) -> Result<Expression, String>
+where
+    F: Fn(&Expr)->Result<Option<Expression>, String>,
+{
+    if let Some(compiled) = leaf(expr)? {
+        return Ok(compiled);
+    }
+    Ok(match expr {
+        Expr::Column(qualifier, name) => { return Err(format!("unresolved column: {}", display_column(qualifier, name))); },
+        Expr::Aggregate(..) => { return Err("unresolved aggregate".to_string()); },
+        Expr::Literal(value) => Expression::Literal(value.clone()),
+        Expr::Unary(op, arg) => Expression::Unary(*op, Box::new(compile_expr(arg, leaf)?)),
+        Expr::Binary(op, arg1, arg2) => Expression::Binary(*op, Box::new(compile_expr(arg1, leaf)?), Box::new(compile_expr(arg2, leaf)?)),
+        Expr::Cast(arg, typ) => Expression::Cast(Box::new(compile_expr(arg, leaf)?), *typ),
+        Expr::Case(branches, otherwise) => {
+            let mut compiled = Vec::new();
+            for (condition, result) in branches.iter() {
+                compiled.push((compile_expr(condition, leaf)?, compile_expr(result, leaf)?));
+            }
+            Expression::Case(compiled, Box::new(compile_expr(otherwise, leaf)?))
+        },
+    })
+}
+
+/// Collects the conjuncts of a condition.
+fn conjuncts_of<'a>(expr: &'a Expr, conjuncts: &mut Vec<&'a Expr>) {
+    if let Expr::Binary(BinaryOp::And, expr1, expr2) = expr {
+        conjuncts_of(expr1, conjuncts);
+        conjuncts_of(expr2, conjuncts);
+    }
+    else {
+        conjuncts.push(expr);
+    }
+}
+
+/// Collects the distinct aggregates of an expression, which may not be nested.
+fn aggregates_of(expr: &Expr, aggregates: &mut Vec<(Function, Option<Expr>)>) -> Result<(), String> {
+    match expr {
+        Expr::Aggregate(function, argument) => {
+            if let Some(argument) = argument {
+                let mut nested = Vec::new();
+                aggregates_of(argument, &mut nested)?;
+                if !nested.is_empty() {
+                    return Err("aggregates may not be nested".to_string());
+                }
+            }
+            let aggregate = (*function, argument.as_ref().map(|x| (**x).clone()));
+            if !aggregates.contains(&aggregate) {
+                aggregates.push(aggregate);
+            }
+        },
+        Expr::Column(..) | Expr::Literal(_) => { },
+        Expr::Unary(_, arg) | Expr::Cast(arg, _) => aggregates_of(arg, aggregates)?,
+        Expr::Binary(_, arg1, arg2) => {
+            aggregates_of(arg1, aggregates)?;
+            aggregates_of(arg2, aggregates)?;
+        },
+        Expr::Case(branches, otherwise) => {
+            for (condition, result) in branches.iter() {
+                aggregates_of(condition, aggregates)?;
+                aggregates_of(result, aggregates)?;
+            }
+            aggregates_of(otherwise, aggregates)?;
+        },
+    }
+    Ok(())
+}
+
+/// The name of a selected column: its alias, the referenced column, or its position.
+fn name_of(expr: &Expr, alias: &Option<String>, position: usize) -> String {
+    match (alias, expr) {
+        (Some(alias), _) => alias.clone(),
+        (None, Expr::Column(_, name)) => name.clone(),
+        (None, _) => format!("column{}", position),
+    }
+}
+
+/// A column reference as written, for error messages.
+fn display_column(qualifier: &Option<String>, name: &str) -> String {
+    match qualifier {
+        Some(qualifier) => format!("{}.{}", qualifier, name),
+        None => name.to_string(),
+    }
+}
+
+/// A lexical token.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// An identifier or keyword.
+    Word(String),
+    Number(usize),
+    String(String),
+    Symbol(&'static str),
+}
+
+/// Words that cannot be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "ALL", "AND", "AS", "BY", "CASE", "CAST", "CREATE", "DISTINCT", "ELSE", "END", "FALSE", "FROM",
+    "GROUP", "INNER", "JOIN", "NOT", "ON", "OR", "SELECT", "TABLE", "THEN", "TRUE", "UNION", "VIEW",
+    "WHEN", "WHERE",
+];
+
+/// Symbols, with longer symbols before their prefixes.
+const SYMBOLS: &[&str] = &["<>", "<=", ">=", "!=", "||", "(", ")", ",", ".", ";", "*", "+", "-", "/", "%", "=", "<", ">"];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        }
+        else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' { word.push(c); chars.next(); } else { break; }
+            }
+            tokens.push(Token::Word(word));
+        }
+        else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_ascii_digit() { digits.push(c); chars.next(); } else { break; }
+            }
+            tokens.push(Token::Number(digits.parse().map_err(|_| format!("number too large: {}", digits))?));
+        }
+        else if c == '\'' {
+            // Strings are quoted by single quotes, with quotes escaped by doubling them.
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\'')) => {
+                        if let Some(&(_, '\'')) = chars.peek() { string.push('\''); chars.next(); } else { break; }
+                    },
+                    Some((_, c)) => string.push(c),
+                    None => { return Err("unterminated string".to_string()); },
+                }
+            }
+            tokens.push(Token::String(string));
+        }
+        else if let Some(symbol) = SYMBOLS.iter().find(|symbol| text[start ..].starts_with(*symbol)) {
+            for _ in 0 .. symbol.len() { chars.next(); }
+            tokens.push(Token::Symbol(*symbol));
+        }
+        else {
+            return Err(format!("unexpected character: {:?}", c));
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over tokens.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+
+    fn new(text: &str) -> Result<Self, String> {
+        Ok(Parser { tokens: tokenize(text)?, position: 0 })
+    }
+
+    /// Requires that all tokens other than a trailing semicolon have been consumed.
+    fn finish(&mut self) -> Result<(), String> {
+        self.symbol(";");
+        match self.tokens.get(self.position) {
+            None => Ok(()),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) => word.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    /// Consumes `keyword` if it is next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found { self.position += 1; }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.keyword(keyword) { Ok(()) } else { Err(format!("expected {}, found {:?}", keyword, self.tokens.get(self.position))) }
+    }
+
+    /// Consumes `symbol` if it is next.
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = match self.tokens.get(self.position) {
+            Some(Token::Symbol(next)) => *next == symbol,
+            _ => false,
+        };
+        if found { self.position += 1; }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.symbol(symbol) { Ok(()) } else { Err(format!("expected {:?}, found {:?}", symbol, self.tokens.get(self.position))) }
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) if !KEYWORDS.iter().any(|keyword| word.eq_ignore_ascii_case(keyword)) => {
+                self.position += 1;
+                Ok(word.clone())
+            },
+            token => Err(format!("expected identifier, found {:?}", token)),
+        }
+    }
+
+    fn typ(&mut self) -> Result<Type, String> {
+        let typ = match self.tokens.get(self.position) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("BOOL") || word.eq_ignore_ascii_case("BOOLEAN") => Type::Bool,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("INT") || word.eq_ignore_ascii_case("INTEGER") || word.eq_ignore_ascii_case("USIZE") => Type::Usize,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("STRING") || word.eq_ignore_ascii_case("TEXT") || word.eq_ignore_ascii_case("VARCHAR") => Type::String,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("DURATION") || word.eq_ignore_ascii_case("INTERVAL") => Type::Duration,
+            token => { return Err(format!("expected type, found {:?}", token)); },
+        };
+        self.position += 1;
+        Ok(typ)
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        if self.keyword("CREATE") {
+            if self.keyword("TABLE") {
+                let name = self.identifier()?;
+                self.expect_symbol("(")?;
+                let mut columns = vec![(self.identifier()?, self.typ()?)];
+                while self.symbol(",") {
+                    columns.push((self.identifier()?, self.typ()?));
+                }
+                self.expect_symbol(")")?;
+                Ok(Statement::CreateTable(name, columns))
+            }
+            else {
+                self.expect_keyword("VIEW")?;
+                let name = self.identifier()?;
+                self.expect_keyword("AS")?;
+                Ok(Statement::CreateView(name, self.query()?))
+            }
+        }
+        else {
+            Ok(Statement::Query(self.query()?))
+        }
+    }
+
+    fn query(&mut self) -> Result<QueryExpr, String> {
+        let mut query = QueryExpr::Select(Box::new(self.select()?));
+        while self.keyword("UNION") {
+            let all = self.keyword("ALL");
+            let other = QueryExpr::Select(Box::new(self.select()?));
+            query = QueryExpr::Union(Box::new(query), Box::new(other), all);
+        }
+        Ok(query)
+    }
+
+    fn select(&mut self) -> Result<Select, String> {
+        self.expect_keyword("SELECT")?;
+        let distinct = self.keyword("DISTINCT");
+
+        let mut items = Vec::new();
+        loop {
+            if self.symbol("*") {
+                items.push(SelectItem::Wildcard);
+            }
+            else {
+                let expr = self.expr()?;
+                let alias = if self.keyword("AS") { Some(self.identifier()?) } else { None };
+                items.push(SelectItem::Expr(expr, alias));
+            }
+            if !self.symbol(",") { break; }
+        }
+
+        self.expect_keyword("FROM")?;
+        let mut from = vec![self.relation()?];
+        let mut conditions = Vec::new();
+        loop {
+            if self.symbol(",") {
+                from.push(self.relation()?);
+            }
+            else if self.peek_keyword("JOIN") || self.peek_keyword("INNER") {
+                self.keyword("INNER");
+                self.expect_keyword("JOIN")?;
+                from.push(self.relation()?);
+                self.expect_keyword("ON")?;
+                conditions.push(self.expr()?);
+            }
+            else {
+                break;
+            }
+        }
+
+        if self.keyword("WHERE") {
+            conditions.push(self.expr()?);
+        }
+
+        let mut group_by = Vec::new();
+        if self.keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            group_by.push(self.expr()?);
+            while self.symbol(",") {
+                group_by.push(self.expr()?);
+            }
+        }
+
+        Ok(Select { distinct, items, from, conditions, group_by })
+    }
+
+    /// A relation name and its alias, which defaults to the name.
+    fn relation(&mut self) -> Result<(String, String), String> {
+        let name = self.identifier()?;
+        let alias =
+        if self.keyword("AS") {
+            self.identifier()?
+        }
+        else {
+            // Keywords that follow the relation are not aliases.
+            self.identifier().unwrap_or_else(|_| name.clone())
+        };
+        Ok((name, alias))
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut expr = self.conjunction()?;
+        while self.keyword("OR") {
+            expr = Expr::Binary(BinaryOp::Or, Box::new(expr), Box::new(self.conjunction()?));
+        }
+        Ok(expr)
+    }
+
+    fn conjunction(&mut self) -> Result<Expr, String> {
+        let mut expr = self.negation()?;
+        while self.keyword("AND") {
+            expr = Expr::Binary(BinaryOp::And, Box::new(expr), Box::new(self.negation()?));
+        }
+        Ok(expr)
+    }
+
+    fn negation(&mut self) -> Result<Expr, String> {
+        if self.keyword("NOT") {
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.negation()?)))
+        }
+        else {
+            self.comparison()
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let expr = self.additive()?;
+        let op =
+        if self.symbol("=") { BinaryOp::Eq }
+        else if self.symbol("<>") || self.symbol("!=") { BinaryOp::Ne }
+        else if self.symbol("<=") { BinaryOp::Le }
+        else if self.symbol(">=") { BinaryOp::Ge }
+        else if self.symbol("<") { BinaryOp::Lt }
+        else if self.symbol(">") { BinaryOp::Gt }
+        else { return Ok(expr); };
+        Ok(Expr::Binary(op, Box::new(expr), Box::new(self.additive()?)))
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut expr = self.multiplicative()?;
+        loop {
+            let op =
+            if self.symbol("+") { BinaryOp::Add }
+            else if self.symbol("-") { BinaryOp::Sub }
+            else if self.symbol("||") { BinaryOp::Concat }
+            else { return Ok(expr); };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        loop {
+            let op =
+            if self.symbol("*") { BinaryOp::Mul }
+            else if self.symbol("/") { BinaryOp::Div }
+            else if self.symbol("%") { BinaryOp::Mod }
+            else { return Ok(expr); };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.primary()?));
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or_else(|| "unexpected end of statement".to_string())?;
+        match token {
+            Token::Number(number) => { self.position += 1; Ok(Expr::Literal(Value::Usize(number))) },
+            Token::String(string) => { self.position += 1; Ok(Expr::Literal(Value::String(string))) },
+            Token::Symbol("(") => {
+                self.position += 1;
+                let expr = self.expr()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            },
+            Token::Word(ref word) if word.eq_ignore_ascii_case("TRUE") => { self.position += 1; Ok(Expr::Literal(Value::Bool(true))) },
+            Token::Word(ref word) if word.eq_ignore_ascii_case("FALSE") => { self.position += 1; Ok(Expr::Literal(Value::Bool(false))) },
+            Token::Word(ref word) if word.eq_ignore_ascii_case("CAST") => {
+                self.position += 1;
+                self.expect_symbol("(")?;
+                let expr = self.expr()?;
+                self.expect_keyword("AS")?;
+                let typ = self.typ()?;
+                self.expect_symbol(")")?;
+                Ok(Expr::Cast(Box::new(expr), typ))
+            },
+            Token::Word(ref word) if word.eq_ignore_ascii_case("CASE") => {
+                self.position += 1;
+                let mut branches = Vec::new();
+                while self.keyword("WHEN") {
+                    let condition = self.expr()?;
+                    self.expect_keyword("THEN")?;
+                    branches.push((condition, self.expr()?));
+                }
+                if branches.is_empty() {
+                    return Err("expected WHEN".to_string());
+                }
+                self.expect_keyword("ELSE")?;
+                let otherwise = self.expr()?;
+                self.expect_keyword("END")?;
+                Ok(Expr::Case(branches, Box::new(otherwise)))
+            },
+            Token::Word(_) if self.tokens.get(self.position + 1) == Some(&Token::Symbol("(")) => {
+                let name = self.identifier()?.to_uppercase();
+                self.expect_symbol("(")?;
+                let expr =
+                match name.as_str() {
+                    "COUNT" if self.symbol("*") => Expr::Aggregate(Function::Count, None),
+                    "COUNT" => Expr::Aggregate(Function::Count, Some(Box::new(self.expr()?))),
+                    "SUM" => Expr::Aggregate(Function::Sum, Some(Box::new(self.expr()?))),
+                    "MIN" => Expr::Aggregate(Function::Min, Some(Box::new(self.expr()?))),
+                    "MAX" => Expr::Aggregate(Function::Max, Some(Box::new(self.expr()?))),
+                    "LENGTH" => Expr::Unary(UnaryOp::Length, Box::new(self.expr()?)),
+                    "UPPER" => Expr::Unary(UnaryOp::Upper, Box::new(self.expr()?)),
+                    "LOWER" => Expr::Unary(UnaryOp::Lower, Box::new(self.expr()?)),
+                    _ => { return Err(format!("unknown function: {}", name)); },
+                };
+                self.expect_symbol(")")?;
+                Ok(expr)
+            },
+            Token::Word(_) => {
+                let name = self.identifier()?;
+                if self.symbol(".") {
+                    Ok(Expr::Column(Some(name), self.identifier()?))
+                }
+                else {
+                    Ok(Expr::Column(None, name))
+                }
+            },
+            token => Err(format!("unexpected {:?}", token)),
+        }
+    }
+}
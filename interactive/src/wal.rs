@@ -0,0 +1,188 @@
+//! A durable log of executed commands.
+//!
+//! A server may append each command it executes successfully to a local log, before the
+//! command is acknowledged, and replay the log when it restarts to restore its inputs, rules,
+//! and arrangements. Each process of a server keeps its own log, and as all processes execute
+//! the same commands in the same order, their logs agree up to the last commands before a crash.
+//! Logs may then differ in length, so a server replays only one of them, and replaces the others
+//! with it (see `server`).
+//!
+//! Commands that only respond to a client (`Subscribe` and `Peek`), that depend on external
+//! connections (`SourceLogging`), and `Shutdown` are not logged, as they should not be repeated.
+//!
+//! The log can be compacted, which rewrites the updates to each input as one consolidated batch.
+//! The compacted log restores the same contents of inputs and rules, but not their histories.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use differential_dataflow::ExchangeData;
+use differential_dataflow::consolidation::consolidate_updates;
+
+use {Command, Datum, Time, Diff};
+use logging::LoggingValue;
+use sql::SqlValue;
+
+/// An append-only file of commands.
+pub struct CommandLog<V: Datum> {
+    file: File,
+    phantom: PhantomData<V>,
+}
+
+impl<V> CommandLog<V>
+where
+    V: ExchangeData+Hash+LoggingValue+SqlValue,
+{
+    /// Opens the log at `path`, creating it if it does not exist, and returns the commands it contains.
+    ///
+    /// A partially written command at the end of the log, as left by a crash, is removed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<(Self, Vec<Command<V>>)> {
+
+        let mut file = OpenOptions::new().read(true).write(true).create(true).open(path)?;
+
+        let mut bytes = Vec::new();
+        io::Read::read_to_end(&mut file, &mut bytes)?;
+
+        let mut commands = Vec::new();
+        let mut reader = &bytes[..];
+        while let Ok(command) = Command::deserialize_from(&mut reader) {
+            commands.push(command);
+        }
+
+        // Discard any bytes following the last complete command.
+        let length = (bytes.len() - reader.len()) as u64;
+        file.set_len(length)?;
+        io::Seek::seek(&mut file, io::SeekFrom::Start(length))?;
+
+        Ok((CommandLog { file, phantom: PhantomData }, commands))
+    }
+
+    /// Replaces the log at `path` with `commands`.
+    ///
+    /// The commands are written to a temporary file that then replaces the log, so that the
+    /// log is not lost if writing fails.
+    pub fn create<P: AsRef<Path>>(path: P, commands: &[Command<V>]) -> io::Result<Self> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("compacting");
+        {
+            let mut file = File::create(&temporary)?;
+            let mut bytes = Vec::new();
+            for command in commands.iter() {
+                command.serialize_into(&mut bytes);
+            }
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&temporary, path)?;
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(CommandLog { file, phantom: PhantomData })
+    }
+
+    /// Indicates if a command is recorded in logs.
+    pub fn records(command: &Command<V>) -> bool {
+        match command {
+            Command::Subscribe(_) |
+            Command::Peek(_, _) |
+            Command::Disconnect |
+            Command::SourceLogging(_, _, _, _, _) |
+            Command::Shutdown => false,
+            _ => true,
+        }
+    }
+
+    /// Appends a command to the log, returning once it is durable.
+    pub fn append(&mut self, command: &Command<V>) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        command.serialize_into(&mut bytes);
+        self.file.write_all(&bytes)?;
+        self.file.sync_data()
+    }
+}
+
+/// Rewrites a sequence of commands so that the updates to each input form one consolidated batch.
+///
+/// Updates at times before the final `AdvanceTime` are advanced to the earliest time, and
+/// consolidated with the initial contents of their input. The batch for each input is
+/// introduced once the input has been created and the commands that do not update inputs have
+/// been replayed, or just before the input is closed or created anew. `AdvanceTime` commands are
+/// replaced by one advancing to the last time.
+pub fn compact<V>(commands: Vec<Command<V>>) -> Vec<Command<V>>
+where
+    V: ExchangeData+Hash+Datum,
+{
+    let mut result = Vec::new();
+
+    // Inputs in order of creation, and their pending updates.
+    let mut inputs: Vec<String> = Vec::new();
+    let mut pending: HashMap<String, Vec<(Vec<V>, Time, Diff)>> = HashMap::new();
+
+    // The time to which the log last advanced inputs, before which times need not be distinguished.
+    let frontier = commands.iter().filter_map(|command| match command {
+        Command::AdvanceTime(time) => Some(time.clone()),
+        _ => None,
+    }).last();
+
+    // Moves the pending updates to an input into the result.
+    fn flush<V: ExchangeData+Datum>(
+        name: &str,
+        pending: &mut HashMap<String, Vec<(Vec<V>, Time, Diff)>>,
+        result: &mut Vec<Command<V>>,
+    )
+    {
+        if let Some(mut updates) = pending.remove(name) {
+            consolidate_updates(&mut updates);
+            if !updates.is_empty() {
+                result.push(Command::UpdateInput(name.to_string(), updates));
+            }
+        }
+    }
+
+    for command in commands.into_iter() {
+        match command {
+            Command::CreateInput(name, schema, tuples) => {
+                flush(&name, &mut pending, &mut result);
+                inputs.retain(|input| input != &name);
+                inputs.push(name.clone());
+                pending.insert(name.clone(), tuples.into_iter().map(|tuple| (tuple, Default::default(), 1)).collect());
+                result.push(Command::CreateInput(name, schema, Vec::new()));
+            },
+            Command::UpdateInput(name, updates) => {
+                let earliest = Time::default();
+                let updates = updates.into_iter().map(|(tuple, time, diff)| {
+                    let time = if frontier.map(|frontier| time < frontier).unwrap_or(false) { earliest } else { time };
+                    (tuple, time, diff)
+                });
+                pending.entry(name).or_insert(Vec::new()).extend(updates);
+            },
+            Command::CloseInput(name) => {
+                flush(&name, &mut pending, &mut result);
+                inputs.retain(|input| input != &name);
+                result.push(Command::CloseInput(name));
+            },
+            Command::AdvanceTime(_) => { },
+            command => {
+                result.push(command);
+            },
+        }
+    }
+
+    for name in inputs.iter() {
+        flush(name, &mut pending, &mut result);
+    }
+    // Updates to inputs not created by `CreateInput`, for example by SQL statements.
+    let mut others = pending.keys().cloned().collect::<Vec<_>>();
+    others.sort();
+    for name in others.iter() {
+        flush(name, &mut pending, &mut result);
+    }
+
+    if let Some(time) = frontier {
+        result.push(Command::AdvanceTime(time));
+    }
+
+    result
+}
@@ -0,0 +1,104 @@
+extern crate timely;
+extern crate differential_dataflow;
+extern crate interactive;
+
+mod common;
+
+use std::time::Duration;
+
+use timely::progress::frontier::Antichain;
+use differential_dataflow::trace::TraceReader;
+
+use interactive::{Command, Manager, Plan, Response, Schema};
+use interactive::plan::Aggregation;
+use interactive::concrete::{Value, Type};
+
+use common::{Connection, secs};
+
+/// Indicates that the dataflow maintaining a trace has shut down, and will not update it.
+fn is_closed<Tr: TraceReader<Time=Duration>>(trace: &mut Tr) -> bool {
+    let mut upper = Antichain::new();
+    trace.read_upper(&mut upper);
+    upper.elements().is_empty()
+}
+
+fn sale(amount: usize, store: usize) -> Vec<Value> {
+    vec![Value::Usize(amount), Value::Usize(store)]
+}
+
+fn row(values: &[usize]) -> (Vec<Value>, isize) {
+    (values.iter().map(|&value| Value::Usize(value)).collect(), 1)
+}
+
+#[test]
+fn aggregates_share_keyed_arrangement() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        Command::CreateInput("sales".to_string(), Schema::new(vec![("amount", Type::Usize), ("store", Type::Usize)]), vec![sale(10, 1), sale(20, 1), sale(5, 2)])
+            .execute(0, &mut manager, worker);
+
+        // Counts and sums by store, which arranges sales by store.
+        Command::from(
+            Plan::source("sales")
+                .aggregate(vec![1], vec![Aggregation::Count, Aggregation::Sum(0)])
+                .into_rule("totals"))
+            .execute(0, &mut manager, worker);
+
+        let mut by_store = manager.traces.get_keyed(&Plan::source("sales"), &[1]).expect("sales not arranged by store");
+
+        // Minimums and maximums by store, which should use the same arrangement.
+        Command::from(
+            Plan::source("sales")
+                .aggregate(vec![1], vec![Aggregation::Min(0), Aggregation::Max(0)])
+                .into_rule("extremes"))
+            .execute(0, &mut manager, worker);
+
+        Command::Peek("totals".to_string(), secs(0)).execute(0, &mut manager, worker);
+        Command::Peek("extremes".to_string(), secs(0)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+
+        // Retractions update each aggregate.
+        Command::UpdateInput("sales".to_string(), vec![(sale(20, 1), secs(1), -1), (sale(7, 2), secs(1), 1)]).execute(0, &mut manager, worker);
+        Command::Peek("totals".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::Peek("extremes".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+
+        // Retracting the last sale of a store removes its group.
+        Command::UpdateInput("sales".to_string(), vec![(sale(10, 1), secs(2), -1)]).execute(0, &mut manager, worker);
+        Command::Peek("totals".to_string(), secs(2)).execute(0, &mut manager, worker);
+        Command::Peek("extremes".to_string(), secs(2)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(3)).execute(0, &mut manager, worker);
+
+        for _ in 0 .. 100 { worker.step(); }
+
+        let mut snapshots = connection.snapshots();
+        snapshots.sort();
+        assert_eq!(snapshots, vec![
+            ("extremes".to_string(), secs(0), vec![row(&[1, 10, 20]), row(&[2, 5, 5])]),
+            ("extremes".to_string(), secs(1), vec![row(&[1, 10, 10]), row(&[2, 5, 7])]),
+            ("extremes".to_string(), secs(2), vec![row(&[2, 5, 7])]),
+            ("totals".to_string(), secs(0), vec![row(&[1, 2, 30]), row(&[2, 1, 5])]),
+            ("totals".to_string(), secs(1), vec![row(&[1, 1, 10]), row(&[2, 2, 12])]),
+            ("totals".to_string(), secs(2), vec![row(&[2, 2, 12])]),
+        ]);
+
+        // The arrangement by store belongs to the first query, and is kept for the second.
+        Command::DropQuery("totals".to_string()).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.traces.get_keyed(&Plan::source("sales"), &[1]).is_some());
+        assert!(!is_closed(&mut by_store));
+
+        // Once neither query remains, the arrangement is released.
+        Command::DropQuery("extremes".to_string()).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.traces.get_keyed(&Plan::source("sales"), &[1]).is_none());
+        assert!(is_closed(&mut by_store));
+    });
+}
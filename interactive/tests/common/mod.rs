@@ -0,0 +1,88 @@
+//! Helpers shared by the integration tests.
+//!
+//! Each test file includes this module with `mod common;`, and uses only some of its helpers.
+
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use interactive::Response;
+use interactive::concrete::Value;
+
+/// A client connection recording the bytes of responses, which fails once closed.
+#[derive(Clone)]
+pub struct Connection {
+    bytes: Arc<Mutex<Vec<u8>>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.closed.load(Ordering::SeqCst) {
+            Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "connection closed"))
+        }
+        else {
+            self.bytes.lock().unwrap().write(buf)
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+impl Connection {
+    /// Creates a new open connection.
+    pub fn new() -> Self {
+        Connection {
+            bytes: Arc::new(Mutex::new(Vec::new())),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    /// Causes subsequent writes to fail.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+    /// Removes and returns the responses received so far, including acknowledgements.
+    pub fn all_responses(&self) -> Vec<Response<Value>> {
+        let bytes = std::mem::replace(&mut *self.bytes.lock().unwrap(), Vec::new());
+        let mut reader = &bytes[..];
+        let mut responses = Vec::new();
+        while !reader.is_empty() {
+            responses.push(Response::deserialize_from(&mut reader).expect("failed to deserialize response"));
+        }
+        responses
+    }
+    /// Removes and returns the responses received so far, other than acknowledgements.
+    pub fn responses(&self) -> Vec<Response<Value>> {
+        self.all_responses()
+            .into_iter()
+            .filter(|response| match response { Response::Ack => false, _ => true })
+            .collect()
+    }
+    /// Removes and returns the snapshots received so far, with their results sorted.
+    ///
+    /// Panics if responses other than snapshots and acknowledgements were received.
+    pub fn snapshots(&self) -> Vec<(String, Duration, Vec<(Vec<Value>, isize)>)> {
+        self.responses()
+            .into_iter()
+            .map(|response| match response {
+                Response::Snapshot(name, time, mut results) => {
+                    results.sort();
+                    (name, time, results)
+                },
+                response => panic!("unexpected response: {:?}", response),
+            })
+            .collect()
+    }
+}
+
+/// A directed edge between two nodes.
+pub fn edge(src: usize, dst: usize) -> Vec<Value> {
+    vec![Value::Usize(src), Value::Usize(dst)]
+}
+
+/// A duration of `secs` seconds.
+pub fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
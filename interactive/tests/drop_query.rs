@@ -0,0 +1,195 @@
+extern crate timely;
+extern crate differential_dataflow;
+extern crate interactive;
+
+mod common;
+
+use std::rc::Weak;
+use std::cell::RefCell;
+use std::time::Duration;
+
+use differential_dataflow::trace::wrappers::rc::TraceBox;
+use differential_dataflow::trace::implementations::ord::OrdKeySpine;
+
+use interactive::{Command, Manager, Plan, Response, Schema};
+use interactive::concrete::{Value, Type};
+
+use common::{Connection, edge};
+
+/// The trace shared by the handles to an arrangement.
+type Spine = TraceBox<OrdKeySpine<Vec<Value>, Duration, isize>>;
+
+/// A reference to the spine of a published rule, which does not keep it from being freed.
+fn spine(manager: &mut Manager<Value>, name: &str) -> Weak<RefCell<Spine>> {
+    let trace = manager.traces.get_unkeyed(&Plan::source(name)).expect("rule not found");
+    std::rc::Rc::downgrade(&trace.trace_box_unstable())
+}
+
+#[test]
+fn drop_query() {
+
+    timely::execute_directly(|worker| {
+
+        let mut manager = Manager::<Value>::new();
+
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2), edge(2, 3)])
+            .execute(0, &mut manager, worker);
+
+        // Paths of two hops, arranging edges by source.
+        Command::from(
+            Plan::source("edges")
+                .join(Plan::source("edges"), vec![(1, 0)])
+                .project(vec![1, 2])
+                .into_rule("two_hop"))
+            .execute(0, &mut manager, worker);
+
+        // Paths of three hops, using both the two-hop paths and the arrangement of edges by source.
+        Command::from(
+            Plan::source("two_hop")
+                .join(Plan::source("edges"), vec![(1, 0)])
+                .project(vec![1, 2])
+                .into_rule("three_hop"))
+            .execute(0, &mut manager, worker);
+
+        Command::AdvanceTime(Duration::from_secs(1)).execute(0, &mut manager, worker);
+
+        // The test holds no handles to the traces, so that only the system keeps them alive.
+        let edges = spine(&mut manager, "edges");
+        let two_hop = spine(&mut manager, "two_hop");
+        let three_hop = spine(&mut manager, "three_hop");
+        let by_source = {
+            let trace = manager.traces.get_keyed(&Plan::source("edges"), &[0]).expect("edges not arranged by source");
+            std::rc::Rc::downgrade(&trace.trace_box_unstable())
+        };
+
+        // Dropping the two-hop query unpublishes it, but its dataflow continues for the three-hop query.
+        Command::DropQuery("two_hop".to_string()).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.traces.get_unkeyed(&Plan::source("two_hop")).is_none());
+        assert!(manager.traces.get_keyed(&Plan::source("edges"), &[0]).is_some());
+        assert!(two_hop.upgrade().is_some());
+        assert!(three_hop.upgrade().is_some());
+        assert!(by_source.upgrade().is_some());
+
+        // Dropping the three-hop query shuts down both dataflows, and frees their spines.
+        Command::DropQuery("three_hop".to_string()).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.traces.get_unkeyed(&Plan::source("three_hop")).is_none());
+        assert!(manager.traces.get_keyed(&Plan::source("edges"), &[0]).is_none());
+        assert!(manager.traces.get_keyed(&Plan::source("edges"), &[1]).is_none());
+        assert!(two_hop.upgrade().is_none());
+        assert!(three_hop.upgrade().is_none());
+        assert!(by_source.upgrade().is_none());
+
+        // The input is unaffected.
+        assert!(manager.traces.get_unkeyed(&Plan::source("edges")).is_some());
+        assert!(edges.upgrade().is_some());
+    });
+}
+
+#[test]
+fn drop_subscribed_query() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2)])
+            .execute(0, &mut manager, worker);
+
+        Command::from(
+            Plan::source("edges")
+                .join(Plan::source("edges"), vec![(1, 0)])
+                .project(vec![1, 2])
+                .into_rule("two_hop"))
+            .execute(0, &mut manager, worker);
+
+        // A subscription, and a peek that cannot be answered before the rule is dropped.
+        Command::Subscribe("two_hop".to_string()).execute(0, &mut manager, worker);
+        Command::Peek("two_hop".to_string(), Duration::from_secs(5)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(Duration::from_secs(1)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert_eq!(connection.responses(), vec![
+            Response::Updates("two_hop".to_string(), vec![(edge(0, 2), Duration::from_secs(0), 1)]),
+        ]);
+
+        let two_hop = spine(&mut manager, "two_hop");
+
+        // Reading the rule does not keep its query from being released, and ends with the rule.
+        Command::DropQuery("two_hop".to_string()).execute(0, &mut manager, worker);
+        Command::UpdateInput("edges".to_string(), vec![(edge(2, 3), Duration::from_secs(1), 1)]).execute(0, &mut manager, worker);
+        Command::AdvanceTime(Duration::from_secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.subscriptions.is_empty());
+        assert!(two_hop.upgrade().is_none());
+        assert_eq!(connection.responses(), vec![
+            Response::Error("Peek of \"two_hop\" at 5s interrupted, as the rule was dropped".to_string()),
+        ]);
+    });
+}
+
+#[test]
+fn redefine_dropped_query() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2), edge(2, 3)])
+            .execute(0, &mut manager, worker);
+
+        let two_hop =
+        Plan::source("edges")
+            .join(Plan::source("edges"), vec![(1, 0)])
+            .project(vec![1, 2]);
+
+        // Paths of three hops, arranging the two-hop paths by destination.
+        let three_hop =
+        Plan::source("two_hop")
+            .join(Plan::source("edges"), vec![(1, 0)])
+            .project(vec![1, 2]);
+
+        Command::from(two_hop.into_rule("two_hop")).execute(0, &mut manager, worker);
+        Command::from(three_hop.clone().into_rule("three_hop")).execute(0, &mut manager, worker);
+        Command::AdvanceTime(Duration::from_secs(1)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.traces.get_keyed(&Plan::source("two_hop"), &[1]).is_some());
+        assert!(manager.traces.get_unkeyed(&three_hop).is_some());
+
+        // Dropping the two-hop query forgets the arrangements built on it, though the three-hop query still runs.
+        Command::DropQuery("two_hop".to_string()).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.traces.get_keyed(&Plan::source("two_hop"), &[1]).is_none());
+        assert!(manager.traces.get_unkeyed(&three_hop).is_none());
+        assert!(manager.traces.is_published("three_hop"));
+        assert!(connection.responses().is_empty());
+
+        // The name cannot be redefined while the dropped query runs for the three-hop query.
+        Command::from(Plan::source("edges").into_rule("two_hop")).execute(0, &mut manager, worker);
+        assert_eq!(connection.responses(), vec![
+            Response::Error("Query error: \"two_hop\" was dropped, but is still used by other queries".to_string()),
+        ]);
+
+        // Once released, the names can be redefined by the same plans, whose results reflect the new rules.
+        Command::DropQuery("three_hop".to_string()).execute(0, &mut manager, worker);
+        Command::from(Plan::source("edges").project(vec![1, 0]).into_rule("two_hop")).execute(0, &mut manager, worker);
+        Command::from(three_hop.into_rule("three_hop")).execute(0, &mut manager, worker);
+        Command::Peek("three_hop".to_string(), Duration::from_secs(1)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(Duration::from_secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert_eq!(connection.responses(), vec![
+            Response::Snapshot("three_hop".to_string(), Duration::from_secs(1), vec![(edge(1, 1), 1), (edge(2, 2), 1), (edge(3, 3), 1)]),
+        ]);
+    });
+}
@@ -0,0 +1,135 @@
+extern crate timely;
+extern crate interactive;
+
+mod common;
+
+use interactive::{Command, Manager, Plan, Response, Schema};
+use interactive::manager::Statistics;
+use interactive::concrete::{Value, Type};
+
+use common::{Connection, edge, secs};
+
+/// The join orders of the delta queries of a multiway join, as the manager would render it.
+fn delta_orders(plan: &Plan<Value>, manager: &Manager<Value>) -> Vec<Vec<usize>> {
+    match plan {
+        Plan::MultiwayJoin(join) => join.delta_orders(&manager.traces),
+        plan => panic!("not a multiway join: {:?}", plan),
+    }
+}
+
+#[test]
+fn multiway_join_order() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        let edges = vec![edge(0, 1), edge(1, 2), edge(0, 2), edge(2, 3), edge(1, 3), edge(3, 4)];
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), edges)
+            .execute(0, &mut manager, worker);
+        Command::CreateInput("marked".to_string(), Schema::new(vec![("node", Type::Usize)]), vec![vec![Value::Usize(0)], vec![Value::Usize(1)]])
+            .execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        // Statistics count the records of inputs, as updated by commands.
+        assert_eq!(manager.traces.statistics(&Plan::source("edges")), Some(Statistics { records: 6 }));
+        assert_eq!(manager.traces.statistics(&Plan::source("marked")), Some(Statistics { records: 2 }));
+
+        // Triangles starting from a marked node, whose delta queries join the small `marked` early.
+        let triangles =
+        Plan::multiway_join(
+            vec![Plan::source("edges"), Plan::source("edges"), Plan::source("edges"), Plan::source("marked")],
+            vec![vec![(0, 0), (0, 2), (0, 3)], vec![(1, 0), (0, 1)], vec![(1, 1), (1, 2)]],
+            vec![(0, 0), (1, 0), (1, 1)],
+        );
+        assert_eq!(delta_orders(&triangles, &manager), vec![vec![0, 3, 1, 2], vec![1, 0, 3, 2], vec![2, 3, 0, 1], vec![3, 0, 1, 2]]);
+
+        Command::from(triangles.into_rule("triangles")).execute(0, &mut manager, worker);
+        assert!(manager.traces.statistics(&Plan::source("triangles")).is_none());
+
+        Command::UpdateInput("edges".to_string(), vec![(edge(0, 3), secs(1), 1)]).execute(0, &mut manager, worker);
+        assert_eq!(manager.traces.statistics(&Plan::source("edges")), Some(Statistics { records: 7 }));
+        Command::Peek("triangles".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        let snapshot =
+        connection
+            .all_responses()
+            .into_iter()
+            .filter_map(|response| match response {
+                Response::Snapshot(name, time, mut results) => {
+                    assert_eq!(name, "triangles");
+                    assert_eq!(time, secs(1));
+                    results.sort();
+                    Some(results)
+                },
+                Response::Ack => None,
+                response => panic!("unexpected response: {:?}", response),
+            })
+            .next()
+            .expect("snapshot not received");
+
+        let triangle = |a, b, c| vec![Value::Usize(a), Value::Usize(b), Value::Usize(c)];
+        assert_eq!(snapshot, vec![(triangle(0, 1, 2), 1), (triangle(0, 1, 3), 1), (triangle(0, 2, 3), 1), (triangle(1, 2, 3), 1)]);
+    });
+}
+
+#[test]
+fn join_orders_agree_across_workers() {
+
+    // Each worker holds a different shard of each input, but plans from what all workers know.
+    let guards = timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut manager = Manager::<Value>::new();
+
+        let edges = vec![edge(0, 1), edge(1, 2), edge(0, 2), edge(2, 3), edge(1, 3), edge(3, 4)];
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), edges)
+            .execute(0, &mut manager, worker);
+        let nodes = (0 .. 10).map(|node| vec![Value::Usize(node)]).collect();
+        Command::CreateInput("nodes".to_string(), Schema::new(vec![("node", Type::Usize)]), nodes)
+            .execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+
+        // Paths of two edges through a node, which join the smaller `edges` first.
+        let paths =
+        Plan::multiway_join(
+            vec![Plan::source("edges"), Plan::source("nodes"), Plan::source("edges")],
+            vec![vec![(1, 0), (0, 1)], vec![(1, 0), (0, 2)]],
+            vec![(0, 0), (1, 2)],
+        );
+        let before = delta_orders(&paths, &manager);
+
+        // Edges to nodes, which arranges `nodes` by node.
+        Command::from(
+            Plan::multiway_join(
+                vec![Plan::source("edges"), Plan::source("nodes")],
+                vec![vec![(1, 0), (0, 1)]],
+                vec![(0, 0)],
+            )
+            .into_rule("sources"))
+            .execute(0, &mut manager, worker);
+
+        // Existing arrangements are preferred to building new ones, whatever the sizes.
+        let after = delta_orders(&paths, &manager);
+
+        // Dataflows of all workers agree, and so complete.
+        Command::from(paths.into_rule("paths")).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+
+        (before, after)
+    })
+    .expect("failed to execute");
+
+    let plans = guards.join().into_iter().map(|result| result.expect("worker failed")).collect::<Vec<_>>();
+    assert_eq!(plans.len(), 2);
+    for plan in plans.iter() {
+        assert_eq!(plan, &(
+            vec![vec![0, 2, 1], vec![1, 0, 2], vec![2, 0, 1]],
+            vec![vec![0, 1, 2], vec![1, 0, 2], vec![2, 0, 1]],
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+extern crate timely;
+extern crate interactive;
+
+mod common;
+
+use interactive::{Command, Manager, Plan, Query, Response, Schema};
+use interactive::concrete::{Value, Type};
+
+use common::{Connection, edge, secs};
+
+fn nodes(nodes: &[usize]) -> Vec<(Vec<Value>, isize)> {
+    nodes.iter().map(|&node| (vec![Value::Usize(node)], 1)).collect()
+}
+
+#[test]
+fn reachability() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2), edge(2, 3), edge(4, 5)])
+            .execute(0, &mut manager, worker);
+        Command::CreateInput("roots".to_string(), Schema::new(vec![("node", Type::Usize)]), vec![vec![Value::Usize(0)]])
+            .execute(0, &mut manager, worker);
+
+        // Nodes reachable from roots, extended by a binary join with edges.
+        let reach =
+        Plan::source("roots")
+            .concat(
+                Plan::source("reach")
+                    .join(Plan::source("edges"), vec![(0, 0)])
+                    .project(vec![1])
+            )
+            .distinct()
+            .into_rule("reach");
+
+        // The same nodes, extended by a multiway join with edges, which is not a delta query in the loop.
+        let reach_multiway =
+        Plan::source("roots")
+            .concat(
+                Plan::multiway_join(
+                    vec![Plan::source("reach_multiway"), Plan::source("edges")],
+                    vec![vec![(0, 0), (0, 1)]],
+                    vec![(1, 1)],
+                )
+            )
+            .distinct()
+            .into_rule("reach_multiway");
+
+        Query::new()
+            .add_rule(reach)
+            .add_rule(reach_multiway)
+            .into_command()
+            .execute(0, &mut manager, worker);
+
+        // Peeks are issued before advancing past their times, which allows the traces to compact.
+        Command::Peek("reach".to_string(), secs(0)).execute(0, &mut manager, worker);
+        Command::Peek("reach_multiway".to_string(), secs(0)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+
+        // Connecting the two components makes every node reachable.
+        Command::UpdateInput("edges".to_string(), vec![(edge(3, 4), secs(1), 1)]).execute(0, &mut manager, worker);
+        Command::Peek("reach".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::Peek("reach_multiway".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+
+        // Removing an edge retracts every node beyond it.
+        Command::UpdateInput("edges".to_string(), vec![(edge(1, 2), secs(2), -1)]).execute(0, &mut manager, worker);
+        Command::Peek("reach".to_string(), secs(2)).execute(0, &mut manager, worker);
+        Command::Peek("reach_multiway".to_string(), secs(2)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(3)).execute(0, &mut manager, worker);
+
+        for _ in 0 .. 1000 { worker.step(); }
+
+        let mut snapshots = connection.snapshots();
+        snapshots.sort();
+        assert_eq!(snapshots, vec![
+            ("reach".to_string(), secs(0), nodes(&[0, 1, 2, 3])),
+            ("reach".to_string(), secs(1), nodes(&[0, 1, 2, 3, 4, 5])),
+            ("reach".to_string(), secs(2), nodes(&[0, 1])),
+            ("reach_multiway".to_string(), secs(0), nodes(&[0, 1, 2, 3])),
+            ("reach_multiway".to_string(), secs(1), nodes(&[0, 1, 2, 3, 4, 5])),
+            ("reach_multiway".to_string(), secs(2), nodes(&[0, 1])),
+        ]);
+    });
+}
@@ -0,0 +1,133 @@
+extern crate timely;
+extern crate interactive;
+
+mod common;
+
+use std::time::Duration;
+
+use interactive::{Command, Manager, Plan, Response, Schema};
+use interactive::plan::{Predicate, SecondArgument};
+use interactive::concrete::{Value, Type};
+
+use common::{Connection, edge};
+
+/// Indicates that the responses consist of a single error.
+fn is_error(responses: Vec<Response<Value>>) -> bool {
+    match &responses[..] {
+        [Response::Error(_)] => true,
+        _ => false,
+    }
+}
+
+#[test]
+fn schema() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        let edges = Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]);
+        let names = Schema::new(vec![("node", Type::Usize), ("name", Type::String)]);
+
+        // Initial input must match the schema.
+        Command::CreateInput("edges".to_string(), edges.clone(), vec![edge(0, 1), vec![Value::Usize(1)]])
+            .execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+        assert!(manager.schema("edges").is_err());
+
+        Command::CreateInput("edges".to_string(), edges.clone(), vec![edge(0, 1), edge(1, 2)])
+            .execute(0, &mut manager, worker);
+        Command::CreateInput("names".to_string(), names.clone(), vec![])
+            .execute(0, &mut manager, worker);
+        assert_eq!(connection.all_responses(), vec![Response::Ack, Response::Ack]);
+        assert_eq!(manager.schema("edges"), Ok(Some(edges.clone())));
+
+        // Updates are rejected if any tuple does not match the schema.
+        let update = vec![
+            (edge(2, 3), Duration::from_secs(0), 1),
+            (vec![Value::Usize(3), Value::String("four".to_string())], Duration::from_secs(0), 1),
+        ];
+        Command::UpdateInput("edges".to_string(), update).execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+
+        // Updates and advances are rejected if any time precedes that to which inputs were advanced.
+        Command::AdvanceTime(Duration::from_secs(2)).execute(0, &mut manager, worker);
+        assert_eq!(connection.all_responses(), vec![Response::Ack]);
+        let update = vec![
+            (edge(2, 3), Duration::from_secs(2), 1),
+            (edge(3, 4), Duration::from_secs(1), 1),
+        ];
+        Command::UpdateInput("edges".to_string(), update).execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+        Command::AdvanceTime(Duration::from_secs(1)).execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+        assert_eq!(manager.inputs.time, Duration::from_secs(2));
+
+        // Joined keys must have the same type.
+        Command::from(
+            Plan::source("edges")
+                .join(Plan::source("names"), vec![(1, 1)])
+                .into_rule("bad_join"))
+            .execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+        assert!(manager.schema("bad_join").is_err());
+
+        // Columns must exist.
+        Command::from(
+            Plan::source("edges")
+                .project(vec![0, 2])
+                .into_rule("bad_project"))
+            .execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+
+        // Compared values must have the same type.
+        Command::from(
+            Plan::source("names")
+                .filter(Predicate::Equal(1, SecondArgument::Constant(Value::Usize(0))))
+                .into_rule("bad_filter"))
+            .execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+
+        // Sources must exist.
+        Command::from(
+            Plan::source("missing")
+                .into_rule("bad_source"))
+            .execute(0, &mut manager, worker);
+        assert!(is_error(connection.all_responses()));
+
+        // Well-typed rules are installed, with inferred schemas.
+        Command::from(
+            Plan::source("edges")
+                .join(Plan::source("names"), vec![(1, 0)])
+                .project(vec![2, 1])
+                .into_rule("labels"))
+            .execute(0, &mut manager, worker);
+        assert_eq!(connection.all_responses(), vec![Response::Ack]);
+        assert_eq!(
+            manager.schema("labels"),
+            Ok(Some(Schema::new(vec![("name", Type::String), ("src", Type::Usize)]))),
+        );
+
+        // Recursive rules have the schema they agree on.
+        Command::from(
+            Plan::source("edges")
+                .concat(
+                    Plan::source("reach")
+                        .join(Plan::source("edges"), vec![(1, 0)])
+                        .project(vec![1, 2])
+                )
+                .distinct()
+                .into_rule("reach"))
+            .execute(0, &mut manager, worker);
+        assert_eq!(connection.all_responses(), vec![Response::Ack]);
+        assert_eq!(manager.schema("reach"), Ok(Some(edges.clone())));
+
+        // Dropped rules no longer have schemas.
+        Command::DropQuery("labels".to_string()).execute(0, &mut manager, worker);
+        assert_eq!(connection.all_responses(), vec![Response::Ack]);
+        assert!(manager.schema("labels").is_err());
+        assert!(manager.schemas.get("labels").is_none());
+    });
+}
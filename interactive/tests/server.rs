@@ -0,0 +1,224 @@
+extern crate interactive;
+
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::Child;
+use std::time::Duration;
+
+use interactive::{Command, Plan, Response, Schema};
+use interactive::concrete::{Session, Value, Type};
+use interactive::wal::CommandLog;
+
+/// A client connected to a server process.
+struct Client {
+    session: Session<TcpStream>,
+    responses: TcpStream,
+}
+
+impl Client {
+    /// Connects to a server process, waiting for it to start listening.
+    fn connect(address: &str) -> Self {
+        for _ in 0 .. 100 {
+            if let Ok(socket) = TcpStream::connect(address) {
+                let responses = socket.try_clone().expect("failed to clone socket");
+                return Client { session: Session::new(socket), responses };
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        panic!("failed to connect to {}", address);
+    }
+    /// Issues a command.
+    fn issue<C: Into<Command<Value>>>(&mut self, command: C) {
+        self.session.issue(command);
+    }
+    /// Awaits the next response.
+    fn response(&mut self) -> Response<Value> {
+        Response::deserialize_from(&mut self.responses).expect("failed to read response")
+    }
+}
+
+/// The processes of a server, each running the `server` binary, which are killed if not shut down.
+struct Server {
+    processes: Vec<Child>,
+    addresses: Vec<String>,
+    hostfile: PathBuf,
+}
+
+impl Server {
+    /// Starts a server of `processes` processes, each with two workers and the arguments in `args`.
+    fn start(processes: usize, args: &[Vec<String>]) -> Self {
+
+        // Ports chosen by the system, for timely's connections and for clients.
+        let ports = {
+            let listeners = (0 .. 2 * processes).map(|_| TcpListener::bind("127.0.0.1:0").expect("failed to bind")).collect::<Vec<_>>();
+            listeners.iter().map(|listener| listener.local_addr().expect("no address").port()).collect::<Vec<_>>()
+        };
+
+        let hostfile = std::env::temp_dir().join(format!("interactive-server-{}-{}.txt", std::process::id(), ports[0]));
+        let hosts = ports[.. processes].iter().map(|port| format!("127.0.0.1:{}\n", port)).collect::<String>();
+        std::fs::write(&hostfile, hosts).expect("failed to write hostfile");
+
+        let addresses = ports[processes ..].iter().map(|port| format!("127.0.0.1:{}", port)).collect::<Vec<_>>();
+        let processes =
+        (0 .. processes)
+            .map(|process| {
+                std::process::Command::new(env!("CARGO_BIN_EXE_server"))
+                    .args(&["-w", "2"])
+                    .arg("-n").arg(processes.to_string())
+                    .arg("-p").arg(process.to_string())
+                    .arg("-h").arg(&hostfile)
+                    .args(&["--bind", &addresses[process]])
+                    .args(&args[process])
+                    .spawn()
+                    .expect("failed to start server")
+            })
+            .collect();
+
+        Server { processes, addresses, hostfile }
+    }
+    /// Connects a client to a process.
+    fn connect(&self, process: usize) -> Client {
+        Client::connect(&self.addresses[process])
+    }
+    /// Awaits the processes, which should have been shut down.
+    fn join(mut self) {
+        for process in self.processes.iter_mut() {
+            assert!(process.wait().expect("failed to await server").success());
+        }
+        self.processes.clear();
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        for process in self.processes.iter_mut() {
+            let _ = process.kill();
+        }
+        let _ = std::fs::remove_file(&self.hostfile);
+    }
+}
+
+fn edge(src: usize, dst: usize) -> Vec<Value> {
+    vec![Value::Usize(src), Value::Usize(dst)]
+}
+
+#[test]
+fn multiple_processes() {
+
+    let server = Server::start(2, &[Vec::new(), Vec::new()]);
+    let mut client0 = server.connect(0);
+    let mut client1 = server.connect(1);
+
+    // Commands from either process are executed by all workers, in sequence.
+    client1.issue(Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2)]));
+    assert_eq!(client1.response(), Response::Ack);
+
+    client0.issue(
+        Plan::source("edges")
+            .join(Plan::source("edges"), vec![(1, 0)])
+            .project(vec![1, 2])
+            .into_rule("two_hop"));
+    assert_eq!(client0.response(), Response::Ack);
+
+    client1.issue(Command::UpdateInput("edges".to_string(), vec![(edge(2, 3), Duration::from_secs(0), 1)]));
+    assert_eq!(client1.response(), Response::Ack);
+
+    // Errors are returned to the issuing client only.
+    client1.issue(Command::UpdateInput("edges".to_string(), vec![(vec![Value::Usize(3)], Duration::from_secs(0), 1)]));
+    match client1.response() {
+        Response::Error(_) => { },
+        response => panic!("expected error, found {:?}", response),
+    }
+    client0.issue(Command::DropQuery("three_hop".to_string()));
+    match client0.response() {
+        Response::Error(_) => { },
+        response => panic!("expected error, found {:?}", response),
+    }
+
+    client0.issue(Command::AdvanceTime(Duration::from_secs(2)));
+    assert_eq!(client0.response(), Response::Ack);
+
+    // Results are returned through the process connected to the client, once complete.
+    client1.issue(Command::Peek("two_hop".to_string(), Duration::from_secs(2)));
+    assert_eq!(client1.response(), Response::Ack);
+    client0.issue(Command::AdvanceTime(Duration::from_secs(3)));
+    assert_eq!(client0.response(), Response::Ack);
+    assert_eq!(
+        client1.response(),
+        Response::Snapshot("two_hop".to_string(), Duration::from_secs(2), vec![(edge(0, 2), 1), (edge(1, 3), 1)]),
+    );
+
+    client0.issue(Command::Shutdown);
+    assert_eq!(client0.response(), Response::Ack);
+
+    server.join();
+}
+
+#[test]
+fn restart_from_unequal_logs() {
+
+    let logs = (0 .. 2).map(|process| std::env::temp_dir().join(format!("interactive-server-{}-{}.log", std::process::id(), process))).collect::<Vec<_>>();
+    for log in logs.iter() {
+        let _ = std::fs::remove_file(log);
+    }
+    let args = |compact: &[bool]| {
+        logs.iter().zip(compact.iter()).map(|(log, &compact)| {
+            let mut args = vec!["--log".to_string(), log.to_str().expect("invalid path").to_string()];
+            if compact { args.push("--compact".to_string()); }
+            args
+        })
+        .collect::<Vec<_>>()
+    };
+
+    let server = Server::start(2, &args(&[false, false]));
+    let mut client0 = server.connect(0);
+
+    client0.issue(Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2)]));
+    assert_eq!(client0.response(), Response::Ack);
+    client0.issue(
+        Plan::source("edges")
+            .join(Plan::source("edges"), vec![(1, 0)])
+            .project(vec![1, 2])
+            .into_rule("two_hop"));
+    assert_eq!(client0.response(), Response::Ack);
+    client0.issue(Command::AdvanceTime(Duration::from_secs(1)));
+    assert_eq!(client0.response(), Response::Ack);
+    client0.issue(Command::UpdateInput("edges".to_string(), vec![(edge(2, 3), Duration::from_secs(1), 1)]));
+    assert_eq!(client0.response(), Response::Ack);
+    client0.issue(Command::Shutdown);
+    assert_eq!(client0.response(), Response::Ack);
+    server.join();
+
+    let (_log, logged) = CommandLog::<Value>::open(&logs[0]).expect("failed to open log");
+    assert_eq!(logged.len(), 4);
+
+    // Process 1 loses its last command, as if it crashed before logging it, and compacts its log.
+    CommandLog::create(&logs[1], &logged[.. 3]).expect("failed to write log");
+
+    let server = Server::start(2, &args(&[false, true]));
+    let mut client0 = server.connect(0);
+    let mut client1 = server.connect(1);
+
+    // All workers replay the log of process 0, including the update process 1 did not log.
+    client1.issue(Command::Peek("two_hop".to_string(), Duration::from_secs(1)));
+    assert_eq!(client1.response(), Response::Ack);
+    client0.issue(Command::AdvanceTime(Duration::from_secs(2)));
+    assert_eq!(client0.response(), Response::Ack);
+    assert_eq!(
+        client1.response(),
+        Response::Snapshot("two_hop".to_string(), Duration::from_secs(1), vec![(edge(0, 2), 1), (edge(1, 3), 1)]),
+    );
+
+    client0.issue(Command::Shutdown);
+    assert_eq!(client0.response(), Response::Ack);
+    server.join();
+
+    // Both logs are the uncompacted log of process 0, followed by the commands since.
+    let mut expected = logged;
+    expected.push(Command::AdvanceTime(Duration::from_secs(2)));
+    for log in logs.iter() {
+        let (_log, commands) = CommandLog::<Value>::open(log).expect("failed to open log");
+        assert_eq!(commands, expected);
+        std::fs::remove_file(log).expect("failed to remove log");
+    }
+}
@@ -0,0 +1,192 @@
+extern crate timely;
+extern crate interactive;
+
+mod common;
+
+use std::time::Duration;
+
+use interactive::{Command, Manager, Plan, Response, Schema};
+use interactive::plan::{Aggregation, Predicate};
+use interactive::concrete::{Value, Type, Expression, BinaryOp};
+use interactive::sql::{compile, Catalog};
+
+use interactive::concrete::Expression::Column;
+
+use common::{Connection, edge};
+
+/// A catalog with a table of edges, created by SQL.
+fn catalog() -> Catalog {
+    let mut catalog = Catalog::new();
+    compile("CREATE TABLE edges (src INT, dst INT)", &mut catalog).expect("failed to create table");
+    catalog
+}
+
+/// Compiles a view definition, returning the plan of its single rule.
+fn view(statement: &str) -> Result<Plan<Value>, String> {
+    let mut catalog = catalog();
+    let mut commands = compile(statement, &mut catalog)?;
+    assert_eq!(commands.len(), 1);
+    match commands.pop() {
+        Some(Command::Query(mut query)) => {
+            assert_eq!(query.rules.len(), 1);
+            let rule = query.rules.pop().expect("one rule");
+            assert_eq!(rule.name, "out");
+            Ok(rule.plan)
+        },
+        command => panic!("unexpected command: {:?}", command),
+    }
+}
+
+#[test]
+fn create_table() {
+    let mut catalog = Catalog::new();
+    let commands = compile("CREATE TABLE people (name STRING, age INT, active BOOL, since DURATION);", &mut catalog);
+    let schema = Schema::new(vec![("name", Type::String), ("age", Type::Usize), ("active", Type::Bool), ("since", Type::Duration)]);
+    assert_eq!(commands, Ok(vec![Command::CreateInput("people".to_string(), schema, Vec::new())]));
+    assert_eq!(catalog.get("people"), Some(&vec!["name".to_string(), "age".to_string(), "active".to_string(), "since".to_string()]));
+
+    assert_eq!(compile("CREATE TABLE bad (x INT, x INT)", &mut catalog), Err("duplicate column: x".to_string()));
+}
+
+#[test]
+fn select_where() {
+    let expected =
+    Plan::source("edges")
+        .filter(Predicate::Expression(Expression::Binary(BinaryOp::Eq, Box::new(Column(0)), Box::new(Expression::Literal(Value::Usize(1))))))
+        .map(vec![Column(1)]);
+    assert_eq!(view("CREATE VIEW out AS SELECT dst FROM edges WHERE src = 1"), Ok(expected));
+
+    // Queries that are not views are installed as rules named by their text, whose results are printed.
+    let statement = "SELECT * FROM edges";
+    let expected = Plan::source("edges").map(vec![Column(0), Column(1)]).inspect(statement).into_rule(statement);
+    assert_eq!(compile(statement, &mut catalog()), Ok(vec![Command::from(expected)]));
+}
+
+#[test]
+fn multiway_join() {
+    // Triangles, whose equalities between columns of different relations drive the join.
+    let expected =
+    Plan::multiway_join(
+        vec![Plan::source("edges"), Plan::source("edges"), Plan::source("edges")],
+        vec![vec![(1, 0), (0, 1)], vec![(1, 1), (1, 2)], vec![(0, 0), (0, 2)]],
+        vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)],
+    )
+    .map(vec![Column(0), Column(1), Column(3)]);
+    assert_eq!(
+        view("CREATE VIEW out AS SELECT a.src, a.dst, b.dst FROM edges AS a JOIN edges AS b ON a.dst = b.src, edges c WHERE b.dst = c.dst AND a.src = c.src"),
+        Ok(expected),
+    );
+
+    // Conditions other than equalities of columns of different relations filter the joined tuples.
+    let expected =
+    Plan::multiway_join(
+        vec![Plan::source("edges"), Plan::source("edges")],
+        vec![vec![(1, 0), (0, 1)]],
+        vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+    )
+    .filter(Predicate::Expression(Expression::Binary(BinaryOp::Ne, Box::new(Column(0)), Box::new(Column(3)))))
+    .map(vec![Column(0), Column(3)]);
+    assert_eq!(view("CREATE VIEW out AS SELECT a.src, b.dst FROM edges a INNER JOIN edges b ON a.dst = b.src WHERE a.src <> b.dst"), Ok(expected));
+}
+
+#[test]
+fn group_by() {
+    let expected =
+    Plan::source("edges")
+        .map(vec![Column(0), Column(1), Column(1), Column(1)])
+        .aggregate(vec![0], vec![Aggregation::Count, Aggregation::Sum(1), Aggregation::Min(2), Aggregation::Max(3)])
+        .map(vec![Column(0), Column(1), Column(2), Column(3), Column(4)]);
+    assert_eq!(view("CREATE VIEW out AS SELECT src, COUNT(*), SUM(dst), MIN(dst), MAX(dst) FROM edges GROUP BY src"), Ok(expected));
+
+    assert_eq!(
+        view("CREATE VIEW out AS SELECT src, dst FROM edges GROUP BY src"),
+        Err("column dst must appear in GROUP BY or in an aggregate".to_string()),
+    );
+}
+
+#[test]
+fn union_distinct() {
+    let expected = Plan::source("edges").map(vec![Column(0)]).concat(Plan::source("edges").map(vec![Column(1)])).distinct();
+    assert_eq!(view("CREATE VIEW out AS SELECT src FROM edges UNION SELECT dst FROM edges"), Ok(expected));
+
+    let expected = Plan::source("edges").map(vec![Column(0)]).concat(Plan::source("edges").map(vec![Column(1)]));
+    assert_eq!(view("CREATE VIEW out AS SELECT src FROM edges UNION ALL SELECT dst FROM edges"), Ok(expected));
+
+    let expected = Plan::source("edges").map(vec![Column(0)]).distinct();
+    assert_eq!(view("CREATE VIEW out AS SELECT DISTINCT src FROM edges"), Ok(expected));
+
+    assert_eq!(
+        view("CREATE VIEW out AS SELECT src FROM edges UNION SELECT src, dst FROM edges"),
+        Err("UNION of queries with 1 and 2 columns".to_string()),
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(view("CREATE VIEW out AS SELECT missing FROM edges"), Err("unknown column: missing".to_string()));
+    assert_eq!(view("CREATE VIEW out AS SELECT src FROM edges AS a JOIN edges AS b ON a.dst = b.src"), Err("ambiguous column: src".to_string()));
+    assert_eq!(view("CREATE VIEW out AS SELECT src FROM nodes"), Err("unknown relation: nodes".to_string()));
+    assert_eq!(
+        view("CREATE VIEW out AS SELECT a.src FROM edges AS a, edges AS b"),
+        Err("every relation must be joined to the others by an equality of columns".to_string()),
+    );
+    assert_eq!(view("CREATE VIEW out AS SELECT src FROM edges WHERE src = #1"), Err("unexpected character: '#'".to_string()));
+    assert_eq!(view("CREATE VIEW out AS SELECT src FROM edges WHERE name = 'unterminated"), Err("unterminated string".to_string()));
+    assert!(view("CREATE VIEW out AS SELECT FROM edges").is_err());
+    assert!(view("CREATE VIEW out AS SELECT src FROM edges extra tokens").is_err());
+}
+
+#[test]
+fn execute_sql() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        Command::Sql("CREATE TABLE edges (src INT, dst INT)".to_string()).execute(0, &mut manager, worker);
+        Command::UpdateInput("edges".to_string(), vec![(edge(0, 1), Duration::from_secs(0), 1), (edge(1, 2), Duration::from_secs(0), 1), (edge(1, 3), Duration::from_secs(0), 1)])
+            .execute(0, &mut manager, worker);
+        Command::Sql("CREATE VIEW two_hop AS SELECT a.src, b.dst FROM edges AS a JOIN edges AS b ON a.dst = b.src".to_string())
+            .execute(0, &mut manager, worker);
+        Command::Sql("CREATE VIEW broken AS SELECT missing FROM edges".to_string()).execute(0, &mut manager, worker);
+        Command::Peek("two_hop".to_string(), Duration::from_secs(0)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(Duration::from_secs(1)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        // The snapshot follows the acknowledgement of the peek, but may precede that of advancing time.
+        let mut responses = connection.all_responses();
+        let snapshot = responses.iter().position(|response| match response { Response::Snapshot(..) => true, _ => false }).expect("snapshot not received");
+        assert_eq!(
+            responses.remove(snapshot),
+            Response::Snapshot("two_hop".to_string(), Duration::from_secs(0), vec![(edge(0, 2), 1), (edge(0, 3), 1)]),
+        );
+        assert_eq!(responses, vec![
+            Response::Ack,
+            Response::Ack,
+            Response::Ack,
+            Response::Error("SQL error: unknown column: missing".to_string()),
+            Response::Ack,
+            Response::Ack,
+        ]);
+
+        // Views are published as rules, and failed statements are not.
+        assert!(manager.traces.is_published("two_hop"));
+        assert!(!manager.traces.is_published("broken"));
+        assert!(manager.catalog.contains_key("two_hop"));
+        assert!(!manager.catalog.contains_key("broken"));
+
+        // A view that compiles but cannot be installed is not recorded in the catalog.
+        Command::Sql("CREATE VIEW mistyped AS SELECT src + 'text' FROM edges".to_string()).execute(0, &mut manager, worker);
+        assert!(!manager.traces.is_published("mistyped"));
+        assert!(!manager.catalog.contains_key("mistyped"));
+
+        // Dropping a view removes it from the catalog, so that SQL statements can no longer use it.
+        Command::DropQuery("two_hop".to_string()).execute(0, &mut manager, worker);
+        assert!(!manager.catalog.contains_key("two_hop"));
+        assert!(manager.catalog.contains_key("edges"));
+        let mut catalog = manager.catalog.clone();
+        assert_eq!(compile("SELECT * FROM two_hop", &mut catalog), Err("unknown relation: two_hop".to_string()));
+    });
+}
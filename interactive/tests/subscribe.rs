@@ -0,0 +1,111 @@
+extern crate timely;
+extern crate interactive;
+
+mod common;
+
+use interactive::{Command, Manager, Response, Schema};
+use interactive::concrete::{Value, Type};
+
+use common::{Connection, edge, secs};
+
+#[test]
+fn subscribe() {
+
+    timely::execute_directly(|worker| {
+
+        let connection0 = Connection::new();
+        let connection1 = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection0.clone());
+        manager.clients.insert(1, connection1.clone());
+
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2)])
+            .execute(0, &mut manager, worker);
+        Command::Subscribe("edges".to_string()).execute(0, &mut manager, worker);
+        Command::Subscribe("edges".to_string()).execute(1, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        // Subscribers receive the initial contents, and then consolidated changes at complete times.
+        let initial = Response::Updates("edges".to_string(), vec![(edge(0, 1), secs(0), 1), (edge(1, 2), secs(0), 1)]);
+        assert_eq!(connection0.responses(), vec![initial.clone()]);
+        assert_eq!(connection1.responses(), vec![initial]);
+
+        Command::UpdateInput("edges".to_string(), vec![(edge(0, 1), secs(1), -1), (edge(2, 3), secs(1), 1), (edge(2, 3), secs(1), 1), (edge(2, 3), secs(1), -1)])
+            .execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        let changes = Response::Updates("edges".to_string(), vec![(edge(0, 1), secs(1), -1), (edge(2, 3), secs(1), 1)]);
+        assert_eq!(connection0.responses(), vec![changes.clone()]);
+        assert_eq!(connection1.responses(), vec![changes]);
+
+        // A subscription whose responses fail to send shuts down its dataflow.
+        connection0.close();
+        Command::UpdateInput("edges".to_string(), vec![(edge(3, 4), secs(2), 1)]).execute(1, &mut manager, worker);
+        Command::AdvanceTime(secs(3)).execute(1, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert!(manager.subscriptions[&0].iter().all(|(_name, subscription)| subscription.borrow().is_none()));
+        assert!(manager.subscriptions[&1].iter().all(|(_name, subscription)| subscription.borrow().is_some()));
+        assert_eq!(connection1.responses(), vec![Response::Updates("edges".to_string(), vec![(edge(3, 4), secs(2), 1)])]);
+
+        // A client that disconnects has its subscriptions shut down, and receives no further changes.
+        Command::Disconnect.execute(0, &mut manager, worker);
+        Command::Disconnect.execute(1, &mut manager, worker);
+        assert!(manager.subscriptions.is_empty());
+
+        Command::UpdateInput("edges".to_string(), vec![(edge(4, 5), secs(3), 1)]).execute(1, &mut manager, worker);
+        Command::AdvanceTime(secs(4)).execute(1, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert_eq!(connection1.responses(), vec![]);
+
+        // Subscriptions to unknown rules are errors.
+        Command::Subscribe("missing".to_string()).execute(1, &mut manager, worker);
+        assert_eq!(connection1.responses(), vec![Response::Error("Rule not found: \"missing\"".to_string())]);
+        assert!(manager.subscriptions.is_empty());
+    });
+}
+
+#[test]
+fn peek() {
+
+    timely::execute_directly(|worker| {
+
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        Command::CreateInput("edges".to_string(), Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)]), vec![edge(0, 1), edge(1, 2)])
+            .execute(0, &mut manager, worker);
+
+        // Peeks at the current and a future time are answered once their times are complete.
+        Command::Peek("edges".to_string(), secs(0)).execute(0, &mut manager, worker);
+        Command::Peek("edges".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert_eq!(connection.responses(), vec![
+            Response::Snapshot("edges".to_string(), secs(0), vec![(edge(0, 1), 1), (edge(1, 2), 1)]),
+        ]);
+
+        Command::UpdateInput("edges".to_string(), vec![(edge(0, 1), secs(1), -1), (edge(2, 3), secs(1), 2)]).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert_eq!(connection.responses(), vec![
+            Response::Snapshot("edges".to_string(), secs(1), vec![(edge(1, 2), 1), (edge(2, 3), 2)]),
+        ]);
+
+        // Times before the frontier to which the traces have advanced are no longer available.
+        Command::Peek("edges".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::Peek("missing".to_string(), secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        assert_eq!(connection.responses(), vec![
+            Response::Error("Peek time 1s for \"edges\" is no longer available".to_string()),
+            Response::Error("Rule not found: \"missing\"".to_string()),
+        ]);
+    });
+}
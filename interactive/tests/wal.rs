@@ -0,0 +1,119 @@
+extern crate timely;
+extern crate interactive;
+
+mod common;
+
+use interactive::{Command, Manager, Plan, Response, Schema};
+use interactive::concrete::{Value, Type};
+use interactive::wal::{CommandLog, compact};
+
+use common::{Connection, edge, secs};
+
+fn edges() -> Schema<Value> {
+    Schema::new(vec![("src", Type::Usize), ("dst", Type::Usize)])
+}
+
+fn reversed() -> Command<Value> {
+    Command::from(Plan::source("edges").project(vec![1, 0]).into_rule("reversed"))
+}
+
+#[test]
+fn compaction() {
+
+    let nodes = Schema::new(vec![("node", Type::Usize)]);
+
+    let commands = vec![
+        Command::CreateInput("edges".to_string(), edges(), vec![edge(0, 1), edge(1, 2)]),
+        reversed(),
+        Command::UpdateInput("edges".to_string(), vec![(edge(1, 2), secs(0), -1), (edge(2, 3), secs(0), 1)]),
+        Command::AdvanceTime(secs(1)),
+        Command::UpdateInput("edges".to_string(), vec![(edge(3, 4), secs(1), 1), (edge(4, 5), secs(5), 1)]),
+        Command::AdvanceTime(secs(2)),
+        Command::CreateInput("nodes".to_string(), nodes.clone(), vec![]),
+        Command::UpdateInput("nodes".to_string(), vec![(vec![Value::Usize(0)], secs(2), 1)]),
+        Command::UpdateInput("nodes".to_string(), vec![(vec![Value::Usize(1)], secs(3), 1)]),
+        Command::CloseInput("nodes".to_string()),
+        Command::AdvanceTime(secs(3)),
+    ];
+
+    // Updates before the final frontier are advanced and consolidated, and later updates retain their times.
+    let compacted = vec![
+        Command::CreateInput("edges".to_string(), edges(), vec![]),
+        reversed(),
+        Command::CreateInput("nodes".to_string(), nodes.clone(), vec![]),
+        Command::UpdateInput("nodes".to_string(), vec![(vec![Value::Usize(0)], secs(0), 1), (vec![Value::Usize(1)], secs(3), 1)]),
+        Command::CloseInput("nodes".to_string()),
+        Command::UpdateInput("edges".to_string(), vec![(edge(0, 1), secs(0), 1), (edge(2, 3), secs(0), 1), (edge(3, 4), secs(0), 1), (edge(4, 5), secs(5), 1)]),
+        Command::AdvanceTime(secs(3)),
+    ];
+
+    assert_eq!(compact(commands), compacted);
+}
+
+#[test]
+fn replay() {
+
+    let path = std::env::temp_dir().join(format!("interactive-wal-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // Executed commands are logged, unless they fail or only respond to the client.
+    let logged = path.clone();
+    timely::execute_directly(move |worker| {
+        let mut manager = Manager::<Value>::new();
+        let (log, commands) = CommandLog::open(&logged).expect("failed to open log");
+        assert!(commands.is_empty());
+        manager.log = Some(log);
+
+        Command::CreateInput("edges".to_string(), edges(), vec![edge(0, 1), edge(1, 2)]).execute(0, &mut manager, worker);
+        reversed().execute(0, &mut manager, worker);
+        Command::UpdateInput("edges".to_string(), vec![(edge(2, 3), secs(0), 1)]).execute(0, &mut manager, worker);
+        Command::UpdateInput("edges".to_string(), vec![(vec![Value::Usize(3)], secs(0), 1)]).execute(0, &mut manager, worker);
+        Command::Peek("reversed".to_string(), secs(0)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(1)).execute(0, &mut manager, worker);
+    });
+
+    let expected = vec![
+        Command::CreateInput("edges".to_string(), edges(), vec![edge(0, 1), edge(1, 2)]),
+        reversed(),
+        Command::UpdateInput("edges".to_string(), vec![(edge(2, 3), secs(0), 1)]),
+        Command::AdvanceTime(secs(1)),
+    ];
+
+    // A partially written command is discarded.
+    let length = std::fs::metadata(&path).expect("log not found").len();
+    let mut bytes = Vec::new();
+    Command::<Value>::CloseInput("edges".to_string()).serialize_into(&mut bytes);
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .expect("failed to open log")
+        .write_all(&bytes[.. bytes.len() - 1])
+        .expect("failed to write log");
+
+    let (_log, commands) = CommandLog::<Value>::open(&path).expect("failed to open log");
+    assert_eq!(commands, expected);
+    assert_eq!(std::fs::metadata(&path).expect("log not found").len(), length);
+
+    // Replaying the log restores inputs and rules.
+    timely::execute_directly(move |worker| {
+        let connection = Connection::new();
+        let mut manager = Manager::<Value>::new();
+        manager.clients.insert(0, connection.clone());
+
+        for command in expected.iter() {
+            command.clone().execute(1, &mut manager, worker);
+        }
+
+        Command::Peek("reversed".to_string(), secs(1)).execute(0, &mut manager, worker);
+        Command::AdvanceTime(secs(2)).execute(0, &mut manager, worker);
+        for _ in 0 .. 100 { worker.step(); }
+
+        // The snapshot may be sent while advancing time, before that command is acknowledged.
+        let responses = connection.all_responses();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0], Response::Ack);
+        assert!(responses.contains(&Response::Snapshot("reversed".to_string(), secs(1), vec![(edge(1, 0), 1), (edge(2, 1), 1), (edge(3, 2), 1)])));
+    });
+
+    std::fs::remove_file(&path).expect("failed to remove log");
+}
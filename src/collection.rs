@@ -483,7 +483,107 @@ impl<G: Scope, D: Data, R: Semigroup> Collection<G, D, R> where G::Timestamp: Da
     {
         use operators::consolidate::Consolidate;
         self.consolidate()
-            .inspect(|x| panic!("Assertion failed: non-empty collection: {:?}", x));
+            .inspect_batch(|_time, updates| {
+                let mut message = String::from("Assertion failed: non-empty collection:");
+                for (data, time, diff) in updates.iter() {
+                    message.push_str(&format!("\n    {:?} at time {:?} with multiplicity {:?}", data, time, diff));
+                }
+                panic!("{}", message);
+            });
+    }
+
+    /// Assert if the accumulated contents of the collection at `time` differ from `expected`.
+    ///
+    /// The updates at times less or equal to `time` are gathered at the first worker, and compared
+    /// with `expected` once the collection is complete through `time`. Records in `expected` may be
+    /// repeated, and their multiplicities are accumulated. On failure, the assertion reports each
+    /// record whose multiplicities differ, with its expected and observed multiplicities. Only the
+    /// first worker's `expected` is used, and the other workers may supply anything.
+    ///
+    /// As with `assert_empty`, the test is only applied as the computation is run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let (mut input, data) = scope.new_collection();
+    ///
+    ///         data.assert_contents_at(0, vec![(1, 1), (2, 1)]);
+    ///         data.assert_contents_at(1, vec![(2, 1), (3, 2)]);
+    ///
+    ///         input.insert(1);
+    ///         input.insert(2);
+    ///         input.advance_to(1);
+    ///         input.remove(1);
+    ///         input.update(3, 2);
+    ///     });
+    /// }
+    /// ```
+    pub fn assert_contents_at<I>(&self, time: G::Timestamp, expected: I)
+    where I: IntoIterator<Item=(D, R)>,
+          D: ::ExchangeData,
+          R: ::ExchangeData,
+          G::Timestamp: Lattice+Ord,
+    {
+        use std::cmp::Ordering;
+        use timely::dataflow::channels::pact::Exchange;
+
+        let mut expected = expected.into_iter().collect::<Vec<_>>();
+        ::consolidation::consolidate(&mut expected);
+
+        let mut accumulated = Vec::new();
+        let mut checked = false;
+        let mut buffer = Vec::new();
+
+        self.inner.sink(Exchange::new(|_: &(D, G::Timestamp, R)| 0), "AssertContentsAt", move |input| {
+
+            input.for_each(|_capability, data| {
+                data.swap(&mut buffer);
+                for (data, update_time, diff) in buffer.drain(..) {
+                    if update_time.less_equal(&time) {
+                        accumulated.push((data, diff));
+                    }
+                }
+            });
+
+            if !checked && !input.frontier().less_equal(&time) {
+                checked = true;
+                ::consolidation::consolidate(&mut accumulated);
+                if accumulated != expected {
+                    let mut message = format!("Assertion failed: contents differ at time {:?}:", time);
+                    let mut actual = accumulated.iter().peekable();
+                    let mut wanted = expected.iter().peekable();
+                    loop {
+                        let order = match (actual.peek(), wanted.peek()) {
+                            (Some(a), Some(w)) => Some(a.0.cmp(&w.0)),
+                            (Some(_), None) => Some(Ordering::Less),
+                            (None, Some(_)) => Some(Ordering::Greater),
+                            (None, None) => None,
+                        };
+                        let (found, sought) = match order {
+                            Some(Ordering::Equal) => (actual.next(), wanted.next()),
+                            Some(Ordering::Less) => (actual.next(), None),
+                            Some(Ordering::Greater) => (None, wanted.next()),
+                            None => break,
+                        };
+                        let data = found.or(sought).map(|x| &x.0).unwrap();
+                        let found = found.map(|x| &x.1);
+                        let sought = sought.map(|x| &x.1);
+                        if found != sought {
+                            message.push_str(&format!("\n    {:?}: expected multiplicity {:?}, found {:?}", data, sought, found));
+                        }
+                    }
+                    panic!("{}", message);
+                }
+            }
+        });
     }
 
     /// The scope containing the underlying timely dataflow stream.
@@ -601,9 +701,68 @@ impl<G: Scope, D: Data, R: Abelian> Collection<G, D, R> where G::Timestamp: Data
           R: ::ExchangeData+Hashable,
           G::Timestamp: Lattice+Ord
     {
-        self.negate()
-            .concat(other)
-            .assert_empty();
+        use operators::consolidate::Consolidate;
+        self.concat(&other.negate())
+            .consolidate()
+            .inspect_batch(|_time, updates| {
+                let mut message = String::from("Assertion failed: collections differ (multiplicities in self minus those in other):");
+                for (data, time, diff) in updates.iter() {
+                    message.push_str(&format!("\n    {:?} at time {:?}: {:?}", data, time, diff));
+                }
+                panic!("{}", message);
+            });
+    }
+
+    /// Assert if any record ever has a negative accumulated multiplicity.
+    ///
+    /// Negative multiplicities usually indicate a retraction of a record that was never inserted,
+    /// or was retracted twice. The check accumulates the consolidated updates of each record at each
+    /// time, using the `sink_consolidated` operator, and reports the first record, time, and negative
+    /// multiplicity found. All updates are retained, and so this should be used only in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let data = scope.new_collection_from(1 .. 10).1;
+    ///
+    ///         // removing odd records leaves only non-negative multiplicities.
+    ///         data.concat(&data.filter(|x| x % 2 == 1).negate())
+    ///             .assert_no_negative_multiplicities();
+    ///     });
+    /// }
+    /// ```
+    pub fn assert_no_negative_multiplicities(&self)
+    where D: ::ExchangeData+Hashable,
+          R: ::ExchangeData,
+          G::Timestamp: Lattice+Ord
+    {
+        use std::collections::BTreeMap;
+        use operators::sink::Sink;
+
+        let mut history = BTreeMap::<D, Vec<(G::Timestamp, R)>>::new();
+        self.sink_consolidated(move |time, updates| {
+            for (data, diff) in updates {
+                let times = history.entry(data.clone()).or_insert(Vec::new());
+                times.push((time.clone(), diff));
+                let mut sum = R::zero();
+                for (update_time, update_diff) in times.iter() {
+                    if update_time.less_equal(time) {
+                        sum += update_diff;
+                    }
+                }
+                if sum < R::zero() {
+                    panic!("Assertion failed: {:?} has negative multiplicity {:?} at time {:?}", data, sum, time);
+                }
+            }
+        });
     }
 }
 
@@ -4,7 +4,7 @@
 //! integers) where we want to reduce the collection to the point that each record occurs
 //! at most once, with the accumulated weights. These methods supply that functionality.
 
-use crate::difference::Semigroup;
+use crate::difference::{Semigroup, CheckedSemigroup};
 
 /// Sorts and consolidates `vec`.
 pub fn consolidate<T: Ord, R: Semigroup>(vec: &mut Vec<(T, R)>) {
@@ -136,3 +136,73 @@ pub fn consolidate_updates_slice<D: Ord, T: Ord, R: Semigroup>(slice: &mut [(D,
         slice.len()
     }
 }
+
+/// An update whose accumulation overflowed during checked consolidation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow<D, T, R> {
+    /// The record whose differences overflowed.
+    pub data: D,
+    /// The time at which the differences overflowed.
+    pub time: T,
+    /// The differences accumulated before the overflowing update.
+    pub accumulated: R,
+    /// The difference whose addition overflowed.
+    pub update: R,
+}
+
+impl<D: std::fmt::Debug, T: std::fmt::Debug, R: std::fmt::Debug> std::fmt::Display for Overflow<D, T, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "difference overflow for {:?} at {:?}: {:?} + {:?}", self.data, self.time, self.accumulated, self.update)
+    }
+}
+
+/// Sorts and consolidates `vec`, reporting the first update whose accumulation overflows.
+///
+/// Should an overflow be detected, the contents of `vec` are left in an unspecified order.
+pub fn consolidate_updates_checked<D: Ord+Clone, T: Ord+Clone, R: CheckedSemigroup>(vec: &mut Vec<(D, T, R)>) -> Result<(), Overflow<D, T, R>> {
+    let length = consolidate_updates_slice_checked(&mut vec[..])?;
+    vec.truncate(length);
+    Ok(())
+}
+
+/// Sorts and consolidates a slice, returning the valid prefix length or the first update whose accumulation overflows.
+pub fn consolidate_updates_slice_checked<D: Ord+Clone, T: Ord+Clone, R: CheckedSemigroup>(slice: &mut [(D, T, R)]) -> Result<usize, Overflow<D, T, R>> {
+
+    if slice.len() > 1 {
+
+        slice.sort_unstable_by(|x,y| (&x.0, &x.1).cmp(&(&y.0, &y.1)));
+
+        let mut offset = 0;
+        for index in 1 .. slice.len() {
+
+            // LOOP INVARIANT: offset < index
+            let (prefix, suffix) = slice.split_at_mut(index);
+            let (prev, next) = (&mut prefix[offset], &suffix[0]);
+
+            if prev.0 == next.0 && prev.1 == next.1 {
+                if !prev.2.checked_add_assign(&next.2) {
+                    return Err(Overflow {
+                        data: prev.0.clone(),
+                        time: prev.1.clone(),
+                        accumulated: prev.2.clone(),
+                        update: next.2.clone(),
+                    });
+                }
+            }
+            else {
+                if !prev.2.is_zero() {
+                    offset += 1;
+                }
+                slice.swap(offset, index);
+            }
+        }
+        if !slice[offset].2.is_zero() {
+            offset += 1;
+        }
+
+        Ok(offset)
+    }
+    else {
+        Ok(slice.len())
+    }
+}
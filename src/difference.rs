@@ -6,7 +6,7 @@
 //! dataflow collections would then track for each record the total of counts and heights, which allows
 //! us to track something like the average.
 
-use std::ops::{AddAssign, Neg, Mul};
+use std::ops::{Add, AddAssign, Neg, Mul};
 use std::iter::Iterator;
 
 use ::Data;
@@ -251,3 +251,507 @@ impl<T: Copy, R: Mul<T>> Mul<T> for DiffVector<R> {
 		DiffVector { buffer }
 	}
 }
+
+/// A difference that accumulates to the minimum of its values.
+///
+/// Multiplication of `Min` differences, as performed by `join`, produces the maximum of the two
+/// values. Together, these form the "bottleneck" semiring used to find paths minimizing their
+/// largest edge weight.
+///
+/// There is no zero element, and updates with `Min` differences are never retired; operators that
+/// need to retract prior outputs may not accept this type.
+#[derive(Abomonation, Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash)]
+pub struct Min<T> {
+	/// The accumulated value.
+	pub value: T,
+}
+
+impl<T> Min<T> {
+	/// Creates a new difference from a value.
+	#[inline] pub fn new(value: T) -> Self { Min { value } }
+}
+
+impl<'a, T: Ord+Clone> AddAssign<&'a Min<T>> for Min<T> {
+	#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+		if rhs.value < self.value {
+			self.value = rhs.value.clone();
+		}
+	}
+}
+
+impl<T: Ord> Mul<Min<T>> for Min<T> {
+	type Output = Min<T>;
+	#[inline] fn mul(self, rhs: Min<T>) -> Self::Output {
+		Min { value: ::std::cmp::max(self.value, rhs.value) }
+	}
+}
+
+impl<T: Ord+Data> Semigroup for Min<T> {
+	#[inline] fn is_zero(&self) -> bool { false }
+}
+
+/// A difference that accumulates to the maximum of its values.
+///
+/// Multiplication of `Max` differences, as performed by `join`, produces the minimum of the two
+/// values. Together, these form the "widest path" semiring used to find paths maximizing their
+/// smallest edge weight.
+///
+/// There is no zero element, and updates with `Max` differences are never retired; operators that
+/// need to retract prior outputs may not accept this type.
+#[derive(Abomonation, Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash)]
+pub struct Max<T> {
+	/// The accumulated value.
+	pub value: T,
+}
+
+impl<T> Max<T> {
+	/// Creates a new difference from a value.
+	#[inline] pub fn new(value: T) -> Self { Max { value } }
+}
+
+impl<'a, T: Ord+Clone> AddAssign<&'a Max<T>> for Max<T> {
+	#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+		if rhs.value > self.value {
+			self.value = rhs.value.clone();
+		}
+	}
+}
+
+impl<T: Ord> Mul<Max<T>> for Max<T> {
+	type Output = Max<T>;
+	#[inline] fn mul(self, rhs: Max<T>) -> Self::Output {
+		Max { value: ::std::cmp::min(self.value, rhs.value) }
+	}
+}
+
+impl<T: Ord+Data> Semigroup for Max<T> {
+	#[inline] fn is_zero(&self) -> bool { false }
+}
+
+/// A difference in the tropical (min, +) semiring.
+///
+/// Differences accumulate to the minimum of their values, and multiplication of differences, as
+/// performed by `join`, adds their values. This is the semiring of shortest paths: a collection of
+/// edges weighted by their lengths can be repeatedly joined and accumulated to find the lengths of
+/// shortest paths.
+///
+/// There is no zero element, and updates with `Tropical` differences are never retired; operators
+/// that need to retract prior outputs may not accept this type.
+#[derive(Abomonation, Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash)]
+pub struct Tropical<T> {
+	/// The accumulated value.
+	pub value: T,
+}
+
+impl<T> Tropical<T> {
+	/// Creates a new difference from a value.
+	#[inline] pub fn new(value: T) -> Self { Tropical { value } }
+}
+
+impl<'a, T: Ord+Clone> AddAssign<&'a Tropical<T>> for Tropical<T> {
+	#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+		if rhs.value < self.value {
+			self.value = rhs.value.clone();
+		}
+	}
+}
+
+impl<T: Add<Output=T>> Mul<Tropical<T>> for Tropical<T> {
+	type Output = Tropical<T>;
+	#[inline] fn mul(self, rhs: Tropical<T>) -> Self::Output {
+		Tropical { value: self.value + rhs.value }
+	}
+}
+
+impl<T: Ord+Data> Semigroup for Tropical<T> {
+	#[inline] fn is_zero(&self) -> bool { false }
+}
+
+/// A non-negative count whose arithmetic saturates at the maximum value of its type.
+///
+/// Saturating addition of unsigned integers is associative and commutative, and so is a valid
+/// difference, though there is no negation. This can be useful to bound the size of counts, for
+/// example when only whether a count exceeds some threshold is of interest, or to avoid overflow.
+#[derive(Abomonation, Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash, Default)]
+pub struct Saturating<T> {
+	/// The accumulated count.
+	pub value: T,
+}
+
+impl<T> Saturating<T> {
+	/// Creates a new difference from a count.
+	#[inline] pub fn new(value: T) -> Self { Saturating { value } }
+}
+
+macro_rules! implement_saturating {
+	($index_type:ty) => (
+		impl<'a> AddAssign<&'a Saturating<$index_type>> for Saturating<$index_type> {
+			#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+				self.value = self.value.saturating_add(rhs.value);
+			}
+		}
+
+		impl Mul<Saturating<$index_type>> for Saturating<$index_type> {
+			type Output = Saturating<$index_type>;
+			#[inline] fn mul(self, rhs: Saturating<$index_type>) -> Self::Output {
+				Saturating { value: self.value.saturating_mul(rhs.value) }
+			}
+		}
+
+		impl Semigroup for Saturating<$index_type> {
+			#[inline] fn is_zero(&self) -> bool { self.value == 0 }
+		}
+
+		impl Monoid for Saturating<$index_type> {
+			#[inline] fn zero() -> Self { Saturating { value: 0 } }
+		}
+	)
+}
+
+implement_saturating!(usize);
+implement_saturating!(u64);
+implement_saturating!(u32);
+implement_saturating!(u16);
+implement_saturating!(u8);
+
+/// A semigroup whose addition can report overflow rather than wrapping.
+///
+/// Consolidation methods like `consolidation::consolidate_updates_checked` use this trait to detect
+/// overflow and report the record whose accumulation overflowed.
+pub trait CheckedSemigroup : Semigroup {
+	/// Adds `rhs` to `self`, returning false and leaving `self` unchanged if the addition overflows.
+	fn checked_add_assign(&mut self, rhs: &Self) -> bool;
+}
+
+/// An integer difference whose arithmetic panics on overflow, in release as well as debug builds.
+///
+/// The primitive integer differences add with `+=`, which wraps silently in release builds; a count
+/// that overflows produces incorrect results without any indication. Using `Checked` differences
+/// instead causes any overflow, whether in consolidation, merging of arrangements, or multiplication
+/// in joins, to panic with the operands.
+///
+/// Arithmetic on differences does not know the records it accumulates, and these panics report
+/// only the differences. Arrangements add the record to the panic message when the overflow occurs
+/// while batching updates, or while merging and compacting batches: the key, and for compaction
+/// also the value. Overflow within a single batch of input updates, and in `consolidate` and
+/// `consolidate_updates`, reports only the differences; the checked consolidation methods,
+/// `consolidate_checked` from the `operators::consolidate` module and
+/// `consolidation::consolidate_updates_checked`, report the record there instead.
+///
+/// # Examples
+///
+/// ```
+/// use differential_dataflow::difference::{Checked, CheckedSemigroup};
+///
+/// let mut count = Checked::new(i8::max_value());
+/// assert!(!count.checked_add_assign(&Checked::new(1)));
+/// assert_eq!(count, Checked::new(127));
+/// ```
+#[derive(Abomonation, Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash, Default)]
+pub struct Checked<T> {
+	/// The accumulated count.
+	pub value: T,
+}
+
+impl<T> Checked<T> {
+	/// Creates a new difference from a count.
+	#[inline] pub fn new(value: T) -> Self { Checked { value } }
+}
+
+macro_rules! implement_checked {
+	($index_type:ty) => (
+		impl CheckedSemigroup for $index_type {
+			#[inline] fn checked_add_assign(&mut self, rhs: &Self) -> bool {
+				self.checked_add(*rhs).map(|sum| *self = sum).is_some()
+			}
+		}
+
+		impl<'a> AddAssign<&'a Checked<$index_type>> for Checked<$index_type> {
+			#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+				if !self.checked_add_assign(rhs) {
+					panic!("difference overflow: {:?} + {:?}", self.value, rhs.value);
+				}
+			}
+		}
+
+		impl Neg for Checked<$index_type> {
+			type Output = Checked<$index_type>;
+			#[inline] fn neg(self) -> Self::Output {
+				match self.value.checked_neg() {
+					Some(value) => Checked { value },
+					None => panic!("difference overflow: -{:?}", self.value),
+				}
+			}
+		}
+
+		impl Mul<Checked<$index_type>> for Checked<$index_type> {
+			type Output = Checked<$index_type>;
+			#[inline] fn mul(self, rhs: Checked<$index_type>) -> Self::Output {
+				match self.value.checked_mul(rhs.value) {
+					Some(value) => Checked { value },
+					None => panic!("difference overflow: {:?} * {:?}", self.value, rhs.value),
+				}
+			}
+		}
+
+		impl Semigroup for Checked<$index_type> {
+			#[inline] fn is_zero(&self) -> bool { self.value == 0 }
+		}
+
+		impl CheckedSemigroup for Checked<$index_type> {
+			#[inline] fn checked_add_assign(&mut self, rhs: &Self) -> bool {
+				self.value.checked_add_assign(&rhs.value)
+			}
+		}
+
+		impl Monoid for Checked<$index_type> {
+			#[inline] fn zero() -> Self { Checked { value: 0 } }
+		}
+	)
+}
+
+implement_checked!(isize);
+implement_checked!(i128);
+implement_checked!(i64);
+implement_checked!(i32);
+implement_checked!(i16);
+implement_checked!(i8);
+
+/// Runs `logic`, returning the message of any panic it raises.
+///
+/// Arrangements use this to add the record being accumulated to the message of a panic raised by
+/// arithmetic on its differences, such as the overflow of `Checked` differences.
+pub(crate) fn catch_panic<F: FnOnce()>(logic: F) -> Result<(), String> {
+	::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(logic))
+		.map_err(|payload| {
+			if let Some(message) = payload.downcast_ref::<String>() { message.clone() }
+			else if let Some(message) = payload.downcast_ref::<&str>() { message.to_string() }
+			else { "panic with unknown payload".to_string() }
+		})
+}
+
+/// An exact fixed-point decimal number, with `Decimal::SCALE` digits after the decimal point.
+///
+/// Decimals are represented as integer multiples of `10^-SCALE`, and their arithmetic is exact:
+/// sums of monetary amounts, for example, do not accumulate rounding errors. All arithmetic is
+/// checked for overflow, and panics rather than wrapping.
+///
+/// # Examples
+///
+/// ```
+/// use differential_dataflow::difference::Decimal;
+///
+/// let price = Decimal::from_parts(1999, 2);   // 19.99
+/// let mut total = price * 3;
+/// total += &Decimal::from_parts(3, 2);
+///
+/// assert_eq!(total, Decimal::from_integer(60));
+/// assert_eq!(format!("{}", price), "19.990000");
+/// assert_eq!("19.99".parse::<Decimal>(), Ok(price));
+/// ```
+#[derive(Abomonation, Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash, Default)]
+pub struct Decimal {
+	units: i64,
+}
+
+impl Decimal {
+	/// The number of decimal digits after the decimal point.
+	pub const SCALE: u32 = 6;
+
+	/// Creates a decimal from a number of units of `10^-SCALE`.
+	#[inline] pub fn from_units(units: i64) -> Self { Decimal { units } }
+
+	/// Creates a decimal from an integer.
+	#[inline] pub fn from_integer(value: i64) -> Self {
+		Decimal::from_parts(value, 0)
+	}
+
+	/// Creates a decimal from `value * 10^-digits`.
+	///
+	/// Panics if `digits` exceeds `Decimal::SCALE`, as the value may not be exactly representable.
+	#[inline] pub fn from_parts(value: i64, digits: u32) -> Self {
+		assert!(digits <= Decimal::SCALE, "decimal digits {} exceed scale {}", digits, Decimal::SCALE);
+		let units = value.checked_mul(10i64.pow(Decimal::SCALE - digits)).expect("decimal overflow");
+		Decimal { units }
+	}
+
+	/// The number of units of `10^-SCALE`.
+	#[inline] pub fn units(&self) -> i64 { self.units }
+
+	/// The nearest floating point approximation.
+	#[inline] pub fn to_f64(&self) -> f64 { self.units as f64 / 10f64.powi(Decimal::SCALE as i32) }
+}
+
+impl ::std::fmt::Display for Decimal {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		let divisor = 10u64.pow(Decimal::SCALE);
+		let sign = if self.units < 0 { "-" } else { "" };
+		let units = self.units.wrapping_abs() as u64;
+		write!(f, "{}{}.{:0width$}", sign, units / divisor, units % divisor, width = Decimal::SCALE as usize)
+	}
+}
+
+impl ::std::str::FromStr for Decimal {
+	type Err = String;
+	/// Parses a decimal with at most `Decimal::SCALE` digits after the decimal point.
+	fn from_str(text: &str) -> Result<Self, Self::Err> {
+		let (negative, digits) = if text.starts_with('-') { (true, &text[1..]) } else { (false, text) };
+		let mut parts = digits.splitn(2, '.');
+		let whole = parts.next().unwrap_or("");
+		let fraction = parts.next().unwrap_or("");
+		if (whole.is_empty() && fraction.is_empty()) || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+			return Err(format!("invalid decimal: {:?}", text));
+		}
+		if fraction.len() > Decimal::SCALE as usize {
+			return Err(format!("decimal {:?} has more than {} digits after the decimal point", text, Decimal::SCALE));
+		}
+		let overflow = || format!("decimal overflow: {:?}", text);
+		let mut units: i64 = 0;
+		for c in whole.chars().chain(fraction.chars()) {
+			units = units.checked_mul(10).and_then(|u| u.checked_add(c.to_digit(10).unwrap() as i64)).ok_or_else(overflow)?;
+		}
+		units = units.checked_mul(10i64.pow(Decimal::SCALE - fraction.len() as u32)).ok_or_else(overflow)?;
+		Ok(Decimal { units: if negative { -units } else { units } })
+	}
+}
+
+impl<'a> AddAssign<&'a Decimal> for Decimal {
+	#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+		self.units = self.units.checked_add(rhs.units).expect("decimal overflow");
+	}
+}
+
+impl Neg for Decimal {
+	type Output = Decimal;
+	#[inline] fn neg(self) -> Self::Output {
+		Decimal { units: self.units.checked_neg().expect("decimal overflow") }
+	}
+}
+
+impl Mul<isize> for Decimal {
+	type Output = Decimal;
+	#[inline] fn mul(self, rhs: isize) -> Self::Output {
+		Decimal { units: self.units.checked_mul(rhs as i64).expect("decimal overflow") }
+	}
+}
+
+impl Mul<Decimal> for isize {
+	type Output = Decimal;
+	#[inline] fn mul(self, rhs: Decimal) -> Self::Output {
+		rhs * self
+	}
+}
+
+impl Semigroup for Decimal {
+	#[inline] fn is_zero(&self) -> bool { self.units == 0 }
+}
+
+impl Monoid for Decimal {
+	#[inline] fn zero() -> Self { Decimal { units: 0 } }
+}
+
+/// A floating point sum accumulated with compensated (Kahan-Babuska) summation.
+///
+/// The accumulator tracks both a running sum and the low-order bits lost from it, which greatly
+/// reduces the error of long sums. However, floating point addition is not associative, and the
+/// accumulated value may depend slightly on the order in which differential dataflow happens to add
+/// updates, which can differ between runs and between workers. In particular, updates that should
+/// cancel may leave a tiny non-zero residue, which prevents them from being retired and may cause
+/// operators to produce spurious outputs. Where exact results are required, use `Decimal` instead.
+///
+/// # Examples
+///
+/// ```
+/// use differential_dataflow::difference::KahanSum;
+///
+/// let mut sum = KahanSum::new(1e16);
+/// for _ in 0 .. 10 {
+///     sum += &KahanSum::new(1.0);
+/// }
+/// sum += &KahanSum::new(-1e16);
+///
+/// assert_eq!(sum.value(), 10.0);
+/// ```
+#[derive(Abomonation, Copy, Debug, Clone, Serialize, Deserialize, Default)]
+pub struct KahanSum {
+	sum: f64,
+	compensation: f64,
+}
+
+impl KahanSum {
+	/// Creates an accumulator holding `value`.
+	#[inline] pub fn new(value: f64) -> Self { KahanSum { sum: value, compensation: 0.0 } }
+
+	/// The accumulated value.
+	#[inline] pub fn value(&self) -> f64 { self.sum + self.compensation }
+
+	/// Adds `value` to the sum, retaining lost low-order bits in the compensation.
+	#[inline] fn add_value(&mut self, value: f64) {
+		let sum = self.sum + value;
+		if self.sum.abs() >= value.abs() {
+			self.compensation += (self.sum - sum) + value;
+		}
+		else {
+			self.compensation += (value - sum) + self.sum;
+		}
+		self.sum = sum;
+	}
+
+	/// A key whose integer order is a total order on the accumulator.
+	#[inline] fn order_key(&self) -> (i64, i64) {
+		fn key(value: f64) -> i64 {
+			let bits = value.to_bits() as i64;
+			bits ^ ((((bits >> 63) as u64) >> 1) as i64)
+		}
+		(key(self.sum), key(self.compensation))
+	}
+}
+
+impl PartialEq for KahanSum {
+	fn eq(&self, other: &Self) -> bool { self.order_key() == other.order_key() }
+}
+
+impl Eq for KahanSum { }
+
+impl PartialOrd for KahanSum {
+	fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for KahanSum {
+	fn cmp(&self, other: &Self) -> ::std::cmp::Ordering { self.order_key().cmp(&other.order_key()) }
+}
+
+impl ::std::hash::Hash for KahanSum {
+	fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) { self.order_key().hash(state); }
+}
+
+impl<'a> AddAssign<&'a KahanSum> for KahanSum {
+	#[inline] fn add_assign(&mut self, rhs: &'a Self) {
+		self.add_value(rhs.sum);
+		self.add_value(rhs.compensation);
+	}
+}
+
+impl Neg for KahanSum {
+	type Output = KahanSum;
+	#[inline] fn neg(self) -> Self::Output {
+		KahanSum { sum: -self.sum, compensation: -self.compensation }
+	}
+}
+
+impl Mul<isize> for KahanSum {
+	type Output = KahanSum;
+	#[inline] fn mul(self, rhs: isize) -> Self::Output {
+		let mut result = KahanSum::new(self.sum * rhs as f64);
+		result.add_value(self.compensation * rhs as f64);
+		result
+	}
+}
+
+impl Semigroup for KahanSum {
+	#[inline] fn is_zero(&self) -> bool { self.value() == 0.0 }
+}
+
+impl Monoid for KahanSum {
+	#[inline] fn zero() -> Self { KahanSum { sum: 0.0, compensation: 0.0 } }
+}
@@ -9,7 +9,10 @@
 use timely::progress::Timestamp;
 use timely::dataflow::operators::Input as TimelyInput;
 use timely::dataflow::operators::input::Handle;
+use timely::dataflow::operators::unordered_input::{UnorderedInput, UnorderedHandle};
+use timely::dataflow::operators::ActivateCapability;
 use timely::dataflow::scopes::ScopeParent;
+use timely::progress::frontier::Antichain;
 
 use ::Data;
 use ::difference::Semigroup;
@@ -110,9 +113,59 @@ pub trait Input : TimelyInput {
     /// ```
     fn new_collection_from_raw<D, R, I>(&mut self, data: I) -> (InputSession<<Self as ScopeParent>::Timestamp, D, R>, Collection<Self, D, R>)
     where I: IntoIterator<Item=(D,<Self as ScopeParent>::Timestamp,R)>+'static, D: Data, R: Semigroup+Data;
+    /// Create a new collection and an unordered input handle to control the collection.
+    ///
+    /// Unlike `new_collection`, whose handle has a single current time, the handle returned by this method
+    /// may hold several incomparable times at once, and accept updates at any time greater or equal to one
+    /// of them. This is useful for partially ordered timestamps, like `Pair`, where each coordinate may be
+    /// advanced independently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::Configuration;
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::lattice::Pair;
+    ///
+    /// fn main() {
+    ///     ::timely::execute(Configuration::Thread, |worker| {
+    ///
+    ///         // times are pairs of "system time" and "event time".
+    ///         let (mut handle, probe) = worker.dataflow::<Pair<u32, u32>,_,_>(|scope| {
+    ///             let (handle, data) = scope.new_unordered_collection();
+    ///             let probe = data.map(|x: u32| x * 2)
+    ///                             .inspect(|x| println!("{:?}", x))
+    ///                             .probe();
+    ///             (handle, probe)
+    ///         });
+    ///
+    ///         handle.update_at(1, Pair::new(0, 0), 1);
+    ///         handle.update_at(5, Pair::new(0, 3), 1);
+    ///
+    ///         // complete event times before three, while still allowing corrections in system time.
+    ///         handle.advance_second_to(3);
+    ///         handle.flush();
+    ///
+    ///         while probe.less_than(&Pair::new(0, 3)) {
+    ///             worker.step();
+    ///         }
+    ///
+    ///         // correct the record at event time zero, at the next system time.
+    ///         handle.update_at(1, Pair::new(1, 0),-1);
+    ///         handle.advance_first_to(1);
+    ///         handle.flush();
+    ///
+    ///     }).unwrap();
+    /// }
+    /// ```
+    fn new_unordered_collection<D, R>(&mut self) -> (UnorderedInputSession<<Self as ScopeParent>::Timestamp, D, R>, Collection<Self, D, R>)
+    where D: Data, R: Semigroup;
 }
 
-use lattice::Lattice;
+use lattice::{Lattice, Pair};
 impl<G: TimelyInput> Input for G where <G as ScopeParent>::Timestamp: Lattice {
     fn new_collection<D, R>(&mut self) -> (InputSession<<G as ScopeParent>::Timestamp, D, R>, Collection<G, D, R>)
     where D: Data, R: Semigroup{
@@ -135,7 +188,13 @@ impl<G: TimelyInput> Input for G where <G as ScopeParent>::Timestamp: Lattice {
         let source = data.to_stream(self).as_collection();
 
         (InputSession::from(handle), stream.as_collection().concat(&source))
-    }}
+    }
+    fn new_unordered_collection<D, R>(&mut self) -> (UnorderedInputSession<<G as ScopeParent>::Timestamp, D, R>, Collection<G, D, R>)
+    where D: Data, R: Semigroup {
+        let ((handle, capability), stream) = self.new_unordered_input();
+        (UnorderedInputSession::from(handle, capability), stream.as_collection())
+    }
+}
 
 /// An input session wrapping a single timely dataflow capability.
 ///
@@ -306,3 +365,134 @@ impl<T: Timestamp+Clone, D: Data, R: Semigroup> Drop for InputSession<T, D, R> {
 		self.flush();
 	}
 }
+
+/// An input session wrapping a set of timely dataflow capabilities.
+///
+/// The session may hold capabilities for several incomparable times, and accepts updates at any time
+/// greater or equal to one of them. Each capability can be advanced independently of the others, which
+/// allows partially ordered timestamps to make progress in each of their coordinates separately.
+///
+/// Like `InputSession`, updates are buffered and only exposed to timely dataflow when the session is
+/// flushed, advanced, or dropped.
+pub struct UnorderedInputSession<T: Timestamp+Clone, D: Data, R: Semigroup> {
+    buffer: Vec<(D, T, R)>,
+    handle: UnorderedHandle<T, (D, T, R)>,
+    capabilities: Vec<ActivateCapability<T>>,
+}
+
+impl<T: Timestamp+Clone, D: Data, R: Semigroup> UnorderedInputSession<T, D, R> {
+
+    /// Creates a new session from an unordered input handle and its initial capability.
+    pub fn from(handle: UnorderedHandle<T, (D, T, R)>, capability: ActivateCapability<T>) -> Self {
+        UnorderedInputSession {
+            buffer: Vec::new(),
+            handle,
+            capabilities: vec![capability],
+        }
+    }
+
+    /// Adds to the weight of an element in the collection at `time`.
+    ///
+    /// The time must be greater or equal to some element of the session's frontier.
+    pub fn update_at(&mut self, element: D, time: T, change: R) {
+        assert!(self.capabilities.iter().any(|capability| capability.time().less_equal(&time)));
+        if self.buffer.len() == self.buffer.capacity() {
+            if self.buffer.len() > 0 {
+                self.send_buffer();
+            }
+            // TODO : This is a fairly arbitrary choice; should probably use `Context::default_size()` or such.
+            self.buffer.reserve(1024);
+        }
+        self.buffer.push((element, time, change));
+    }
+
+    /// Sends each buffered update using the first capability that permits its time.
+    fn send_buffer(&mut self) {
+        for capability in self.capabilities.iter() {
+            let mut index = 0;
+            let mut batch = Vec::new();
+            while index < self.buffer.len() {
+                if capability.time().less_equal(&self.buffer[index].1) {
+                    batch.push(self.buffer.swap_remove(index));
+                }
+                else {
+                    index += 1;
+                }
+            }
+            if !batch.is_empty() {
+                self.handle.session(capability.clone()).give_iterator(batch.into_iter());
+            }
+        }
+        debug_assert!(self.buffer.is_empty());
+    }
+
+    /// Forces buffered data into the timely dataflow input.
+    pub fn flush(&mut self) {
+        self.send_buffer();
+    }
+
+    /// Advances the frontier of the session to `frontier`.
+    ///
+    /// Buffered updates are flushed before the frontier is advanced. Each element of `frontier` must be
+    /// greater or equal to some element of the current frontier, and future updates must be at times
+    /// greater or equal to some element of `frontier`.
+    pub fn advance_to(&mut self, frontier: &[T]) {
+        self.flush();
+        let capabilities =
+        frontier
+            .iter()
+            .map(|time| {
+                self.capabilities
+                    .iter()
+                    .find(|capability| capability.time().less_equal(time))
+                    .expect("frontier not in advance of the session frontier")
+                    .delayed(time)
+            })
+            .collect::<Vec<_>>();
+        self.capabilities = capabilities;
+    }
+
+    /// Reveals the current frontier of the session.
+    pub fn frontier(&self) -> Vec<T> {
+        self.capabilities.iter().map(|capability| capability.time().clone()).collect()
+    }
+
+    /// Closes the input, flushing and sealing the wrapped timely input.
+    pub fn close(self) { }
+}
+
+impl<S, T, D, R> UnorderedInputSession<Pair<S, T>, D, R>
+where
+    S: Timestamp+Lattice,
+    T: Timestamp+Lattice,
+    D: Data,
+    R: Semigroup,
+{
+    /// Advances the first coordinate of each element of the frontier to at least `first`.
+    ///
+    /// The second coordinates are unchanged, so updates may continue to arrive at earlier second coordinates.
+    pub fn advance_first_to(&mut self, first: S) {
+        let mut frontier = Antichain::new();
+        for time in self.frontier() {
+            frontier.insert(Pair::new(time.first.join(&first), time.second));
+        }
+        self.advance_to(frontier.elements());
+    }
+
+    /// Advances the second coordinate of each element of the frontier to at least `second`.
+    ///
+    /// The first coordinates are unchanged, so updates may continue to arrive at earlier first coordinates.
+    pub fn advance_second_to(&mut self, second: T) {
+        let mut frontier = Antichain::new();
+        for time in self.frontier() {
+            frontier.insert(Pair::new(time.first, time.second.join(&second)));
+        }
+        self.advance_to(frontier.elements());
+    }
+}
+
+impl<T: Timestamp+Clone, D: Data, R: Semigroup> Drop for UnorderedInputSession<T, D, R> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
@@ -202,3 +202,415 @@ implement_lattice!(u16, 0);
 implement_lattice!(u8, 0);
 implement_lattice!(i32, 0);
 implement_lattice!((), ());
+
+pub use self::pair::Pair;
+pub use self::vector::Vector;
+pub use self::altneu::AltNeu;
+
+/// A pair of timestamps, partially ordered by the product order.
+///
+/// Unlike timely dataflow's `Product`, which is used for nested scopes, a `Pair` may be used as the
+/// timestamp of a top-level scope. This allows multiple independent notions of time, for example
+/// "system time" and "event time" in bitemporal computation, where each may advance separately.
+mod pair {
+
+    use std::fmt::{Formatter, Error, Debug};
+
+    use timely::order::PartialOrder;
+    use timely::progress::{PathSummary, Timestamp};
+    use timely::progress::timestamp::Refines;
+
+    use super::Lattice;
+
+    /// A pair of timestamps, partially ordered by the product order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate timely;
+    /// # extern crate differential_dataflow;
+    /// # use timely::PartialOrder;
+    /// # use differential_dataflow::lattice::{Lattice, Pair};
+    /// # fn main() {
+    ///
+    /// let time1 = Pair::new(3, 7);
+    /// let time2 = Pair::new(4, 6);
+    ///
+    /// assert!(!time1.less_equal(&time2));
+    /// assert!(!time2.less_equal(&time1));
+    /// assert_eq!(time1.join(&time2), Pair::new(4, 7));
+    /// assert_eq!(time1.meet(&time2), Pair::new(3, 6));
+    /// # }
+    /// ```
+    #[derive(Hash, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Abomonation)]
+    pub struct Pair<S, T> {
+        /// The first coordinate.
+        pub first: S,
+        /// The second coordinate.
+        pub second: T,
+    }
+
+    impl<S, T> Pair<S, T> {
+        /// Creates a new pair.
+        pub fn new(first: S, second: T) -> Self {
+            Pair { first, second }
+        }
+    }
+
+    impl<S: PartialOrder, T: PartialOrder> PartialOrder for Pair<S, T> {
+        #[inline]
+        fn less_equal(&self, other: &Self) -> bool {
+            self.first.less_equal(&other.first) && self.second.less_equal(&other.second)
+        }
+    }
+
+    impl<S: Timestamp, T: Timestamp> Refines<()> for Pair<S, T> {
+        fn to_inner(_outer: ()) -> Self { Default::default() }
+        fn to_outer(self) -> () { () }
+        fn summarize(_summary: <Self>::Summary) -> () { () }
+    }
+
+    /// Summaries of pairs advance each coordinate by the corresponding summary.
+    impl<S: Timestamp, T: Timestamp> PathSummary<Pair<S, T>> for Pair<S::Summary, T::Summary> {
+        #[inline]
+        fn results_in(&self, timestamp: &Pair<S, T>) -> Option<Pair<S, T>> {
+            let first = self.first.results_in(&timestamp.first)?;
+            let second = self.second.results_in(&timestamp.second)?;
+            Some(Pair::new(first, second))
+        }
+        #[inline]
+        fn followed_by(&self, other: &Self) -> Option<Self> {
+            let first = self.first.followed_by(&other.first)?;
+            let second = self.second.followed_by(&other.second)?;
+            Some(Pair::new(first, second))
+        }
+    }
+
+    impl<S: Timestamp, T: Timestamp> Timestamp for Pair<S, T> {
+        type Summary = Pair<S::Summary, T::Summary>;
+    }
+
+    impl<S: Lattice, T: Lattice> Lattice for Pair<S, T> {
+        #[inline]
+        fn minimum() -> Self { Pair::new(S::minimum(), T::minimum()) }
+        #[inline]
+        fn join(&self, other: &Self) -> Self {
+            Pair {
+                first: self.first.join(&other.first),
+                second: self.second.join(&other.second),
+            }
+        }
+        #[inline]
+        fn meet(&self, other: &Self) -> Self {
+            Pair {
+                first: self.first.meet(&other.first),
+                second: self.second.meet(&other.second),
+            }
+        }
+    }
+
+    /// Debug implementation to avoid seeing fully qualified path names.
+    impl<S: Debug, T: Debug> Debug for Pair<S, T> {
+        fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+            f.write_str(&format!("({:?}, {:?})", self.first, self.second))
+        }
+    }
+}
+
+/// A vector of timestamps, partially ordered by the product order.
+///
+/// Vectors may have any number of coordinates, where absent coordinates are treated as the default
+/// value. This allows computations to introduce new notions of time without changing their types.
+mod vector {
+
+    use std::cmp::Ordering;
+    use std::hash::{Hash, Hasher};
+
+    use timely::order::PartialOrder;
+    use timely::progress::{PathSummary, Timestamp};
+    use timely::progress::timestamp::Refines;
+
+    use super::Lattice;
+
+    /// A vector of timestamps, partially ordered by the product order.
+    ///
+    /// Absent coordinates are treated as the default value, which should be the minimum of the type.
+    /// Vectors that differ only in trailing default coordinates are equal, hash identically, and
+    /// are ordered lexicographically as if padded with default values, so that `Ord` extends the
+    /// partial order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate timely;
+    /// # extern crate differential_dataflow;
+    /// # use timely::PartialOrder;
+    /// # use differential_dataflow::lattice::{Lattice, Vector};
+    /// # fn main() {
+    ///
+    /// let time1 = Vector::new(vec![3, 7]);
+    /// let time2 = Vector::new(vec![4]);
+    ///
+    /// assert!(!time1.less_equal(&time2));
+    /// assert!(time2.less_equal(&time1.join(&time2)));
+    /// assert_eq!(time1.join(&time2), Vector::new(vec![4, 7]));
+    /// assert_eq!(time1.meet(&time2), Vector::new(vec![3]));
+    /// assert_eq!(Vector::new(vec![3, 0]), Vector::new(vec![3]));
+    /// # }
+    /// ```
+    #[derive(Default, Clone, Abomonation, Debug)]
+    pub struct Vector<T> {
+        /// The coordinates of the vector.
+        pub vector: Vec<T>,
+    }
+
+    impl<T> Vector<T> {
+        /// Creates a new vector.
+        pub fn new(vector: Vec<T>) -> Self {
+            Vector { vector }
+        }
+    }
+
+    impl<T: Default+PartialEq> Vector<T> {
+        /// The coordinates of the vector, without trailing default values.
+        fn trimmed(&self) -> &[T] {
+            let default = T::default();
+            let mut len = self.vector.len();
+            while len > 0 && self.vector[len-1] == default {
+                len -= 1;
+            }
+            &self.vector[.. len]
+        }
+    }
+
+    impl<T: Default+PartialEq> PartialEq for Vector<T> {
+        #[inline]
+        fn eq(&self, other: &Self) -> bool { self.trimmed() == other.trimmed() }
+    }
+
+    impl<T: Default+Eq> Eq for Vector<T> { }
+
+    /// Vectors are ordered lexicographically, which for trimmed vectors is the same as comparing
+    /// them padded with default values, as the default value is the minimum.
+    impl<T: Default+Ord> PartialOrd for Vector<T> {
+        #[inline]
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+    }
+
+    impl<T: Default+Ord> Ord for Vector<T> {
+        #[inline]
+        fn cmp(&self, other: &Self) -> Ordering { self.trimmed().cmp(other.trimmed()) }
+    }
+
+    impl<T: Default+PartialEq+Hash> Hash for Vector<T> {
+        fn hash<H: Hasher>(&self, state: &mut H) { self.trimmed().hash(state) }
+    }
+
+    impl<T: PartialOrder+Default> PartialOrder for Vector<T> {
+        #[inline]
+        fn less_equal(&self, other: &Self) -> bool {
+            let default = T::default();
+            self.vector
+                .iter()
+                .enumerate()
+                .all(|(index, time)| time.less_equal(other.vector.get(index).unwrap_or(&default)))
+        }
+    }
+
+    impl<T: Timestamp> Refines<()> for Vector<T> {
+        fn to_inner(_outer: ()) -> Self { Default::default() }
+        fn to_outer(self) -> () { () }
+        fn summarize(_summary: <Self>::Summary) -> () { () }
+    }
+
+    /// Summaries of vectors advance each coordinate by the corresponding summary, if any.
+    impl<T: Timestamp> PathSummary<Vector<T>> for Vector<T::Summary> {
+        #[inline]
+        fn results_in(&self, timestamp: &Vector<T>) -> Option<Vector<T>> {
+            let len = ::std::cmp::max(self.vector.len(), timestamp.vector.len());
+            let mut vector = Vec::with_capacity(len);
+            for index in 0 .. len {
+                let time = timestamp.vector.get(index).cloned().unwrap_or_default();
+                match self.vector.get(index) {
+                    Some(summary) => vector.push(summary.results_in(&time)?),
+                    None => vector.push(time),
+                }
+            }
+            Some(Vector { vector })
+        }
+        #[inline]
+        fn followed_by(&self, other: &Self) -> Option<Self> {
+            let len = ::std::cmp::max(self.vector.len(), other.vector.len());
+            let mut vector = Vec::with_capacity(len);
+            for index in 0 .. len {
+                let summary1 = self.vector.get(index).cloned().unwrap_or_default();
+                let summary2 = other.vector.get(index).cloned().unwrap_or_default();
+                vector.push(summary1.followed_by(&summary2)?);
+            }
+            Some(Vector { vector })
+        }
+    }
+
+    impl<T: Timestamp> Timestamp for Vector<T> {
+        type Summary = Vector<T::Summary>;
+    }
+
+    impl<T: Lattice+Default+Clone> Lattice for Vector<T> {
+        #[inline]
+        fn minimum() -> Self { Vector { vector: Vec::new() } }
+        #[inline]
+        fn join(&self, other: &Self) -> Self {
+            let min_len = ::std::cmp::min(self.vector.len(), other.vector.len());
+            let max_len = ::std::cmp::max(self.vector.len(), other.vector.len());
+            let mut vector = Vec::with_capacity(max_len);
+            for index in 0 .. min_len {
+                vector.push(self.vector[index].join(&other.vector[index]));
+            }
+            for time in &self.vector[min_len..] {
+                vector.push(time.clone());
+            }
+            for time in &other.vector[min_len..] {
+                vector.push(time.clone());
+            }
+            Vector { vector }
+        }
+        #[inline]
+        fn meet(&self, other: &Self) -> Self {
+            let min_len = ::std::cmp::min(self.vector.len(), other.vector.len());
+            let mut vector = Vec::with_capacity(min_len);
+            for index in 0 .. min_len {
+                vector.push(self.vector[index].meet(&other.vector[index]));
+            }
+            Vector { vector }
+        }
+    }
+}
+
+/// A lexicographically ordered pair of a timestamp and a boolean.
+///
+/// Two timestamps (s1, t1) and (s2, t2) are ordered either if s1 and s2 are ordered,
+/// or if s1 equals s2 and t1 and t2 are ordered.
+///
+/// The join of two timestamps should have as its first coordinate the join of the first
+/// coordinates, and for its second coordinate the join of the second coordinates for
+/// elements whose first coordinate equals the computed join. That may be the minimum
+/// element of the second lattice, if neither first element equals the join.
+///
+/// This timestamp is the basis of delta queries, in which each update at some time is
+/// joined against other collections either at times less or equal to it ("alt") or at
+/// times strictly less than it ("neu").
+mod altneu {
+
+    use timely::order::{PartialOrder, TotalOrder};
+    use timely::progress::{PathSummary, Timestamp};
+    use timely::progress::timestamp::Refines;
+
+    use super::Lattice;
+
+    /// A timestamp refined to distinguish "alt" and "neu" versions of each time.
+    ///
+    /// Each `AltNeu::alt(time)` is less than `AltNeu::neu(time)`, and both are less than the alt and neu
+    /// versions of each time strictly greater than `time`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate timely;
+    /// # extern crate differential_dataflow;
+    /// # use timely::PartialOrder;
+    /// # use differential_dataflow::lattice::{Lattice, AltNeu};
+    /// # fn main() {
+    ///
+    /// assert!(AltNeu::alt(3).less_equal(&AltNeu::neu(3)));
+    /// assert!(AltNeu::neu(3).less_equal(&AltNeu::alt(4)));
+    /// assert!(!AltNeu::neu(3).less_equal(&AltNeu::alt(3)));
+    ///
+    /// assert_eq!(AltNeu::neu(3).join(&AltNeu::alt(3)), AltNeu::neu(3));
+    /// assert_eq!(AltNeu::neu(3).meet(&AltNeu::alt(4)), AltNeu::neu(3));
+    /// # }
+    /// ```
+    #[derive(Debug, Hash, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Abomonation, Serialize, Deserialize)]
+    pub struct AltNeu<T> {
+        /// The refined time.
+        pub time: T,
+        /// Whether this is the "neu" version of the time; alt < neu in timestamp comparisons.
+        pub neu: bool,
+    }
+
+    impl<T> AltNeu<T> {
+        /// The "alt" version of `time`, which is less than the "neu" version.
+        pub fn alt(time: T) -> Self { AltNeu { time, neu: false } }
+        /// The "neu" version of `time`, which is greater than the "alt" version.
+        pub fn neu(time: T) -> Self { AltNeu { time, neu: true } }
+    }
+
+    impl<T: PartialOrder> PartialOrder for AltNeu<T> {
+        #[inline]
+        fn less_equal(&self, other: &Self) -> bool {
+            if self.time.eq(&other.time) {
+                self.neu <= other.neu
+            }
+            else {
+                self.time.less_equal(&other.time)
+            }
+        }
+    }
+
+    impl<T: TotalOrder> TotalOrder for AltNeu<T> { }
+
+    impl<T: Timestamp> PathSummary<AltNeu<T>> for () {
+        #[inline]
+        fn results_in(&self, timestamp: &AltNeu<T>) -> Option<AltNeu<T>> {
+            Some(timestamp.clone())
+        }
+        #[inline]
+        fn followed_by(&self, other: &Self) -> Option<Self> {
+            Some(other.clone())
+        }
+    }
+
+    impl<T: Timestamp> Timestamp for AltNeu<T> {
+        type Summary = ();
+    }
+
+    impl<T: Timestamp> Refines<T> for AltNeu<T> {
+        fn to_inner(other: T) -> Self {
+            AltNeu::alt(other)
+        }
+        fn to_outer(self: AltNeu<T>) -> T {
+            self.time
+        }
+        fn summarize(_path: ()) -> <T as Timestamp>::Summary {
+            Default::default()
+        }
+    }
+
+    impl<T: Lattice> Lattice for AltNeu<T> {
+        #[inline]
+        fn minimum() -> Self { AltNeu::alt(T::minimum()) }
+        #[inline]
+        fn join(&self, other: &Self) -> Self {
+            let time = self.time.join(&other.time);
+            let mut neu = false;
+            if time == self.time {
+                neu = neu || self.neu;
+            }
+            if time == other.time {
+                neu = neu || other.neu;
+            }
+            AltNeu { time, neu }
+        }
+        #[inline]
+        fn meet(&self, other: &Self) -> Self {
+            let time = self.time.meet(&other.time);
+            let mut neu = true;
+            if time == self.time {
+                neu = neu && self.neu;
+            }
+            if time == other.time {
+                neu = neu && other.neu;
+            }
+            AltNeu { time, neu }
+        }
+    }
+}
@@ -25,6 +25,8 @@ pub enum DifferentialEvent {
     Merge(MergeEvent),
     /// A merge failed to complete in time.
     MergeShortfall(MergeShortfall),
+    /// Updates circulating in an iterative scope.
+    Iteration(IterationEvent),
 }
 
 /// Either the start or end of a merge event.
@@ -67,3 +69,19 @@ pub struct MergeShortfall {
 }
 
 impl From<MergeShortfall> for DifferentialEvent { fn from(e: MergeShortfall) -> Self { DifferentialEvent::MergeShortfall(e) } }
+
+/// Updates circulating in an iterative scope.
+///
+/// Each event reports some of the updates produced by the loop body in one round of iteration.
+/// The updates for a round may be reported by several events, whose counts should be summed.
+#[derive(Debug, Clone, Abomonation, Ord, PartialOrd, Eq, PartialEq)]
+pub struct IterationEvent {
+    /// Address of the iterative scope.
+    pub address: Vec<usize>,
+    /// The round of iteration.
+    pub round: u64,
+    /// Number of updates.
+    pub updates: usize,
+}
+
+impl From<IterationEvent> for DifferentialEvent { fn from(e: IterationEvent) -> Self { DifferentialEvent::Iteration(e) } }
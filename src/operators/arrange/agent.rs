@@ -4,16 +4,23 @@ use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 use std::default::Default;
 use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
 
 use timely::dataflow::Scope;
 use timely::dataflow::operators::generic::source;
+use timely::dataflow::operators::Map;
+use timely::order::PartialOrder;
 use timely::progress::Timestamp;
+use timely::progress::frontier::Antichain;
 use timely::dataflow::operators::CapabilitySet;
 
+use ::difference::Semigroup;
 use lattice::Lattice;
-use trace::{Trace, TraceReader, Batch, BatchReader, Cursor};
+use trace::{Trace, TraceReader, Batch, BatchReader, Builder, Cursor};
 
 use trace::wrappers::rc::TraceBox;
+use trace::wrappers::frontier::{TraceFrontier, BatchFrontier};
+use trace::wrappers::keys::{TraceKeys, BatchKeys, KeySet};
 
 use timely::scheduling::Activator;
 
@@ -100,6 +107,14 @@ where
         (reader, writer)
     }
 
+    /// The trace shared by the agents, which is dropped once there are no agents.
+    ///
+    /// This method is unstable, as it exposes how agents share their trace. It allows observers
+    /// to determine whether a trace remains in use, for example by holding a `Weak` reference.
+    pub fn trace_box_unstable(&self) -> Rc<RefCell<TraceBox<Tr>>> {
+        self.trace.clone()
+    }
+
     /// Attaches a new shared queue to the trace.
     ///
     /// The queue is first populated with existing batches from the trace,
@@ -135,6 +150,145 @@ where
         reference.0.activate();
         reference
     }
+
+    /// Reads the accumulated contents of the trace at `time`.
+    ///
+    /// The result enumerates `(key, val, diff)` triples, ordered by key and value, for each pair whose
+    /// updates at times less or equal to `time` accumulate to a non-zero difference. This allows the
+    /// contents of an arrangement to be read from outside of a dataflow, for example to serve queries.
+    ///
+    /// The method returns `None` if the accumulation at `time` cannot be determined, either because
+    /// `time` is not greater or equal to an element of `self.advance_frontier()`, in which case the
+    /// trace may no longer distinguish it from other times, or because the trace is not yet complete
+    /// through `time`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::Configuration;
+    /// use timely::dataflow::operators::Probe;
+    /// use differential_dataflow::input::InputSession;
+    /// use differential_dataflow::operators::arrange::ArrangeByKey;
+    ///
+    /// fn main() {
+    ///     ::timely::execute(Configuration::Thread, |worker| {
+    ///
+    ///         let mut input = InputSession::<u32,(u32,u32),isize>::new();
+    ///
+    ///         let (mut trace, probe) = worker.dataflow(|scope| {
+    ///             let arranged = input.to_collection(scope).arrange_by_key();
+    ///             (arranged.trace, arranged.stream.probe())
+    ///         });
+    ///
+    ///         input.insert((0, 1));
+    ///         input.insert((1, 2));
+    ///         input.advance_to(1);
+    ///         input.remove((0, 1));
+    ///         input.advance_to(2);
+    ///         input.flush();
+    ///
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///
+    ///         let at0 = trace.snapshot_at(&0).unwrap().collect::<Vec<_>>();
+    ///         assert_eq!(at0, vec![(0, 1, 1), (1, 2, 1)]);
+    ///         let at1 = trace.snapshot_at(&1).unwrap().collect::<Vec<_>>();
+    ///         assert_eq!(at1, vec![(1, 2, 1)]);
+    ///         assert!(trace.snapshot_at(&2).is_none());
+    ///
+    ///     }).unwrap();
+    /// }
+    /// ```
+    pub fn snapshot_at(&mut self, time: &Tr::Time) -> Option<::std::vec::IntoIter<(Tr::Key, Tr::Val, Tr::R)>>
+    where
+        Tr::Key: Ord+Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+    {
+        self.snapshot_range_at(.., time)
+    }
+
+    /// Reads the accumulated values associated with `key` at `time`.
+    ///
+    /// This method has the same behavior as `snapshot_at`, restricted to the single key `key`.
+    pub fn snapshot_key_at(&mut self, key: &Tr::Key, time: &Tr::Time) -> Option<::std::vec::IntoIter<(Tr::Key, Tr::Val, Tr::R)>>
+    where
+        Tr::Key: Ord+Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+    {
+        self.snapshot_range_at(key.clone() ..= key.clone(), time)
+    }
+
+    /// Reads the accumulated contents of the trace at `time`, for keys in `range`.
+    ///
+    /// This method has the same behavior as `snapshot_at`, restricted to keys in `range`. The cursor
+    /// seeks directly to the start of the range, and stops once it passes the end of the range, so
+    /// that keys outside the range are not visited.
+    pub fn snapshot_range_at<B>(&mut self, range: B, time: &Tr::Time) -> Option<::std::vec::IntoIter<(Tr::Key, Tr::Val, Tr::R)>>
+    where
+        B: RangeBounds<Tr::Key>,
+        Tr::Key: Ord+Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+    {
+        // Accumulations are only correct for times in advance of our advance frontier.
+        if !self.advance.iter().any(|t| t.less_equal(time)) {
+            return None;
+        }
+
+        // The trace must contain all updates at times less or equal to `time`.
+        let mut upper = Antichain::new();
+        self.read_upper(&mut upper);
+        if upper.less_equal(time) {
+            return None;
+        }
+
+        let (mut cursor, storage) = self.cursor();
+
+        match range.start_bound() {
+            Bound::Included(key) => cursor.seek_key(&storage, key),
+            Bound::Excluded(key) => {
+                cursor.seek_key(&storage, key);
+                if cursor.get_key(&storage) == Some(key) {
+                    cursor.step_key(&storage);
+                }
+            },
+            Bound::Unbounded => { },
+        }
+
+        let mut results = Vec::new();
+        while let Some(key) = cursor.get_key(&storage) {
+            let in_range = match range.end_bound() {
+                Bound::Included(upper) => key <= upper,
+                Bound::Excluded(upper) => key < upper,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                break;
+            }
+            while let Some(val) = cursor.get_val(&storage) {
+                let mut accum: Option<Tr::R> = None;
+                cursor.map_times(&storage, |t, d| {
+                    if t.less_equal(time) {
+                        if let Some(ref mut accum) = accum { *accum += d; }
+                        else { accum = Some(d.clone()); }
+                    }
+                });
+                if let Some(diff) = accum {
+                    if !diff.is_zero() {
+                        results.push((key.clone(), val.clone(), diff));
+                    }
+                }
+                cursor.step_val(&storage);
+            }
+            cursor.step_key(&storage);
+        }
+
+        Some(results.into_iter())
+    }
 }
 
 impl<Tr> TraceAgent<Tr>
@@ -323,6 +477,338 @@ where
 
         (Arranged { stream, trace }, shutdown_button.unwrap())
     }
+
+    /// Imports an arrangement into the supplied scope, with times advanced to `frontier`.
+    ///
+    /// The imported collection starts from a single batch containing the accumulated history of the
+    /// arrangement, whose updates have their times advanced by `frontier`, followed by the batches that
+    /// arrive after the import, also advanced by `frontier`. The historical batches are not replayed, which
+    /// can be substantially less work for downstream operators in queries that arrive late.
+    ///
+    /// The frontier `frontier` must be in advance of `self.advance_frontier()`, as otherwise the trace may no
+    /// longer be able to distinguish the times of `frontier`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::Configuration;
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::arrange::ArrangeBySelf;
+    /// use differential_dataflow::operators::reduce::Reduce;
+    ///
+    /// fn main() {
+    ///     ::timely::execute(Configuration::Thread, |worker| {
+    ///
+    ///         // create a first dataflow
+    ///         let mut trace = worker.dataflow::<u32,_,_>(|scope| {
+    ///             scope.new_collection_from(0 .. 10).1
+    ///                  .arrange_by_self()
+    ///                  .trace
+    ///         });
+    ///
+    ///         // do some work.
+    ///         worker.step();
+    ///         worker.step();
+    ///
+    ///         // import the collection as of time 1.
+    ///         worker.dataflow(move |scope| {
+    ///             trace.import_as_of(scope, &[1])
+    ///                  .reduce(move |_key, src, dst| dst.push((*src[0].0, 1)));
+    ///         });
+    ///
+    ///     }).unwrap();
+    /// }
+    /// ```
+    pub fn import_as_of<G>(&mut self, scope: &G, frontier: &[Tr::Time]) -> Arranged<G, TraceFrontier<TraceAgent<Tr>>>
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+        Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    {
+        self.import_as_of_core(scope, "ArrangedSourceAsOf", frontier).0
+    }
+
+    /// Same as `import_as_of`, but allows to name the source and returns a shutdown button.
+    pub fn import_as_of_core<G>(&mut self, scope: &G, name: &str, frontier: &[Tr::Time]) -> (Arranged<G, TraceFrontier<TraceAgent<Tr>>>, ShutdownButton<CapabilitySet<Tr::Time>>)
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+        Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    {
+        assert!(
+            frontier.iter().all(|t| self.advance.iter().any(|a| a.less_equal(t))),
+            "import frontier must be in advance of the trace advance frontier"
+        );
+
+        self.import_snapshot_core(scope, name, frontier)
+    }
+
+    /// Imports the keys of an arrangement within `range` into the supplied scope.
+    ///
+    /// The imported arrangement contains only those updates whose keys lie in `range`. Cursors over the
+    /// arrangement and its batches seek directly to the keys in the range, and do not visit other keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::Configuration;
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::arrange::ArrangeBySelf;
+    ///
+    /// fn main() {
+    ///     ::timely::execute(Configuration::Thread, |worker| {
+    ///
+    ///         let mut trace = worker.dataflow::<u32,_,_>(|scope| {
+    ///             scope.new_collection_from(0 .. 10).1
+    ///                  .arrange_by_self()
+    ///                  .trace
+    ///         });
+    ///
+    ///         worker.step();
+    ///         worker.step();
+    ///
+    ///         worker.dataflow(move |scope| {
+    ///             let (_,fewer) = scope.new_collection_from(3 .. 6);
+    ///             trace.import_key_range(scope, 3 .. 6)
+    ///                  .as_collection(|k,_| *k)
+    ///                  .assert_eq(&fewer);
+    ///         });
+    ///
+    ///     }).unwrap();
+    /// }
+    /// ```
+    pub fn import_key_range<G, B>(&mut self, scope: &G, range: B) -> Arranged<G, TraceKeys<TraceAgent<Tr>>>
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Ord+Clone,
+        B: RangeBounds<Tr::Key>,
+    {
+        self.import_keys_core(scope, "ArrangedSourceKeyRange", KeySet::from_range(range)).0
+    }
+
+    /// Imports the keys of an arrangement in `keys` into the supplied scope.
+    ///
+    /// The imported arrangement contains only those updates whose keys are among `keys`. Cursors over the
+    /// arrangement and its batches seek directly from one key to the next, and do not visit other keys.
+    pub fn import_key_set<G, I>(&mut self, scope: &G, keys: I) -> Arranged<G, TraceKeys<TraceAgent<Tr>>>
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Ord+Clone,
+        I: IntoIterator<Item=Tr::Key>,
+    {
+        self.import_keys_core(scope, "ArrangedSourceKeySet", KeySet::from_keys(keys)).0
+    }
+
+    /// Imports the keys of an arrangement in `keys` into the supplied scope, with a name and shutdown button.
+    pub fn import_keys_core<G>(&mut self, scope: &G, name: &str, keys: KeySet<Tr::Key>) -> (Arranged<G, TraceKeys<TraceAgent<Tr>>>, ShutdownButton<CapabilitySet<Tr::Time>>)
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Ord+Clone,
+    {
+        let (arranged, shutdown_button) = self.import_core(scope, name);
+
+        let keys = Rc::new(keys);
+        let keys1 = keys.clone();
+        let stream = arranged.stream.map(move |batch| BatchKeys::make_from(batch, keys1.clone()));
+        let trace = TraceKeys::make_from(arranged.trace, keys);
+
+        (Arranged { stream, trace }, shutdown_button)
+    }
+
+    /// Imports an arrangement into the supplied scope, as a snapshot followed by subsequent changes.
+    ///
+    /// This is `import_as_of` with the frontier `self.advance_frontier()`: unlike `import`, which replays each
+    /// historical batch of the arrangement, this method accumulates the history into a single batch, and only
+    /// batches that arrive after the import are presented in addition to the snapshot. This is appropriate for queries that arrive late, and which need only
+    /// the current contents of the arrangement and its changes going forward.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::Configuration;
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::arrange::ArrangeBySelf;
+    /// use differential_dataflow::operators::reduce::Reduce;
+    /// use differential_dataflow::trace::TraceReader;
+    ///
+    /// fn main() {
+    ///     ::timely::execute(Configuration::Thread, |worker| {
+    ///
+    ///         // create a first dataflow
+    ///         let mut trace = worker.dataflow::<u32,_,_>(|scope| {
+    ///             scope.new_collection_from(0 .. 10).1
+    ///                  .arrange_by_self()
+    ///                  .trace
+    ///         });
+    ///
+    ///         // do some work.
+    ///         worker.step();
+    ///         worker.step();
+    ///
+    ///         // allow the trace to compact, and import a snapshot.
+    ///         trace.advance_by(&[1]);
+    ///         worker.dataflow(move |scope| {
+    ///             trace.import_frontier(scope)
+    ///                  .reduce(move |_key, src, dst| dst.push((*src[0].0, 1)));
+    ///         });
+    ///
+    ///     }).unwrap();
+    /// }
+    /// ```
+    pub fn import_frontier<G>(&mut self, scope: &G) -> Arranged<G, TraceFrontier<TraceAgent<Tr>>>
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+        Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    {
+        self.import_frontier_core(scope, "ArrangedSourceFrontier").0
+    }
+
+    /// Same as `import_frontier`, but allows to name the source and returns a shutdown button.
+    pub fn import_frontier_core<G>(&mut self, scope: &G, name: &str) -> (Arranged<G, TraceFrontier<TraceAgent<Tr>>>, ShutdownButton<CapabilitySet<Tr::Time>>)
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+        Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    {
+        let frontier = self.advance.clone();
+        self.import_snapshot_core(scope, name, &frontier[..])
+    }
+
+    /// Imports a snapshot of the arrangement with times advanced by `frontier`, followed by subsequent batches.
+    fn import_snapshot_core<G>(&mut self, scope: &G, name: &str, frontier: &[Tr::Time]) -> (Arranged<G, TraceFrontier<TraceAgent<Tr>>>, ShutdownButton<CapabilitySet<Tr::Time>>)
+    where
+        G: Scope<Timestamp=Tr::Time>,
+        Tr::Time: Timestamp,
+        Tr::Key: Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+        Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    {
+        // The imported trace need not distinguish times before `frontier`.
+        let mut trace = self.clone();
+        trace.advance_by(frontier);
+        let frontier = frontier.to_vec();
+
+        let mut shutdown_button = None;
+
+        let stream = {
+
+            let frontier = frontier.clone();
+            let shutdown_button_ref = &mut shutdown_button;
+            source(scope, name, move |capability, info| {
+
+                let capabilities = Rc::new(RefCell::new(Some(CapabilitySet::new())));
+
+                // The listener is populated with historical batches, which we replace by a snapshot.
+                let activator = scope.activator_for(&info.address[..]);
+                let queue = self.new_listener(activator);
+                queue.1.borrow_mut().clear();
+                let mut snapshot = Some(self.snapshot_batch(&frontier[..]));
+
+                let activator = scope.activator_for(&info.address[..]);
+                *shutdown_button_ref = Some(ShutdownButton::new(capabilities.clone(), activator));
+
+                capabilities.borrow_mut().as_mut().unwrap().insert(capability);
+
+                move |output| {
+
+                    let mut capabilities = capabilities.borrow_mut();
+                    if let Some(ref mut capabilities) = *capabilities {
+
+                        if let Some(batch) = snapshot.take() {
+                            let upper = batch.upper().to_vec();
+                            if !batch.is_empty() {
+                                let delayed = capabilities.delayed(&Default::default());
+                                output.session(&delayed).give(BatchFrontier::make_from(batch, &frontier[..]));
+                            }
+                            capabilities.downgrade(&upper[..]);
+                        }
+
+                        let mut borrow = queue.1.borrow_mut();
+                        for instruction in borrow.drain(..) {
+                            match instruction {
+                                TraceReplayInstruction::Frontier(upper) => {
+                                    capabilities.downgrade(&upper[..]);
+                                },
+                                TraceReplayInstruction::Batch(batch, hint) => {
+                                    if let Some(time) = hint {
+                                        let delayed = capabilities.delayed(&time);
+                                        output.session(&delayed).give(BatchFrontier::make_from(batch, &frontier[..]));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+        };
+
+        let trace = TraceFrontier::make_from(trace, &frontier[..]);
+        (Arranged { stream, trace }, shutdown_button.unwrap())
+    }
+
+    /// Forms a single batch from the accumulated contents of the trace.
+    ///
+    /// The times of updates are advanced by `frontier` and consolidated, and the batch spans from the
+    /// minimal time up to the current upper frontier of the trace.
+    fn snapshot_batch(&mut self, frontier: &[Tr::Time]) -> Tr::Batch
+    where
+        Tr::Time: Timestamp,
+        Tr::Key: Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+        Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    {
+        let mut upper = Antichain::new();
+        self.read_upper(&mut upper);
+
+        let mut builder = <Tr::Batch as Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>>::Builder::new();
+        let mut times = Vec::new();
+
+        let (mut cursor, storage) = self.cursor();
+        while let Some(key) = cursor.get_key(&storage) {
+            while let Some(val) = cursor.get_val(&storage) {
+                cursor.map_times(&storage, |time, diff| {
+                    let mut time = time.clone();
+                    time.advance_by(frontier);
+                    times.push((time, diff.clone()));
+                });
+                ::consolidation::consolidate(&mut times);
+                for (time, diff) in times.drain(..) {
+                    builder.push((key.clone(), val.clone(), time, diff));
+                }
+                cursor.step_val(&storage);
+            }
+            cursor.step_key(&storage);
+        }
+
+        builder.done(&[Default::default()], upper.elements(), frontier)
+    }
 }
 
 
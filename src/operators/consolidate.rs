@@ -9,7 +9,7 @@
 use timely::dataflow::Scope;
 
 use ::{Collection, ExchangeData, Hashable};
-use ::difference::Semigroup;
+use ::difference::{Semigroup, CheckedSemigroup};
 use operators::arrange::ArrangeBySelf;
 
 /// An extension method for consolidating weighted streams.
@@ -115,3 +115,64 @@ where
             .as_collection()
     }
 }
+
+/// An extension method for consolidating weighted streams while detecting overflow.
+pub trait ConsolidateChecked<D: ExchangeData+Hashable> {
+    /// Aggregates the weights of equal records, panicking if their accumulation overflows.
+    ///
+    /// This method behaves as `consolidate_stream`, but accumulates differences with checked
+    /// addition. Should the accumulation overflow, the operator panics with the record, its time,
+    /// and the differences being added, rather than silently producing an incorrect difference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::consolidate::ConsolidateChecked;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(1 .. 10u32).1;
+    ///
+    ///         x.negate()
+    ///          .concat(&x)
+    ///          .consolidate_checked();
+    ///     });
+    /// }
+    /// ```
+    fn consolidate_checked(&self) -> Self;
+}
+
+impl<G: Scope, D, R> ConsolidateChecked<D> for Collection<G, D, R>
+where
+    D: ExchangeData+Hashable,
+    R: ExchangeData+CheckedSemigroup,
+    G::Timestamp: ::lattice::Lattice+Ord,
+ {
+    fn consolidate_checked(&self) -> Self {
+
+        use timely::dataflow::channels::pact::Pipeline;
+        use timely::dataflow::operators::Operator;
+        use collection::AsCollection;
+
+        self.inner
+            .unary(Pipeline, "ConsolidateChecked", |_cap, _info| {
+
+                let mut vector = Vec::new();
+                move |input, output| {
+                    input.for_each(|time, data| {
+                        data.swap(&mut vector);
+                        if let Err(overflow) = crate::consolidation::consolidate_updates_checked(&mut vector) {
+                            panic!("{}", overflow);
+                        }
+                        output.session(&time).give_vec(&mut vector);
+                    })
+                }
+            })
+            .as_collection()
+    }
+}
@@ -0,0 +1,161 @@
+//! Re-timing of out-of-order events to their event times.
+//!
+//! External sources often report events out of order: an event that happened at one time may only
+//! arrive once the source has moved on to later times. The operator in this module accepts records
+//! paired with their *event times*, and re-emits each record at its event time.
+//!
+//! The operator maintains a *watermark*, derived from its input frontier by a user-supplied policy,
+//! for example "the input frontier less ten seconds" to allow ten seconds of lateness. The operator
+//! holds back its output frontier at the watermark, which lets it produce updates at event times
+//! earlier than the input frontier. Updates are buffered in the batcher of an arrangement, as updates
+//! at their event times, until the watermark passes their event time. The batcher then seals them into
+//! a consolidated batch, whose updates are emitted at their event times, and the output is complete
+//! through the watermark. Records whose event times are less than the watermark of the time they
+//! arrive at are too late to be placed at their event time, and are instead routed to a separate
+//! collection of late records at the time they arrived.
+
+use timely::order::TotalOrder;
+use timely::dataflow::Scope;
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Operator, Map};
+
+use ::{Collection, Data, AsCollection};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use trace::{Batch, Batcher, BatchReader, Cursor};
+use trace::implementations::ord::OrdKeyBatch;
+
+/// Extension trait for re-timing records to their event times.
+pub trait EventTime<G: Scope, D: Data, R: Semigroup> where G::Timestamp: Lattice+TotalOrder+Ord {
+    /// Re-emits records `(data, event_time)` as `data` at `event_time`, once the watermark passes `event_time`.
+    ///
+    /// The watermark is computed by applying `watermark` to the input frontier, and should be monotone
+    /// and no greater than its argument; the allowed lateness is the difference between the two. The
+    /// first returned collection contains the records whose event times are not less than the watermark
+    /// of their arrival time, at their event times. The second returned collection contains the remaining
+    /// late records, with their event times, at the times they arrived; these may be reconciled by other
+    /// means, or ignored to drop late records.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::event_time::EventTime;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let (mut input, events) = scope.new_collection();
+    ///
+    ///         // allow records to arrive up to two time units late.
+    ///         let (on_time, late) = events.event_time(|&time| if time > 2 { time - 2 } else { 0 });
+    ///
+    ///         on_time.assert_contents_at(4, vec![('a', 1), ('b', 1)]);
+    ///         late.assert_contents_at(5, vec![(('c', 2), 1)]);
+    ///
+    ///         input.insert(('a', 1));
+    ///         input.advance_to(5);
+    ///         input.insert(('b', 4));
+    ///         input.insert(('c', 2));
+    ///     });
+    /// }
+    /// ```
+    fn event_time<W>(&self, watermark: W) -> (Collection<G, D, R>, Collection<G, (D, G::Timestamp), R>)
+    where
+        W: Fn(&G::Timestamp)->G::Timestamp+'static;
+}
+
+impl<G, D, R> EventTime<G, D, R> for Collection<G, (D, G::Timestamp), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+TotalOrder+Ord,
+    D: Data,
+    R: Semigroup,
+{
+    fn event_time<W>(&self, watermark: W) -> (Collection<G, D, R>, Collection<G, (D, G::Timestamp), R>)
+    where
+        W: Fn(&G::Timestamp)->G::Timestamp+'static,
+    {
+        let tagged =
+        self.inner
+            .unary_frontier(Pipeline, "EventTime", move |capability, _info| {
+
+                // The capability is held at the watermark, which bounds the times of on-time records.
+                let mut capability = Some(capability);
+
+                // On-time updates whose event times the watermark has not yet passed, at their event times.
+                let mut pending = <OrdKeyBatch<D, G::Timestamp, R> as Batch<D, (), G::Timestamp, R>>::Batcher::new();
+                let mut buffer = Vec::new();
+                let mut on_time = Vec::new();
+
+                move |input, output| {
+
+                    input.for_each(|time, data| {
+                        data.swap(&mut buffer);
+                        let mut session = output.session(&time);
+                        for ((data, event_time), time, diff) in buffer.drain(..) {
+                            // records are late if their event time is less than the watermark at their arrival.
+                            let is_on_time = capability.as_ref().map(|cap| watermark(&time).join(cap.time()).less_equal(&event_time));
+                            if is_on_time == Some(true) {
+                                on_time.push(((data, ()), event_time, diff));
+                            }
+                            else {
+                                session.give((Err((data, event_time)), time, diff));
+                            }
+                        }
+                        pending.push_batch(&mut on_time);
+                    });
+
+                    // Determine the new watermark, or none if the input is complete.
+                    let next = input.frontier().frontier().iter().next().map(|time| watermark(time));
+
+                    if let Some(cap) = capability.as_mut() {
+
+                        let next = next.map(|time| time.join(cap.time()));
+
+                        // Emit the updates the watermark has passed, at their event times.
+                        if next.as_ref() != Some(cap.time()) {
+                            let upper = next.iter().cloned().collect::<Vec<_>>();
+                            let batch = pending.seal(&upper[..]);
+                            if !batch.is_empty() {
+                                let mut session = output.session(&*cap);
+                                for_each_update(&batch, |data, time, diff| {
+                                    session.give((Ok(data.clone()), time.clone(), diff.clone()));
+                                });
+                            }
+                        }
+
+                        if let Some(ref time) = next {
+                            cap.downgrade(time);
+                        }
+                    }
+
+                    if next.is_none() {
+                        capability = None;
+                    }
+                }
+            });
+
+        let on_time = tagged.flat_map(|(result, time, diff)| result.ok().map(|data| (data, time, diff)));
+        let late = tagged.flat_map(|(result, time, diff)| result.err().map(|data| (data, time, diff)));
+
+        (on_time.as_collection(), late.as_collection())
+    }
+}
+
+/// Calls `logic` with each update of a batch without values.
+fn for_each_update<K, T, R, B, L>(batch: &B, mut logic: L)
+where
+    B: BatchReader<K, (), T, R>,
+    L: FnMut(&K, &T, &R),
+{
+    let mut cursor = batch.cursor();
+    while cursor.key_valid(batch) {
+        let key = cursor.key(batch);
+        cursor.map_times(batch, |time, diff| logic(key, time, diff));
+        cursor.step_key(batch);
+    }
+}
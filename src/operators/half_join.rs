@@ -0,0 +1,209 @@
+//! Match a stream of updates against an arrangement, without maintaining the stream.
+//!
+//! A `half_join` responds to each update in a stream of updates by looking up matching records in
+//! an arrangement, accumulated at times less or equal to the time of the update. Unlike `join_core`,
+//! updates to the arrangement do not produce output, and the stream of updates is not maintained.
+//!
+//! Half joins are the building blocks of delta queries, in which each input collection drives
+//! its own dataflow of half joins against arrangements of the other inputs. To avoid producing
+//! the same output twice for simultaneous changes to multiple inputs, each input is ordered and
+//! arrangements of inputs later in the order are presented at times strictly less than those of
+//! the updates. This is most easily done with the `AltNeu` timestamp in the `lattice` module,
+//! entering arrangements with `enter_at` at either `AltNeu::alt` or `AltNeu::neu` times.
+
+use std::collections::HashMap;
+use std::ops::Mul;
+
+use timely::PartialOrder;
+use timely::dataflow::Scope;
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
+use timely::dataflow::operators::Operator;
+use timely::progress::frontier::Antichain;
+
+use timely_sort::Unsigned;
+
+use ::{Data, ExchangeData, Collection, AsCollection, Hashable};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use operators::arrange::Arranged;
+use trace::{Cursor, TraceReader, BatchReader};
+
+/// Extension trait for matching streams of updates against an arrangement.
+pub trait HalfJoin<G: Scope, K, V2, R2> where G::Timestamp: Lattice+Ord {
+    /// Matches each update `((key, val1), time, diff)` against the arrangement at times less or equal to `time`.
+    ///
+    /// For each value `val2` associated with `key` in the arrangement, with accumulated difference `sum` at `time`,
+    /// the output contains the results of `logic(key, val1, val2)` at `time` with difference `diff * sum`.
+    /// Updates are held back until the arrangement is complete through their time.
+    ///
+    /// # Examples
+    ///
+    /// This example uses a delta query to join two collections. Each collection drives a half join against
+    /// the arrangement of the other collection, where the second collection is presented at "neu" times so that
+    /// simultaneous changes to both inputs are matched only once.
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::dataflow::Scope;
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::lattice::AltNeu;
+    /// use differential_dataflow::operators::Join;
+    /// use differential_dataflow::operators::arrange::ArrangeByKey;
+    /// use differential_dataflow::operators::half_join::HalfJoin;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(vec![(0u32, 'a'), (1, 'b'), (1, 'c')]).1;
+    ///         let y = scope.new_collection_from(vec![(0u32, 'A'), (1, 'B'), (2, 'C')]).1;
+    ///
+    ///         let x_arranged = x.arrange_by_key();
+    ///         let y_arranged = y.arrange_by_key();
+    ///
+    ///         let delta = scope.scoped::<AltNeu<_>,_,_>("DeltaQuery", |inner| {
+    ///
+    ///             let x_alt = x_arranged.enter_at(inner, |_,_,t| AltNeu::alt(t.clone()));
+    ///             let y_neu = y_arranged.enter_at(inner, |_,_,t| AltNeu::neu(t.clone()));
+    ///
+    ///             // dQ/dx := dx(k,a), y(k,b) and dQ/dy := dy(k,b), x(k,a)
+    ///             let dx = y_neu.half_join(&x.enter(inner), |_k, &a, &b| Some((a, b)));
+    ///             let dy = x_alt.half_join(&y.enter(inner), |_k, &b, &a| Some((a, b)));
+    ///
+    ///             dx.concat(&dy).leave()
+    ///         });
+    ///
+    ///         delta.assert_eq(&x.join_map(&y, |_k, &a, &b| (a, b)));
+    ///     });
+    /// }
+    /// ```
+    fn half_join<V, R, I, L>(&self, stream: &Collection<G, (K, V), R>, logic: L) -> Collection<G, I::Item, <R as Mul<R2>>::Output>
+    where
+        V: ExchangeData,
+        R: ExchangeData+Mul<R2>,
+        <R as Mul<R2>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K, &V, &V2)->I+'static;
+}
+
+impl<G, Tr> HalfJoin<G, Tr::Key, Tr::Val, Tr::R> for Arranged<G, Tr>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    Tr: TraceReader<Time=G::Timestamp>+Clone+'static,
+    Tr::Key: ExchangeData+Hashable,
+    Tr::Val: Clone+'static,
+    Tr::R: Semigroup,
+    Tr::Batch: BatchReader<Tr::Key, Tr::Val, G::Timestamp, Tr::R>+'static,
+    Tr::Cursor: Cursor<Tr::Key, Tr::Val, G::Timestamp, Tr::R>+'static,
+{
+    fn half_join<V, R, I, L>(&self, stream: &Collection<G, (Tr::Key, V), R>, mut logic: L) -> Collection<G, I::Item, <R as Mul<Tr::R>>::Output>
+    where
+        V: ExchangeData,
+        R: ExchangeData+Mul<Tr::R>,
+        <R as Mul<Tr::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&Tr::Key, &V, &Tr::Val)->I+'static,
+    {
+        let mut arrangement_trace = Some(self.trace.clone());
+
+        // Updates received but not yet matched, by the capability for their time.
+        let mut stash = HashMap::new();
+
+        let mut buffer1 = Vec::new();
+        let mut buffer2 = Vec::new();
+
+        let exchange = Exchange::new(move |update: &((Tr::Key, V), G::Timestamp, R)| (update.0).0.hashed().as_u64());
+
+        stream.inner.binary_frontier(&self.stream, exchange, Pipeline, "HalfJoin", move |_,_| move |input1, input2, output| {
+
+            // drain the first input, stashing updates.
+            input1.for_each(|capability, data| {
+                data.swap(&mut buffer1);
+                stash.entry(capability.retain())
+                     .or_insert(Vec::new())
+                     .extend(buffer1.drain(..))
+            });
+
+            // advance the `distinguish_since` frontier to allow all merges.
+            input2.for_each(|_, batches| {
+                batches.swap(&mut buffer2);
+                for batch in buffer2.drain(..) {
+                    if let Some(ref mut trace) = arrangement_trace {
+                        trace.distinguish_since(batch.upper());
+                    }
+                }
+            });
+
+            if let Some(ref mut trace) = arrangement_trace {
+
+                let frontier2 = input2.frontier();
+
+                for (capability, updates) in stash.iter_mut() {
+
+                    // defer updates at incomplete times.
+                    // NOTE: not all updates may be at complete times, but if this test fails then none of them are.
+                    if !frontier2.less_equal(capability.time()) {
+
+                        let mut session = output.session(capability);
+
+                        // sort updates for in-order cursor traversal.
+                        updates.sort_by(|x, y| (x.0).0.cmp(&(y.0).0));
+
+                        let (mut cursor, storage) = trace.cursor();
+
+                        for &((ref key, ref val1), ref time, ref diff) in updates.iter() {
+                            if !frontier2.less_equal(time) {
+                                cursor.seek_key(&storage, key);
+                                if cursor.get_key(&storage) == Some(key) {
+                                    while let Some(val2) = cursor.get_val(&storage) {
+                                        let mut sum: Option<Tr::R> = None;
+                                        cursor.map_times(&storage, |t, d| {
+                                            if t.less_equal(time) {
+                                                if let Some(ref mut sum) = sum { *sum += d; }
+                                                else { sum = Some(d.clone()); }
+                                            }
+                                        });
+                                        if let Some(sum) = sum {
+                                            if !sum.is_zero() {
+                                                let product = diff.clone() * sum;
+                                                for datum in logic(key, val1, val2) {
+                                                    session.give((datum, time.clone(), product.clone()));
+                                                }
+                                            }
+                                        }
+                                        cursor.step_val(&storage);
+                                    }
+                                    cursor.rewind_vals(&storage);
+                                }
+                            }
+                        }
+
+                        updates.retain(|update| frontier2.less_equal(&update.1));
+                    }
+                }
+            }
+
+            // drop fully processed capabilities.
+            stash.retain(|_, updates| !updates.is_empty());
+
+            // The compaction frontier depends on both input1 and stash.
+            let mut frontier = Antichain::new();
+            for time in input1.frontier().frontier().iter() {
+                frontier.insert(time.clone());
+            }
+            for capability in stash.keys() {
+                frontier.insert(capability.time().clone());
+            }
+            arrangement_trace.as_mut().map(|trace| trace.advance_by(frontier.elements()));
+
+            if input1.frontier().is_empty() && stash.is_empty() {
+                arrangement_trace = None;
+            }
+
+        }).as_collection()
+    }
+}
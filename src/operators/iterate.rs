@@ -14,6 +14,11 @@
 //! all paths from the input to the output of the loop involve consolidation, or (iii) you should
 //! be worried that logically cancelable differences may circulate indefinitely.
 //!
+//! The `iterate_bounded` operator guards against this by withholding differences from the loop
+//! after some number of rounds, and reporting the withheld differences as a second collection.
+//! If differential dataflow logging is enabled, the number of updates produced by the loop body
+//! in each round is reported as an `IterationEvent`, which can help to diagnose slow fixed points.
+//!
 //! # Details
 //!
 //! The `iterate` method is written using a `Variable`, which lets you define your own iterative
@@ -29,21 +34,32 @@
 //! and it can be used in most situations where a collection can be used. The act of setting a
 //! `Variable` consumes it and returns the corresponding `Collection`, preventing you from setting
 //! it multiple times.
+//!
+//! A `RecursiveGroup` automates the use of `Variable` for programs with many mutually recursive
+//! collections, as in Datalog-style programs. Each relation is declared by name, and may then be
+//! used by the rules of any relation. Rules are added as productions, and when the group is complete
+//! each relation is bound to the consolidated union of its productions, which is also the result
+//! that leaves the iterative scope.
 
 use std::fmt::Debug;
 use std::ops::Deref;
+use std::rc::Rc;
+use std::cell::RefCell;
 
 use timely::progress::{Timestamp, PathSummary};
 use timely::order::Product;
 
 use timely::dataflow::*;
+use timely::dataflow::scopes::{Child, ScopeParent};
 use timely::dataflow::scopes::child::Iterative;
-use timely::dataflow::operators::{Feedback, ConnectLoop, Map};
+use timely::progress::timestamp::Refines;
+use timely::dataflow::operators::{Feedback, ConnectLoop, Map, Filter};
 use timely::dataflow::operators::feedback::Handle;
 
-use ::{Data, Collection};
+use ::{Data, ExchangeData, Collection, AsCollection, Hashable};
 use ::difference::{Semigroup, Abelian};
 use lattice::Lattice;
+use operators::{Consolidate, Threshold};
 
 /// An extension trait for the `iterate` method.
 pub trait Iterate<G: Scope, D: Data, R: Semigroup> {
@@ -80,6 +96,73 @@ pub trait Iterate<G: Scope, D: Data, R: Semigroup> {
         where
             G::Timestamp: Lattice,
             for<'a> F: FnOnce(&Collection<Iterative<'a, G, u64>, D, R>)->Collection<Iterative<'a, G, u64>, D, R>;
+
+    /// Iteratively apply `logic` to the source collection for at most `max_rounds` rounds.
+    ///
+    /// The first returned collection is the result of the iteration, as for `iterate`. The second
+    /// collection contains the differences withheld from the loop at rounds `max_rounds` and beyond:
+    /// the difference between the result and the input to the final application of `logic`. This
+    /// collection is empty exactly when the iteration converged within `max_rounds` rounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Iterate;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // incrementing values never converges.
+    ///         let (result, residual) =
+    ///         scope.new_collection_from(vec![0u32]).1
+    ///              .iterate_bounded(5, |values| values.map(|x| x + 1));
+    ///
+    ///         // after five rounds, the final application of `logic` replaced `5` with `6`.
+    ///         let expected = scope.new_collection_from(vec![6]).1;
+    ///         let changes = scope.new_collection_from(vec![5]).1
+    ///                            .negate()
+    ///                            .concat(&expected);
+    ///
+    ///         result.assert_eq(&expected);
+    ///         residual.assert_eq(&changes);
+    ///     });
+    /// }
+    /// ```
+    fn iterate_bounded<F>(&self, max_rounds: u64, logic: F) -> (Collection<G, D, R>, Collection<G, D, R>)
+        where
+            G::Timestamp: Lattice,
+            for<'a> F: FnOnce(&Collection<Iterative<'a, G, u64>, D, R>)->Collection<Iterative<'a, G, u64>, D, R>;
+}
+
+/// Reports the number of updates in each round of `collection`, if differential logging is enabled.
+fn log_rounds<'a, G: Scope, D: Data, R: Semigroup>(collection: &Collection<Iterative<'a, G, u64>, D, R>)
+where G::Timestamp: Lattice {
+    let scope = collection.scope();
+    let logger = scope.log_register().get::<::logging::DifferentialEvent>("differential/arrange");
+    if let Some(logger) = logger {
+        let address = scope.addr();
+        collection.inspect_batch(move |time, data| {
+            logger.log(::logging::IterationEvent {
+                address: address.clone(),
+                round: time.inner,
+                updates: data.len(),
+            });
+        });
+    }
+}
+
+/// Splits `collection` into the updates before and at or after round `max_rounds`.
+fn split_rounds<'a, G: Scope, D: Data, R: Semigroup>(collection: &Collection<Iterative<'a, G, u64>, D, R>, max_rounds: u64)
+    -> (Collection<Iterative<'a, G, u64>, D, R>, Collection<Iterative<'a, G, u64>, D, R>)
+where G::Timestamp: Lattice {
+    assert!(max_rounds > 0, "iteration requires at least one round");
+    let within = collection.inner.filter(move |x| x.1.inner < max_rounds).as_collection();
+    let beyond = collection.inner.filter(move |x| x.1.inner >= max_rounds).as_collection();
+    (within, beyond)
 }
 
 impl<G: Scope, D: Ord+Data+Debug, R: Abelian> Iterate<G, D, R> for Collection<G, D, R> {
@@ -96,10 +179,27 @@ impl<G: Scope, D: Ord+Data+Debug, R: Abelian> Iterate<G, D, R> for Collection<G,
             // records are yielded out of the loop.
             let variable = Variable::new_from(self.enter(subgraph), Product::new(Default::default(), 1));
             let result = logic(&variable);
+            log_rounds(&result);
             variable.set(&result);
             result.leave()
         })
     }
+
+    fn iterate_bounded<F>(&self, max_rounds: u64, logic: F) -> (Collection<G, D, R>, Collection<G, D, R>)
+        where G::Timestamp: Lattice,
+              for<'a> F: FnOnce(&Collection<Iterative<'a, G, u64>, D, R>)->Collection<Iterative<'a, G, u64>, D, R> {
+
+        self.inner.scope().scoped("IterateBounded", |subgraph| {
+            // withhold differences from the loop from round `max_rounds` onward; the
+            // variable then stops changing, and the loop body produces no further changes.
+            let variable = Variable::new_from(self.enter(subgraph), Product::new(Default::default(), 1));
+            let result = logic(&variable);
+            log_rounds(&result);
+            let (within, beyond) = split_rounds(&result, max_rounds);
+            variable.set(&within);
+            (result.leave(), beyond.leave())
+        })
+    }
 }
 
 impl<G: Scope, D: Ord+Data+Debug, R: Semigroup> Iterate<G, D, R> for G {
@@ -119,11 +219,91 @@ impl<G: Scope, D: Ord+Data+Debug, R: Semigroup> Iterate<G, D, R> for G {
                 // records are yielded out of the loop.
                 let variable = SemigroupVariable::new(subgraph, Product::new(Default::default(), 1));
                 let result = logic(&variable);
+                log_rounds(&result);
                 variable.set(&result);
                 result.leave()
             }
         )
     }
+
+    fn iterate_bounded<F>(&self, max_rounds: u64, logic: F) -> (Collection<G, D, R>, Collection<G, D, R>)
+        where G::Timestamp: Lattice,
+              for<'a> F: FnOnce(&Collection<Iterative<'a, G, u64>, D, R>)->Collection<Iterative<'a, G, u64>, D, R> {
+
+        let mut clone = self.clone();
+        clone
+            .scoped("IterateBounded", |subgraph| {
+                // withhold differences from the loop from round `max_rounds` onward.
+                let variable = SemigroupVariable::new(subgraph, Product::new(Default::default(), 1));
+                let result = logic(&variable);
+                log_rounds(&result);
+                let (within, beyond) = split_rounds(&result, max_rounds);
+                variable.set(&within);
+                (result.leave(), beyond.leave())
+            }
+        )
+    }
+}
+
+/// An extension trait for the `iterate_delta` method.
+pub trait IterateDelta<G: Scope, D: Data, R: Abelian> {
+    /// Iteratively apply `logic` to the source collection and its per-round changes until convergence.
+    ///
+    /// The logic is provided with both the accumulated collection in each round and the changes to it
+    /// in that round, as produced by `Variable::delta`. Semi-naive rules can join only the changes with
+    /// other collections, rather than the whole of the accumulated collection. As with `iterate`, this
+    /// method does not automatically consolidate results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::{Join, Threshold};
+    /// use differential_dataflow::operators::iterate::IterateDelta;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let edges = scope.new_collection_from(vec![(0u32, 1u32), (1, 2), (2, 3)]).1;
+    ///
+    ///         // paths extend only those paths discovered in the previous round.
+    ///         let reach =
+    ///         edges.iterate_delta(|reach, delta| {
+    ///             let edges = edges.enter(&reach.scope());
+    ///             delta.map(|(x,y)| (y,x))
+    ///                  .join_map(&edges, |_y,&x,&z| (x,z))
+    ///                  .concat(&reach)
+    ///                  .distinct()
+    ///         });
+    ///
+    ///         let expected = scope.new_collection_from(vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]).1;
+    ///         reach.assert_eq(&expected);
+    ///     });
+    /// }
+    /// ```
+    fn iterate_delta<F>(&self, logic: F) -> Collection<G, D, R>
+        where
+            G::Timestamp: Lattice,
+            for<'a> F: FnOnce(&Collection<Iterative<'a, G, u64>, D, R>, &Collection<Iterative<'a, G, u64>, D, R>)->Collection<Iterative<'a, G, u64>, D, R>;
+}
+
+impl<G: Scope, D: Ord+Data+Debug, R: Abelian> IterateDelta<G, D, R> for Collection<G, D, R> {
+    fn iterate_delta<F>(&self, logic: F) -> Collection<G, D, R>
+        where G::Timestamp: Lattice,
+              for<'a> F: FnOnce(&Collection<Iterative<'a, G, u64>, D, R>, &Collection<Iterative<'a, G, u64>, D, R>)->Collection<Iterative<'a, G, u64>, D, R> {
+
+        self.inner.scope().scoped("IterateDelta", |subgraph| {
+            let variable = Variable::new_from(self.enter(subgraph), Product::new(Default::default(), 1));
+            let delta = variable.delta();
+            let result = logic(&variable, &delta);
+            log_rounds(&result);
+            variable.set(&result);
+            result.leave()
+        })
+    }
 }
 
 /// A recursively defined collection.
@@ -186,6 +366,22 @@ impl<G: Scope, D: Data, R: Abelian> Variable<G, D, R> where G::Timestamp: Lattic
         Variable { collection, feedback, source, step }
     }
 
+    /// The changes to the variable in each iteration.
+    ///
+    /// The returned collection accumulates at each time to the difference between the variable at that
+    /// time and at the preceding iteration, or in the first iteration to the variable itself. Rules that
+    /// only produce new results from new inputs, as in semi-naive evaluation, can use this collection in
+    /// place of the variable to avoid repeatedly considering the same inputs.
+    pub fn delta(&self) -> Collection<G, D, R> {
+        let step = self.step.clone();
+        self.collection
+            .negate()
+            .inner
+            .flat_map(move |(x,t,d)| step.results_in(&t).map(|t| (x,t,d)))
+            .as_collection()
+            .concat(&self.collection)
+    }
+
     /// Adds a new source of data to the `Variable`.
     pub fn set(self, result: &Collection<G, D, R>) -> Collection<G, D, R> {
         let step = self.step;
@@ -276,4 +472,250 @@ impl<G: Scope, D: Data, R: Semigroup> Deref for SemigroupVariable<G, D, R> where
     fn deref(&self) -> &Self::Target {
         &self.collection
     }
-}
\ No newline at end of file
+}
+
+/// A relation defined in a `RecursiveGroup`.
+///
+/// A relation dereferences to a `Collection`, the one corresponding to its value in each iteration,
+/// and it can be used by the productions of any relation in the group. Once the group is completed,
+/// the final value of the relation is available from `result` or, in a nested scope, `leave`.
+pub struct Relation<G: Scope, D: Data, R: Abelian>
+where G::Timestamp: Lattice {
+    name: String,
+    collection: Collection<G, D, R>,
+    productions: Rc<RefCell<Vec<Collection<G, D, R>>>>,
+    result: Rc<RefCell<Option<Collection<G, D, R>>>>,
+}
+
+impl<G: Scope, D: Data, R: Abelian> Clone for Relation<G, D, R> where G::Timestamp: Lattice {
+    fn clone(&self) -> Self {
+        Relation {
+            name: self.name.clone(),
+            collection: self.collection.clone(),
+            productions: self.productions.clone(),
+            result: self.result.clone(),
+        }
+    }
+}
+
+impl<G: Scope, D: Data, R: Abelian> Relation<G, D, R> where G::Timestamp: Lattice {
+    /// The name of the relation.
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Adds `production` to the definition of the relation.
+    ///
+    /// Panics if the group containing the relation has been completed.
+    pub fn add_production(&self, production: &Collection<G, D, R>) {
+        assert!(self.result.borrow().is_none(), "relation {:?} already completed", self.name);
+        self.productions.borrow_mut().push(production.clone());
+    }
+
+    /// The final value of the relation, the combination of its productions.
+    ///
+    /// Panics if the group containing the relation has not been completed.
+    pub fn result(&self) -> Collection<G, D, R> {
+        self.result
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| panic!("relation {:?} not yet completed", self.name))
+    }
+}
+
+impl<'a, G: Scope, T: Timestamp, D: Data, R: Abelian> Relation<Child<'a, G, T>, D, R>
+where
+    T: Refines<<G as ScopeParent>::Timestamp>+Lattice,
+{
+    /// Returns the final value of the relation to the containing scope.
+    ///
+    /// Panics if the group containing the relation has not been completed.
+    pub fn leave(&self) -> Collection<G, D, R> {
+        self.result().leave()
+    }
+}
+
+impl<G: Scope, D: Data, R: Abelian> Deref for Relation<G, D, R> where G::Timestamp: Lattice {
+    type Target = Collection<G, D, R>;
+    fn deref(&self) -> &Self::Target {
+        &self.collection
+    }
+}
+
+/// A group of mutually recursive relations.
+///
+/// Relations are declared with a name and an optional initial value, and their definitions are
+/// extended with productions that may use any relation in the group. Completing the group binds
+/// each relation to the combination of its productions: consolidated for `relation` and `relation_from`,
+/// and made distinct for `set_relation` and `set_relation_from`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate timely;
+/// extern crate differential_dataflow;
+///
+/// use timely::order::Product;
+/// use timely::dataflow::Scope;
+///
+/// use differential_dataflow::input::Input;
+/// use differential_dataflow::operators::Join;
+/// use differential_dataflow::operators::iterate::RecursiveGroup;
+///
+/// fn main() {
+///     ::timely::example(|scope| {
+///
+///         let edges = scope.new_collection_from(vec![(0u32, 1u32), (1, 2), (2, 3)]).1;
+///
+///         // reach(x,y) := edge(x,y)
+///         // reach(x,z) := reach(x,y), edge(y,z)
+///         // above(x,y) := reach(y,x)
+///         let (reach, above) = scope.iterative::<u64,_,_>(|nested| {
+///
+///             let edges = edges.enter(nested);
+///
+///             let mut group = RecursiveGroup::new(nested, Product::new(Default::default(), 1));
+///             let reach = group.set_relation_from("reach", &edges);
+///             let above = group.set_relation("above");
+///
+///             reach.add_production(&reach.map(|(x,y)| (y,x)).join_map(&edges, |_y,&x,&z| (x,z)));
+///             above.add_production(&reach.map(|(x,y)| (y,x)));
+///
+///             group.complete();
+///             (reach.leave(), above.leave())
+///         });
+///
+///         let expected = scope.new_collection_from(vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]).1;
+///         reach.assert_eq(&expected);
+///         above.assert_eq(&expected.map(|(x,y)| (y,x)));
+///     });
+/// }
+/// ```
+pub struct RecursiveGroup<'a, G: Scope+'a>
+where G::Timestamp: Lattice {
+    scope: G,
+    step: <G::Timestamp as Timestamp>::Summary,
+    names: Vec<String>,
+    completions: Vec<Box<FnMut()+'a>>,
+}
+
+impl<'a, G: Scope+'a> RecursiveGroup<'a, G> where G::Timestamp: Lattice+Ord {
+
+    /// Creates a new empty group, whose relations advance by `step` in each iteration.
+    pub fn new(scope: &mut G, step: <G::Timestamp as Timestamp>::Summary) -> Self {
+        RecursiveGroup {
+            scope: scope.clone(),
+            step,
+            names: Vec::new(),
+            completions: Vec::new(),
+        }
+    }
+
+    /// Declares a new initially empty relation whose productions are consolidated.
+    pub fn relation<D, R>(&mut self, name: &str) -> Relation<G, D, R>
+    where
+        D: ExchangeData+Hashable,
+        R: ExchangeData+Abelian,
+    {
+        let empty = ::timely::dataflow::operators::generic::operator::empty(&self.scope).as_collection();
+        self.relation_from(name, &empty)
+    }
+
+    /// Declares a new relation initialized with `source`, whose productions are consolidated.
+    ///
+    /// The source is also a production of the relation, and contributes to its final value.
+    pub fn relation_from<D, R>(&mut self, name: &str, source: &Collection<G, D, R>) -> Relation<G, D, R>
+    where
+        D: ExchangeData+Hashable,
+        R: ExchangeData+Abelian,
+    {
+        self.define(name, source, |combined| combined.consolidate())
+    }
+
+    /// Declares a new initially empty relation whose productions are made distinct.
+    pub fn set_relation<D>(&mut self, name: &str) -> Relation<G, D, isize>
+    where
+        D: ExchangeData+Hashable,
+    {
+        let empty = ::timely::dataflow::operators::generic::operator::empty(&self.scope).as_collection();
+        self.set_relation_from(name, &empty)
+    }
+
+    /// Declares a new relation initialized with `source`, whose productions are made distinct.
+    ///
+    /// The source is also a production of the relation, and contributes to its final value.
+    pub fn set_relation_from<D>(&mut self, name: &str, source: &Collection<G, D, isize>) -> Relation<G, D, isize>
+    where
+        D: ExchangeData+Hashable,
+    {
+        self.define(name, source, |combined| combined.distinct())
+    }
+
+    /// Declares a relation whose final value is `finish` applied to the union of its productions.
+    fn define<D, R, F>(&mut self, name: &str, source: &Collection<G, D, R>, finish: F) -> Relation<G, D, R>
+    where
+        D: Data,
+        R: Abelian,
+        F: Fn(&Collection<G, D, R>)->Collection<G, D, R>+'a,
+    {
+        self.declare(name);
+
+        let variable = Variable::new_from(source.clone(), self.step.clone());
+        let relation = Relation {
+            name: name.to_owned(),
+            collection: (*variable).clone(),
+            productions: Rc::new(RefCell::new(vec![source.clone()])),
+            result: Rc::new(RefCell::new(None)),
+        };
+
+        let mut scope = self.scope.clone();
+        let productions = relation.productions.clone();
+        let result = relation.result.clone();
+        let mut variable = Some(variable);
+        self.completions.push(Box::new(move || {
+            if let Some(variable) = variable.take() {
+                let combined = ::collection::concatenate(&mut scope, productions.borrow().iter().cloned());
+                let combined = finish(&combined);
+                variable.set(&combined);
+                *result.borrow_mut() = Some(combined);
+            }
+        }));
+
+        relation
+    }
+
+    /// Reserves `name` for a relation or an entered collection.
+    fn declare(&mut self, name: &str) {
+        assert!(!self.names.iter().any(|n| n == name), "name {:?} already declared", name);
+        self.names.push(name.to_owned());
+    }
+
+    /// The names of the relations and entered collections of the group, in order of declaration.
+    pub fn names(&self) -> &[String] { &self.names }
+
+    /// Binds each relation to the combination of its productions.
+    ///
+    /// Productions can no longer be added to the relations, and their final values are available.
+    pub fn complete(mut self) {
+        for completion in self.completions.iter_mut() {
+            (completion)();
+        }
+    }
+}
+
+impl<'a, 'b, G: Scope, T: Timestamp> RecursiveGroup<'a, Child<'b, G, T>>
+where
+    T: Refines<<G as ScopeParent>::Timestamp>+Lattice+Ord,
+    Child<'b, G, T>: 'a,
+{
+    /// Brings `collection` from the containing scope into the group under `name`.
+    ///
+    /// Entered collections are not recursive, and can be used by the productions of any relation.
+    /// Their names share a namespace with the relations of the group.
+    pub fn enter<D, R>(&mut self, name: &str, collection: &Collection<G, D, R>) -> Collection<Child<'b, G, T>, D, R>
+    where
+        D: Data,
+        R: Semigroup,
+    {
+        self.declare(name);
+        collection.enter(&self.scope)
+    }
+}
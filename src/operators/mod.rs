@@ -18,6 +18,10 @@ pub mod iterate;
 pub mod join;
 pub mod count;
 pub mod threshold;
+pub mod sink;
+pub mod skew;
+pub mod half_join;
+pub mod event_time;
 
 use ::difference::Semigroup;
 use lattice::Lattice;
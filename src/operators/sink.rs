@@ -0,0 +1,174 @@
+//! Deliver consolidated changes to collections outside of the dataflow.
+//!
+//! The `inspect` operators report updates as they flow past, with no guarantee about how they
+//! are batched or whether they have been consolidated. The operators in this module instead hold
+//! updates back until the input frontier has passed their time, at which point all updates for
+//! the time are consolidated and delivered together, exactly once.
+//!
+//! In addition to times with updates, each time the input frontier passes through is reported,
+//! even if there are no updates at that time. This allows recipients to distinguish "no changes"
+//! from "not yet known", for example to flush or acknowledge output for each closed time.
+//!
+//! Updates are exchanged by the hash of their data, and each worker delivers only the updates
+//! it is responsible for. Each worker observes the same sequence of closed times.
+
+use std::io::Write;
+
+use timely::dataflow::Scope;
+use timely::dataflow::operators::Operator;
+use timely::dataflow::channels::pact::Exchange;
+
+use timely_sort::Unsigned;
+
+use ::{Collection, ExchangeData, Hashable};
+use ::difference::Semigroup;
+use lattice::Lattice;
+
+/// Extension trait for delivering consolidated updates for each closed time.
+pub trait Sink<G: Scope, D, R>
+where
+    G::Timestamp: Lattice+Ord,
+{
+    /// Delivers consolidated updates for each time once the input frontier passes it.
+    ///
+    /// The supplied `logic` is called once for each closed time, in an order consistent with the
+    /// partial order on times, with the consolidated updates at that time. Times the input frontier
+    /// passes through that have no updates are reported with an empty vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::sink::Sink;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(1 .. 10u32).1;
+    ///
+    ///         // each record and its negation cancel, leaving nothing.
+    ///         x.negate()
+    ///          .concat(&x)
+    ///          .sink_consolidated(|_time, updates| assert!(updates.is_empty()));
+    ///     });
+    /// }
+    /// ```
+    fn sink_consolidated<F>(&self, logic: F)
+    where
+        F: FnMut(&G::Timestamp, Vec<(D, R)>)+'static;
+
+    /// Writes consolidated updates for each closed time to `writer`.
+    ///
+    /// Each update is rendered as a line by `format`, for example as a JSON object. After the updates
+    /// for a time are written, including for times with no updates, the writer is flushed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::sink::Sink;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         scope.new_collection_from(1 .. 10u32).1
+    ///              .sink_writer(::std::io::sink(), |time, data, diff| {
+    ///                  format!("{{\"data\":{},\"time\":{:?},\"diff\":{}}}", data, time, diff)
+    ///              });
+    ///     });
+    /// }
+    /// ```
+    fn sink_writer<W, F>(&self, mut writer: W, format: F)
+    where
+        W: Write+'static,
+        F: Fn(&G::Timestamp, &D, &R)->String+'static,
+    {
+        self.sink_consolidated(move |time, updates| {
+            for (data, diff) in updates.iter() {
+                writeln!(writer, "{}", format(time, data, diff)).expect("failed to write update");
+            }
+            writer.flush().expect("failed to flush writer");
+        })
+    }
+}
+
+impl<G, D, R> Sink<G, D, R> for Collection<G, D, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    D: ExchangeData+Hashable,
+    R: ExchangeData+Semigroup,
+{
+    fn sink_consolidated<F>(&self, mut logic: F)
+    where
+        F: FnMut(&G::Timestamp, Vec<(D, R)>)+'static,
+    {
+        let exchange = Exchange::new(|update: &(D,G::Timestamp,R)| update.0.hashed().as_u64());
+
+        // Updates at times not yet closed.
+        let mut pending = Vec::new();
+        // Elements of observed input frontiers that have not yet closed.
+        let mut frontier_times: Vec<G::Timestamp> = Vec::new();
+
+        let mut buffer = Vec::new();
+        let mut closed = Vec::new();
+
+        self.inner.sink(exchange, "SinkConsolidated", move |input| {
+
+            input.for_each(|_time, data| {
+                data.swap(&mut buffer);
+                pending.extend(buffer.drain(..));
+            });
+
+            let frontier = input.frontier();
+
+            // Record each frontier element, so that it is reported once passed.
+            for time in frontier.frontier().iter() {
+                if !frontier_times.contains(time) {
+                    frontier_times.push(time.clone());
+                }
+            }
+
+            // Determine the closed times, both with and without updates.
+            closed.clear();
+            closed.extend(pending.iter().map(|x: &(D,G::Timestamp,R)| &x.1).filter(|t| !frontier.less_equal(t)).cloned());
+            closed.extend(frontier_times.iter().filter(|t| !frontier.less_equal(t)).cloned());
+            frontier_times.retain(|t| frontier.less_equal(t));
+
+            if !closed.is_empty() {
+
+                // Lexicographic order on times is consistent with their partial order.
+                closed.sort();
+                closed.dedup();
+
+                let mut ready = Vec::new();
+                let mut index = 0;
+                while index < pending.len() {
+                    if !frontier.less_equal(&pending[index].1) {
+                        ready.push(pending.swap_remove(index));
+                    }
+                    else {
+                        index += 1;
+                    }
+                }
+                ready.sort_by(|x,y| (&x.1, &x.0).cmp(&(&y.1, &y.0)));
+
+                let mut ready = ready.into_iter().peekable();
+                for time in closed.drain(..) {
+                    let mut updates = Vec::new();
+                    while ready.peek().map(|x| x.1 == time).unwrap_or(false) {
+                        let (data, _, diff) = ready.next().unwrap();
+                        updates.push((data, diff));
+                    }
+                    ::consolidation::consolidate(&mut updates);
+                    logic(&time, updates);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,173 @@
+//! Skew-aware routing of keyed data.
+//!
+//! Arrangements exchange records by the hash of their key, which places all records with the same
+//! key at the same worker. When a few keys account for much of the data, the workers responsible for
+//! them do a disproportionate amount of the work. The operators in this module instead identify
+//! "heavy" keys from counts over the input, and route the records of these keys differently.
+//!
+//! Records are tagged with a "salt", and the pair of key and salt is used as the key for arrangement.
+//! Records with light keys have salt zero, and are routed as usual. Records of heavy keys on one side
+//! of a join are *split*, receiving a salt derived from their value, and so are spread across workers.
+//! Records of heavy keys on the other side of the join are *replicated*, once for each salt, so that
+//! each fragment of a split key meets all records with that key exactly once.
+//!
+//! The set of heavy keys is itself a collection, and may change as the input changes. The salts of
+//! records change with it, by retracting records at their old salts and introducing them at new salts,
+//! so that the results are correct at all times, as long as both sides use the same heavy keys.
+
+use timely::dataflow::Scope;
+use timely::dataflow::operators::Broadcast;
+use timely::dataflow::channels::pact::Pipeline;
+use timely::worker::AsWorker;
+
+use timely_sort::Unsigned;
+
+use ::{Collection, AsCollection, ExchangeData, Hashable};
+use lattice::Lattice;
+use operators::{Count, JoinCore};
+use operators::consolidate::ConsolidateStream;
+use operators::arrange::{Arrange, ArrangeByKey};
+use trace::implementations::ord::{OrdKeySpine, OrdValSpine};
+
+/// Determines the keys with more than `threshold` records.
+///
+/// The keys are pre-aggregated at each worker before they are exchanged for counting, so that a heavy
+/// key does not overload the worker responsible for counting it.
+pub fn heavy_keys<G, K, V>(collection: &Collection<G, (K, V), isize>, threshold: isize) -> Collection<G, K, isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+{
+    collection
+        .map(|(key, _val)| key)
+        .consolidate_stream()
+        .count()
+        .filter(move |&(_, count)| count > threshold)
+        .map(|(key, _count)| key)
+}
+
+/// Restricts `collection` to records whose keys are in `heavy`, without exchanging its records.
+///
+/// The heavy keys are broadcast to all workers, each of which locally selects its records with heavy keys.
+fn local_heavy<G, K, V>(collection: &Collection<G, (K, V), isize>, heavy: &Collection<G, K, isize>) -> Collection<G, (K, V), isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+{
+    let heavy =
+    heavy
+        .inner
+        .broadcast()
+        .as_collection()
+        .arrange_core::<_, OrdKeySpine<K, G::Timestamp, isize>>(Pipeline, "HeavyKeys");
+
+    collection
+        .arrange_core::<_, OrdValSpine<K, V, G::Timestamp, isize>>(Pipeline, "LocalRecords")
+        .join_core(&heavy, |key, val, &()| Some((key.clone(), val.clone())))
+}
+
+/// Tags records with salts, spreading records of heavy keys across `0 .. peers` by their values.
+///
+/// The result should be joined with the result of `replicate_heavy` on the other input, with the same heavy keys.
+pub fn split_heavy<G, K, V>(collection: &Collection<G, (K, V), isize>, heavy: &Collection<G, K, isize>) -> Collection<G, ((K, u64), V), isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData+Hashable,
+{
+    let peers = collection.scope().peers() as u64;
+    let heavy = local_heavy(collection, heavy);
+
+    // Move records of heavy keys from salt zero to a salt determined by their value.
+    collection
+        .map(|(key, val)| ((key, 0), val))
+        .concat(&heavy.map(|(key, val)| ((key, 0), val)).negate())
+        .concat(&heavy.map(move |(key, val)| {
+            let salt = val.hashed().as_u64() % peers;
+            ((key, salt), val)
+        }))
+}
+
+/// Tags records with salts, replicating records of heavy keys for each salt in `0 .. peers`.
+///
+/// The result should be joined with the result of `split_heavy` on the other input, with the same heavy keys.
+pub fn replicate_heavy<G, K, V>(collection: &Collection<G, (K, V), isize>, heavy: &Collection<G, K, isize>) -> Collection<G, ((K, u64), V), isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+{
+    let peers = collection.scope().peers() as u64;
+    let heavy = local_heavy(collection, heavy);
+
+    // Add copies of records of heavy keys for each non-zero salt.
+    collection
+        .map(|(key, val)| ((key, 0), val))
+        .concat(&heavy.flat_map(move |(key, val)| (1 .. peers).map(move |salt| ((key.clone(), salt), val.clone()))))
+}
+
+/// Skew-aware join implementations for `(key,val)` data.
+pub trait JoinSkewed<G: Scope, K: ExchangeData, V: ExchangeData> where G::Timestamp: Lattice+Ord {
+    /// Matches pairs `(key,val1)` and `(key,val2)` based on `key` and then applies a function.
+    ///
+    /// Keys with more than `threshold` records in `self` are split across workers, and the records of `other`
+    /// with these keys are replicated to each worker. The results are the same as those of `join_map`, but
+    /// the work for heavy keys is shared among workers. The records of `other` with heavy keys are replicated
+    /// once for each worker, and so this is best used when `other` has few records for the heavy keys of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Join;
+    /// use differential_dataflow::operators::skew::JoinSkewed;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // key zero has many more records than other keys.
+    ///         let x = scope.new_collection_from((0 .. 100u32).map(|i| (if i < 90 { 0 } else { i }, i))).1;
+    ///         let y = scope.new_collection_from((0 .. 100u32).map(|i| (i, i))).1;
+    ///
+    ///         x.join_skewed(&y, 10, |_key, &a, &b| (a, b))
+    ///          .assert_eq(&x.join_map(&y, |_key, &a, &b| (a, b)));
+    ///     });
+    /// }
+    /// ```
+    fn join_skewed<V2, D, L>(&self, other: &Collection<G, (K, V2), isize>, threshold: isize, logic: L) -> Collection<G, D, isize>
+    where
+        V2: ExchangeData,
+        D: ExchangeData,
+        L: Fn(&K, &V, &V2)->D+'static;
+}
+
+impl<G, K, V> JoinSkewed<G, K, V> for Collection<G, (K, V), isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData+Hashable,
+{
+    fn join_skewed<V2, D, L>(&self, other: &Collection<G, (K, V2), isize>, threshold: isize, logic: L) -> Collection<G, D, isize>
+    where
+        V2: ExchangeData,
+        D: ExchangeData,
+        L: Fn(&K, &V, &V2)->D+'static,
+    {
+        let heavy = heavy_keys(self, threshold);
+
+        let split = split_heavy(self, &heavy).arrange_by_key();
+        let replicated = replicate_heavy(other, &heavy).arrange_by_key();
+
+        split.join_core(&replicated, move |&(ref key, _salt), val1, val2| Some(logic(key, val1, val2)))
+    }
+}
@@ -2,7 +2,9 @@
 
 use timely::progress::frontier::Antichain;
 
-use ::difference::Semigroup;
+use std::fmt::Debug;
+
+use ::difference::{Semigroup, catch_panic};
 
 use lattice::Lattice;
 use trace::{Batch, Batcher, Builder};
@@ -17,8 +19,8 @@ pub struct MergeBatcher<K: Ord, V: Ord, T: Ord, R: Semigroup, B: Batch<K, V, T,
 
 impl<K, V, T, R, B> Batcher<K, V, T, R, B> for MergeBatcher<K, V, T, R, B>
 where
-    K: Ord+Clone,
-    V: Ord+Clone,
+    K: Ord+Clone+Debug,
+    V: Ord+Clone+Debug,
     T: Lattice+Ord+Clone,
     R: Semigroup,
     B: Batch<K, V, T, R>,
@@ -176,7 +178,7 @@ pub struct MergeSorter<D: Ord, T: Ord, R: Semigroup> {
     stash: Vec<Vec<(D, T, R)>>,
 }
 
-impl<D: Ord, T: Ord, R: Semigroup> MergeSorter<D, T, R> {
+impl<D: Ord+Debug, T: Ord, R: Semigroup> MergeSorter<D, T, R> {
 
     #[inline]
     pub fn new() -> Self { MergeSorter { queue: Vec::new(), stash: Vec::new() } }
@@ -275,7 +277,10 @@ impl<D: Ord, T: Ord, R: Semigroup> MergeSorter<D, T, R> {
                     Ordering::Equal   => {
                         let (data1, time1, mut diff1) = head1.pop();
                         let (_data2, _time2, diff2) = head2.pop();
-                        diff1 += &diff2;
+                        // Differences may panic when they overflow, without knowing their record.
+                        if let Err(message) = catch_panic(|| diff1 += &diff2) {
+                            panic!("{} (batching record {:?})", message, data1);
+                        }
                         if !diff1.is_zero() {
                             unsafe { push_unchecked(&mut result, (data1, time1, diff1)); }
                         }
@@ -9,8 +9,9 @@
 //! and should consume fewer resources (computation and memory) when it applies.
 
 use std::rc::Rc;
+use std::fmt::Debug;
 
-use ::difference::Semigroup;
+use ::difference::{Semigroup, catch_panic};
 use lattice::Lattice;
 
 use trace::layers::{Trie, TupleBuilder};
@@ -60,7 +61,7 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+'static,
 }
 
 impl<K, V, T, R> Batch<K, V, T, R> for OrdValBatch<K, V, T, R>
-where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Semigroup {
+where K: Ord+Clone+Debug+'static, V: Ord+Clone+Debug+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Semigroup {
 	type Batcher = MergeBatcher<K, V, T, R, Self>;
 	type Builder = OrdValBuilder<K, V, T, R>;
 	type Merger = OrdValMerger<K, V, T, R>;
@@ -71,7 +72,7 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 }
 
 impl<K, V, T, R> OrdValBatch<K, V, T, R>
-where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Semigroup {
+where K: Ord+Clone+Debug+'static, V: Ord+Clone+Debug+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Semigroup {
 	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>>, frontier: &[T], key_pos: usize) {
 
 		let key_start = key_pos;
@@ -92,27 +93,38 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 
 		// 2. For each `(val, off)` pair, sort the range, compact, and rewrite `off`.
 		//    This may leave `val` with an empty range; filtering happens in step 3.
+		//    Panics while accumulating differences are reported with the key and value.
 		let mut write_position = time_start;
-		for i in val_start .. layer.vals.keys.len() {
+		let mut current = val_start;
+		let caught = catch_panic(|| {
+			for i in val_start .. layer.vals.keys.len() {
 
-			// NB: batch.layer.vals.offs[i+1] will be used next iteration, and should not be changed.
-			//     we will change batch.layer.vals.offs[i] in this iteration, from `write_position`'s
-			//     initial value.
+				// NB: batch.layer.vals.offs[i+1] will be used next iteration, and should not be changed.
+				//     we will change batch.layer.vals.offs[i] in this iteration, from `write_position`'s
+				//     initial value.
 
-			let lower = layer.vals.offs[i];
-			let upper = layer.vals.offs[i+1];
+				current = i;
 
-			layer.vals.offs[i] = write_position;
+				let lower = layer.vals.offs[i];
+				let upper = layer.vals.offs[i+1];
 
-			let updates = &mut layer.vals.vals.vals[..];
+				layer.vals.offs[i] = write_position;
 
-			// sort the range by the times (ignore the diffs; they will collapse).
-			let count = crate::consolidation::consolidate_slice(&mut updates[lower .. upper]);
+				let updates = &mut layer.vals.vals.vals[..];
 
-			for index in lower .. (lower + count) {
-				updates.swap(write_position, index);
-				write_position += 1;
+				// sort the range by the times (ignore the diffs; they will collapse).
+				let count = crate::consolidation::consolidate_slice(&mut updates[lower .. upper]);
+
+				for index in lower .. (lower + count) {
+					updates.swap(write_position, index);
+					write_position += 1;
+				}
 			}
+		});
+		if let Err(message) = caught {
+			// Key offsets are not rewritten until step 3, and locate the key of the value.
+			let key = (key_start .. layer.keys.len()).find(|&k| layer.offs[k+1] > current).expect("value without key");
+			panic!("{} (compacting key {:?}, value {:?})", message, layer.keys[key], layer.vals.keys[current]);
 		}
 		layer.vals.vals.vals.truncate(write_position);
 		layer.vals.offs[layer.vals.keys.len()] = write_position;
@@ -179,7 +191,7 @@ pub struct OrdValMerger<K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+O
 }
 
 impl<K, V, T, R> Merger<K, V, T, R, OrdValBatch<K, V, T, R>> for OrdValMerger<K, V, T, R>
-where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Semigroup {
+where K: Ord+Clone+Debug+'static, V: Ord+Clone+Debug+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Semigroup {
 	fn new(batch1: &OrdValBatch<K, V, T, R>, batch2: &OrdValBatch<K, V, T, R>) -> Self {
 
 		assert!(batch1.upper() == batch2.lower());
@@ -219,10 +231,17 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 
 		let initial_key_pos = self.result.keys.len();
 
-		// while both mergees are still active
-		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
-			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
-			effort = self.result.vals.vals.vals.len() - starting_updates;
+		// while both mergees are still active, reporting panics with the key being merged.
+		let (upper1, upper2) = (self.upper1, self.upper2);
+		let (result, lower1, lower2) = (&mut self.result, &mut self.lower1, &mut self.lower2);
+		let caught = catch_panic(|| {
+			while *lower1 < upper1 && *lower2 < upper2 && effort < *fuel {
+				result.merge_step((&source1.layer, &mut *lower1, upper1), (&source2.layer, &mut *lower2, upper2));
+				effort = result.vals.vals.vals.len() - starting_updates;
+			}
+		});
+		if let Err(message) = caught {
+			panic!("{} (merging key {:?})", message, source1.layer.keys[self.lower1]);
 		}
 
 		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
@@ -334,7 +353,7 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 }
 
 impl<K, T, R> Batch<K, (), T, R> for OrdKeyBatch<K, T, R>
-where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
+where K: Ord+Clone+Debug+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 	type Batcher = MergeBatcher<K, (), T, R, Self>;
 	type Builder = OrdKeyBuilder<K, T, R>;
 	type Merger = OrdKeyMerger<K, T, R>;
@@ -345,7 +364,7 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 }
 
 impl<K, T, R> OrdKeyBatch<K, T, R>
-where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
+where K: Ord+Clone+Debug+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedLeafBuilder<T, R>>, frontier: &[T], key_pos: usize) {
 
 		let key_start = key_pos;
@@ -365,27 +384,36 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 
 		// 2. For each `(val, off)` pair, sort the range, compact, and rewrite `off`.
 		//    This may leave `val` with an empty range; filtering happens in step 3.
+		//    Panics while accumulating differences are reported with the key.
 		let mut write_position = time_start;
-		for i in key_start .. layer.keys.len() {
+		let mut current = key_start;
+		let caught = catch_panic(|| {
+			for i in key_start .. layer.keys.len() {
 
-			// NB: batch.layer.vals.offs[i+1] will be used next iteration, and should not be changed.
-			//     we will change batch.layer.vals.offs[i] in this iteration, from `write_position`'s
-			//     initial value.
+				// NB: batch.layer.vals.offs[i+1] will be used next iteration, and should not be changed.
+				//     we will change batch.layer.vals.offs[i] in this iteration, from `write_position`'s
+				//     initial value.
 
-			let lower = layer.offs[i];
-			let upper = layer.offs[i+1];
+				current = i;
 
-			layer.offs[i] = write_position;
+				let lower = layer.offs[i];
+				let upper = layer.offs[i+1];
 
-			let updates = &mut layer.vals.vals[..];
+				layer.offs[i] = write_position;
 
-			// sort the range by the times (ignore the diffs; they will collapse).
-		 	let count = crate::consolidation::consolidate_slice(&mut updates[lower .. upper]);
+				let updates = &mut layer.vals.vals[..];
 
-			for index in lower .. (lower + count) {
-				updates.swap(write_position, index);
-				write_position += 1;
+				// sort the range by the times (ignore the diffs; they will collapse).
+			 	let count = crate::consolidation::consolidate_slice(&mut updates[lower .. upper]);
+
+				for index in lower .. (lower + count) {
+					updates.swap(write_position, index);
+					write_position += 1;
+				}
 			}
+		});
+		if let Err(message) = caught {
+			panic!("{} (compacting key {:?})", message, layer.keys[current]);
 		}
 		layer.vals.vals.truncate(write_position);
 		layer.offs[layer.keys.len()] = write_position;
@@ -423,7 +451,7 @@ pub struct OrdKeyMerger<K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: S
 }
 
 impl<K, T, R> Merger<K, (), T, R, OrdKeyBatch<K, T, R>> for OrdKeyMerger<K, T, R>
-where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
+where K: Ord+Clone+Debug+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 	fn new(batch1: &OrdKeyBatch<K, T, R>, batch2: &OrdKeyBatch<K, T, R>) -> Self {
 
 		assert!(batch1.upper() == batch2.lower());
@@ -463,10 +491,17 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Semigroup {
 
 		let initial_key_pos = self.result.keys.len();
 
-		// while both mergees are still active
-		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
-			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
-			effort = self.result.vals.vals.len() - starting_updates;
+		// while both mergees are still active, reporting panics with the key being merged.
+		let (upper1, upper2) = (self.upper1, self.upper2);
+		let (result, lower1, lower2) = (&mut self.result, &mut self.lower1, &mut self.lower2);
+		let caught = catch_panic(|| {
+			while *lower1 < upper1 && *lower2 < upper2 && effort < *fuel {
+				result.merge_step((&source1.layer, &mut *lower1, upper1), (&source2.layer, &mut *lower2, upper2));
+				effort = result.vals.vals.len() - starting_updates;
+			}
+		});
+		if let Err(message) = caught {
+			panic!("{} (merging key {:?})", message, source1.layer.keys[self.lower1]);
 		}
 
 		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
@@ -0,0 +1,344 @@
+//! Wrapper for traces restricted to a set of keys.
+//!
+//! Unlike `TraceFilter`, which applies a predicate to each `(key, val)` pair it visits, these wrappers
+//! restrict a trace to a contiguous range of keys or an explicit list of keys, and use `seek_key` to
+//! move directly between relevant keys. Keys outside the set are never visited, which makes it cheap
+//! to read a small part of a large shared arrangement.
+
+use std::rc::Rc;
+use std::ops::{Bound, RangeBounds};
+
+use trace::{TraceReader, BatchReader, Description};
+use trace::cursor::Cursor;
+
+/// A set of keys, described either as a range or as a sorted list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeySet<K> {
+    /// All keys within the bounds.
+    Range(Bound<K>, Bound<K>),
+    /// Exactly the keys in the list, which is sorted and deduplicated.
+    List(Vec<K>),
+}
+
+impl<K: Ord+Clone> KeySet<K> {
+    /// Creates a key set from a range of keys.
+    pub fn from_range<B: RangeBounds<K>>(range: B) -> Self {
+        KeySet::Range(clone_bound(range.start_bound()), clone_bound(range.end_bound()))
+    }
+    /// Creates a key set from a collection of keys, in any order.
+    pub fn from_keys<I: IntoIterator<Item=K>>(keys: I) -> Self {
+        let mut keys = keys.into_iter().collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+        KeySet::List(keys)
+    }
+    /// Returns true if `key` is an element of the set.
+    pub fn contains(&self, key: &K) -> bool {
+        match *self {
+            KeySet::Range(ref lower, ref upper) => above(key, lower) && below(key, upper),
+            KeySet::List(ref keys) => keys.binary_search(key).is_ok(),
+        }
+    }
+
+    /// Advances `cursor` to the first key in the set at or after its current key.
+    ///
+    /// Returns false if the cursor has no such key, in which case it should be treated as exhausted
+    /// even if the underlying cursor still has valid keys.
+    fn settle<V, T, R, C: Cursor<K, V, T, R>>(&self, cursor: &mut C, storage: &C::Storage) -> bool {
+        loop {
+            let key = match cursor.get_key(storage) {
+                Some(key) => key,
+                None => return false,
+            };
+            match *self {
+                KeySet::Range(ref lower, ref upper) => {
+                    if above(key, lower) {
+                        return below(key, upper);
+                    }
+                    match *lower {
+                        Bound::Included(ref lower) => cursor.seek_key(storage, lower),
+                        Bound::Excluded(ref lower) => {
+                            cursor.seek_key(storage, lower);
+                            if cursor.get_key(storage) == Some(lower) {
+                                cursor.step_key(storage);
+                            }
+                        },
+                        Bound::Unbounded => unreachable!(),
+                    }
+                },
+                KeySet::List(ref keys) => {
+                    match keys.binary_search(key) {
+                        Ok(_) => return true,
+                        Err(index) if index < keys.len() => cursor.seek_key(storage, &keys[index]),
+                        Err(_) => return false,
+                    }
+                },
+            }
+        }
+    }
+}
+
+fn clone_bound<K: Clone>(bound: Bound<&K>) -> Bound<K> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.clone()),
+        Bound::Excluded(key) => Bound::Excluded(key.clone()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn above<K: Ord>(key: &K, lower: &Bound<K>) -> bool {
+    match *lower {
+        Bound::Included(ref lower) => key >= lower,
+        Bound::Excluded(ref lower) => key > lower,
+        Bound::Unbounded => true,
+    }
+}
+
+fn below<K: Ord>(key: &K, upper: &Bound<K>) -> bool {
+    match *upper {
+        Bound::Included(ref upper) => key <= upper,
+        Bound::Excluded(ref upper) => key < upper,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Wrapper to restrict a trace to a set of keys.
+pub struct TraceKeys<Tr>
+where
+    Tr: TraceReader,
+{
+    trace: Tr,
+    keys: Rc<KeySet<Tr::Key>>,
+}
+
+impl<Tr> Clone for TraceKeys<Tr>
+where
+    Tr: TraceReader+Clone,
+{
+    fn clone(&self) -> Self {
+        TraceKeys {
+            trace: self.trace.clone(),
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+impl<Tr> TraceReader for TraceKeys<Tr>
+where
+    Tr: TraceReader,
+    Tr::Batch: Clone,
+    Tr::Key: Ord+Clone+'static,
+    Tr::Val: 'static,
+    Tr::Time: 'static,
+    Tr::R: 'static,
+{
+    type Key = Tr::Key;
+    type Val = Tr::Val;
+    type Time = Tr::Time;
+    type R = Tr::R;
+
+    type Batch = BatchKeys<Tr::Key, Tr::Val, Tr::Time, Tr::R, Tr::Batch>;
+    type Cursor = CursorKeys<Tr::Key, Tr::Val, Tr::Time, Tr::R, Tr::Cursor>;
+
+    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) {
+        let keys = self.keys.clone();
+        self.trace
+            .map_batches(|batch| f(&Self::Batch::make_from(batch.clone(), keys.clone())))
+    }
+
+    fn advance_by(&mut self, frontier: &[Tr::Time]) { self.trace.advance_by(frontier) }
+    fn advance_frontier(&mut self) -> &[Tr::Time] { self.trace.advance_frontier() }
+
+    fn distinguish_since(&mut self, frontier: &[Tr::Time]) { self.trace.distinguish_since(frontier) }
+    fn distinguish_frontier(&mut self) -> &[Tr::Time] { self.trace.distinguish_frontier() }
+
+    fn cursor_through(&mut self, upper: &[Tr::Time]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<Tr::Key, Tr::Val, Tr::Time, Tr::R>>::Storage)> {
+        let keys = self.keys.clone();
+        self.trace.cursor_through(upper).map(|(x,y)| (CursorKeys::new(x, &y, keys), y))
+    }
+}
+
+impl<Tr> TraceKeys<Tr>
+where
+    Tr: TraceReader,
+{
+    /// Makes a new trace wrapper
+    pub fn make_from(trace: Tr, keys: Rc<KeySet<Tr::Key>>) -> Self {
+        TraceKeys {
+            trace,
+            keys,
+        }
+    }
+}
+
+
+/// Wrapper to restrict a batch to a set of keys.
+///
+/// The length of the wrapped batch is that of the unrestricted batch, as computing the number of
+/// updates with keys in the set would require visiting them. It is an upper bound on the number of
+/// updates presented by the batch's cursor.
+pub struct BatchKeys<K, V, T, R, B> {
+    phantom: ::std::marker::PhantomData<(V, T, R)>,
+    batch: B,
+    keys: Rc<KeySet<K>>,
+}
+
+impl<K, V, T, R, B: Clone> Clone for BatchKeys<K, V, T, R, B> {
+    fn clone(&self) -> Self {
+        BatchKeys {
+            phantom: ::std::marker::PhantomData,
+            batch: self.batch.clone(),
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+impl<K, V, T, R, B> BatchReader<K, V, T, R> for BatchKeys<K, V, T, R, B>
+where
+    B: BatchReader<K, V, T, R>,
+    K: Ord+Clone,
+{
+    type Cursor = BatchCursorKeys<K, V, T, R, B>;
+
+    fn cursor(&self) -> Self::Cursor {
+        BatchCursorKeys::new(self.batch.cursor(), &self.batch, self.keys.clone())
+    }
+    /// The number of updates in the unrestricted batch, an upper bound on the updates with keys in the set.
+    fn len(&self) -> usize { self.batch.len() }
+    fn description(&self) -> &Description<T> { self.batch.description() }
+}
+
+impl<K, V, T, R, B> BatchKeys<K, V, T, R, B>
+where
+    B: BatchReader<K, V, T, R>,
+{
+    /// Makes a new batch wrapper
+    pub fn make_from(batch: B, keys: Rc<KeySet<K>>) -> Self {
+        BatchKeys {
+            phantom: ::std::marker::PhantomData,
+            batch,
+            keys,
+        }
+    }
+}
+
+/// Wrapper to restrict a cursor to a set of keys.
+pub struct CursorKeys<K, V, T, R, C: Cursor<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    cursor: C,
+    keys: Rc<KeySet<K>>,
+    valid: bool,
+}
+
+impl<K: Ord+Clone, V, T, R, C: Cursor<K, V, T, R>> CursorKeys<K, V, T, R, C> {
+    fn new(mut cursor: C, storage: &C::Storage, keys: Rc<KeySet<K>>) -> Self {
+        let valid = keys.settle(&mut cursor, storage);
+        CursorKeys {
+            phantom: ::std::marker::PhantomData,
+            cursor,
+            keys,
+            valid,
+        }
+    }
+}
+
+impl<K, V, T, R, C> Cursor<K, V, T, R> for CursorKeys<K, V, T, R, C>
+where
+    C: Cursor<K, V, T, R>,
+    K: Ord+Clone,
+{
+    type Storage = C::Storage;
+
+    #[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.valid && self.cursor.key_valid(storage) }
+    #[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.valid && self.cursor.val_valid(storage) }
+
+    #[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(storage) }
+    #[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(storage) }
+
+    #[inline]
+    fn map_times<L: FnMut(&T,&R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(storage, logic)
+    }
+
+    #[inline]
+    fn step_key(&mut self, storage: &Self::Storage) {
+        self.cursor.step_key(storage);
+        self.valid = self.keys.settle(&mut self.cursor, storage);
+    }
+    #[inline]
+    fn seek_key(&mut self, storage: &Self::Storage, key: &K) {
+        self.cursor.seek_key(storage, key);
+        self.valid = self.keys.settle(&mut self.cursor, storage);
+    }
+
+    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(storage) }
+    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(storage, val) }
+
+    #[inline]
+    fn rewind_keys(&mut self, storage: &Self::Storage) {
+        self.cursor.rewind_keys(storage);
+        self.valid = self.keys.settle(&mut self.cursor, storage);
+    }
+    #[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(storage) }
+}
+
+
+
+/// Wrapper to restrict a batch cursor to a set of keys.
+pub struct BatchCursorKeys<K, V, T, R, B: BatchReader<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(V, T, R)>,
+    cursor: B::Cursor,
+    keys: Rc<KeySet<K>>,
+    valid: bool,
+}
+
+impl<K: Ord+Clone, V, T, R, B: BatchReader<K, V, T, R>> BatchCursorKeys<K, V, T, R, B> {
+    fn new(mut cursor: B::Cursor, batch: &B, keys: Rc<KeySet<K>>) -> Self {
+        let valid = keys.settle(&mut cursor, batch);
+        BatchCursorKeys {
+            phantom: ::std::marker::PhantomData,
+            cursor,
+            keys,
+            valid,
+        }
+    }
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> Cursor<K, V, T, R> for BatchCursorKeys<K, V, T, R, B>
+where
+    K: Ord+Clone,
+{
+    type Storage = BatchKeys<K, V, T, R, B>;
+
+    #[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.valid && self.cursor.key_valid(&storage.batch) }
+    #[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.valid && self.cursor.val_valid(&storage.batch) }
+
+    #[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(&storage.batch) }
+    #[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(&storage.batch) }
+
+    #[inline]
+    fn map_times<L: FnMut(&T,&R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(&storage.batch, logic)
+    }
+
+    #[inline]
+    fn step_key(&mut self, storage: &Self::Storage) {
+        self.cursor.step_key(&storage.batch);
+        self.valid = self.keys.settle(&mut self.cursor, &storage.batch);
+    }
+    #[inline]
+    fn seek_key(&mut self, storage: &Self::Storage, key: &K) {
+        self.cursor.seek_key(&storage.batch, key);
+        self.valid = self.keys.settle(&mut self.cursor, &storage.batch);
+    }
+
+    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(&storage.batch) }
+    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(&storage.batch, val) }
+
+    #[inline]
+    fn rewind_keys(&mut self, storage: &Self::Storage) {
+        self.cursor.rewind_keys(&storage.batch);
+        self.valid = self.keys.settle(&mut self.cursor, &storage.batch);
+    }
+    #[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(&storage.batch) }
+}
@@ -5,4 +5,6 @@ pub mod enter_at;
 pub mod rc;
 
 pub mod filter;
-pub mod freeze;
\ No newline at end of file
+pub mod freeze;
+pub mod frontier;
+pub mod keys;
\ No newline at end of file
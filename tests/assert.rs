@@ -0,0 +1,84 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use differential_dataflow::input::Input;
+
+#[test]
+fn assert_contents_at() {
+
+    timely::example(|scope| {
+
+        let (mut input, data) = scope.new_collection();
+
+        data.assert_contents_at(0, vec![(1, 1), (2, 1), (2, 1)]);
+        data.assert_contents_at(1, vec![(2, 2)]);
+        data.assert_contents_at(2, vec![]);
+
+        input.insert(1);
+        input.update(2, 2);
+        input.advance_to(1);
+        input.remove(1);
+        input.advance_to(2);
+        input.update(2, -2);
+    });
+}
+
+#[test]
+#[should_panic(expected = "2: expected multiplicity Some(1), found Some(2)")]
+fn assert_contents_at_differ() {
+
+    timely::example(|scope| {
+
+        let (mut input, data) = scope.new_collection();
+
+        data.assert_contents_at(0, vec![(1, 1), (2, 1)]);
+
+        input.insert(1);
+        input.update(2, 2);
+    });
+}
+
+#[test]
+#[should_panic(expected = "3: expected multiplicity None, found Some(1)")]
+fn assert_contents_at_unexpected() {
+
+    timely::example(|scope| {
+
+        let (mut input, data) = scope.new_collection();
+
+        data.assert_contents_at(0, vec![(1, 1)]);
+
+        input.insert(1);
+        input.insert(3);
+    });
+}
+
+#[test]
+#[should_panic(expected = "collections differ")]
+fn assert_eq_differ() {
+
+    timely::example(|scope| {
+
+        let data = scope.new_collection_from(1 .. 10).1;
+
+        data.filter(|x| x % 2 == 0)
+            .assert_eq(&data);
+    });
+}
+
+#[test]
+#[should_panic(expected = "has negative multiplicity -1")]
+fn assert_no_negative_multiplicities() {
+
+    timely::example(|scope| {
+
+        let (mut input, data) = scope.new_collection();
+
+        data.assert_no_negative_multiplicities();
+
+        input.insert(1);
+        input.advance_to(1);
+        input.remove(1);
+        input.remove(1);
+    });
+}
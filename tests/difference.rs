@@ -0,0 +1,265 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::collections::HashMap;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use differential_dataflow::AsCollection;
+use differential_dataflow::input::InputSession;
+use differential_dataflow::difference::{Min, Max, Tropical, Saturating, Decimal, KahanSum, Checked};
+use differential_dataflow::consolidation::{consolidate_updates_checked, Overflow};
+use differential_dataflow::operators::{Join, Reduce, Count, Iterate};
+use differential_dataflow::operators::reduce::ReduceCore;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::trace::TraceReader;
+use differential_dataflow::trace::implementations::ord::OrdKeySpine;
+
+#[test]
+fn reduce_min() {
+
+    let data = timely::example(|scope| {
+
+        let col = vec![((0,()), Default::default(), Min::new(5)), ((0,()), Default::default(), Min::new(3)), ((1,()), Default::default(), Min::new(7))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        col.reduce(|_,s,t| t.push((s[0].1.value, 1))).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,3), Default::default(), 1), ((1,7), Default::default(), 1)]);
+}
+
+#[test]
+fn reduce_max() {
+
+    let data = timely::example(|scope| {
+
+        let col = vec![((0,()), Default::default(), Max::new(5)), ((0,()), Default::default(), Max::new(3)), ((1,()), Default::default(), Max::new(7))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        col.reduce(|_,s,t| t.push((s[0].1.value, 1))).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,5), Default::default(), 1), ((1,7), Default::default(), 1)]);
+}
+
+#[test]
+fn count_saturating() {
+
+    let data = timely::example(|scope| {
+
+        let col = vec![(0, Default::default(), Saturating::new(200u8)), (0, Default::default(), Saturating::new(200u8)), (1, Default::default(), Saturating::new(7u8))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        col.count().inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,Saturating::new(255)), Default::default(), 1), ((1,Saturating::new(7)), Default::default(), 1)]);
+}
+
+#[test]
+fn join_tropical() {
+
+    let data = timely::example(|scope| {
+
+        let col1 = vec![((0,'a'), Default::default(), Tropical::new(2)), ((1,'b'), Default::default(), Tropical::new(4))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let col2 = vec![((0,'A'), Default::default(), Tropical::new(3)), ((2,'C'), Default::default(), Tropical::new(4))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        col1.join(&col2).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,('a','A')), Default::default(), Tropical::new(5))]);
+}
+
+#[test]
+fn join_max() {
+
+    let data = timely::example(|scope| {
+
+        let col1 = vec![((0,'a'), Default::default(), Max::new(2))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let col2 = vec![((0,'A'), Default::default(), Max::new(3))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        col1.join(&col2).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,('a','A')), Default::default(), Max::new(2))]);
+}
+
+#[test]
+fn iterate_tropical() {
+
+    let data = timely::example(|scope| {
+
+        // edges weighted by their lengths.
+        let edges = vec![((0,1), Default::default(), Tropical::new(1u32)), ((1,2), Default::default(), Tropical::new(1)), ((0,2), Default::default(), Tropical::new(5))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let roots = vec![(0, Default::default(), Tropical::new(0u32))]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        // repeatedly update the shortest distance to each node.
+        scope.iterate(|dists| {
+
+            let edges = edges.enter(&dists.scope());
+            let roots = roots.enter(&dists.scope());
+
+            dists.map(|n| (n,()))
+                 .join_map(&edges, |_k,&(),&d| d)
+                 .concat(&roots)
+                 .map(|x| (x,()))
+                 .reduce_core::<_,OrdKeySpine<_,_,_>>(|_key, input, output, updates| {
+                     if output.is_empty() || input[0].1 < output[0].1 {
+                         updates.push(((), input[0].1));
+                     }
+                 })
+                 .as_collection(|k,()| *k)
+        })
+        .inner
+        .capture()
+    });
+
+    // accumulate the reported distances for each node.
+    let mut distances = HashMap::new();
+    for (_time, updates) in data.extract() {
+        for (node, _time, dist) in updates {
+            *distances.entry(node).or_insert(dist) += &dist;
+        }
+    }
+
+    assert_eq!(distances.len(), 3);
+    assert_eq!(distances[&0], Tropical::new(0));
+    assert_eq!(distances[&1], Tropical::new(1));
+    assert_eq!(distances[&2], Tropical::new(2));
+}
+
+#[test]
+fn explode_decimal() {
+
+    let data = timely::example(|scope| {
+
+        // ten payments of 0.10 and one refund of 1.00 by customer 0, which sum to exactly zero.
+        let payments = (0 .. 10).map(|_| ((0, Decimal::from_parts(10, 2)), Default::default(), 1))
+                                .chain(Some(((0, Decimal::from_parts(-100, 2)), Default::default(), 1)))
+                                .chain(Some(((1, Decimal::from_parts(1999, 2)), Default::default(), 3)))
+                                .to_stream(scope)
+                                .as_collection();
+
+        payments
+            .explode(|(customer, amount)| Some((customer, amount)))
+            .count()
+            .inner
+            .capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((1, Decimal::from_parts(5997, 2)), Default::default(), 1)]);
+}
+
+#[test]
+fn explode_kahan() {
+
+    let data = timely::example(|scope| {
+
+        // ten small amounts, each of which is lost when added to the large amounts alone.
+        let amounts = (0 .. 10).map(|_| (1, Default::default(), 1))
+                               .chain(vec![(10_000_000_000_000_000i64, Default::default(), 1), (-10_000_000_000_000_000, Default::default(), 1)])
+                               .to_stream(scope)
+                               .as_collection();
+
+        amounts
+            .explode(|amount: i64| Some(((), KahanSum::new(amount as f64))))
+            .count()
+            .map(|((), sum)| sum.value() as i64)
+            .inner
+            .capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![(10, Default::default(), 1)]);
+}
+
+#[test]
+fn consolidate_checked_overflow() {
+
+    let mut updates = vec![("a", 0, 100i8), ("b", 0, 100), ("a", 1, 100), ("b", 0, 100)];
+    let overflow = consolidate_updates_checked(&mut updates).unwrap_err();
+    assert_eq!(overflow, Overflow { data: "b", time: 0, accumulated: 100, update: 100 });
+
+    let mut updates = vec![("a", 0, 100i8), ("b", 0, 100), ("a", 1, 100), ("b", 0, -100)];
+    consolidate_updates_checked(&mut updates).unwrap();
+    assert_eq!(updates, vec![("a", 0, 100), ("a", 1, 100)]);
+}
+
+#[test]
+#[should_panic(expected = "difference overflow")]
+fn count_checked_overflow() {
+
+    timely::example(|scope| {
+
+        vec![(0, Default::default(), Checked::new(100i8)), (0, Default::default(), Checked::new(100i8))]
+            .into_iter()
+            .to_stream(scope)
+            .as_collection()
+            .count();
+    });
+}
+
+#[test]
+#[should_panic(expected = "key 7")]
+fn arrange_checked_overflow() {
+
+    timely::execute_directly(|worker| {
+
+        let mut input = InputSession::<u64, (u32, u32), Checked<i8>>::new();
+        let mut trace = worker.dataflow(|scope| input.to_collection(scope).arrange_by_key().trace);
+
+        // Updates at distinct times do not overflow until compaction brings them to the same time.
+        input.update_at((7, 0), 0, Checked::new(100));
+        input.update_at((7, 0), 1, Checked::new(100));
+        trace.advance_by(&[10]);
+        trace.distinguish_since(&[10]);
+
+        // Advancing the input produces batches, whose merging compacts the updates.
+        for round in 2 .. 20 {
+            input.advance_to(round);
+            input.flush();
+            worker.step();
+        }
+    });
+}
@@ -0,0 +1,70 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::sync::mpsc::Receiver;
+
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Event;
+
+use differential_dataflow::input::InputSession;
+use differential_dataflow::operators::event_time::EventTime;
+
+/// Drains the updates received so far, ignoring progress.
+fn received<D>(receiver: &Receiver<Event<u64, D>>) -> Vec<D> {
+    receiver
+        .try_iter()
+        .flat_map(|event| match event {
+            Event::Messages(_, data) => data,
+            Event::Progress(_) => Vec::new(),
+        })
+        .collect()
+}
+
+#[test]
+fn event_time_watermark() {
+
+    timely::execute_directly(|worker| {
+
+        let mut input = InputSession::<u64, (char, u64), isize>::new();
+
+        // Records may arrive up to two time units after their event time.
+        let (on_time, late) = worker.dataflow(|scope| {
+            let (on_time, late) = input.to_collection(scope).event_time(|&time| if time > 2 { time - 2 } else { 0 });
+            (on_time.inner.capture(), late.inner.capture())
+        });
+
+        input.insert(('a', 1));
+        input.advance_to(2);
+        input.flush();
+        for _ in 0 .. 10 { worker.step(); }
+
+        // The watermark has not passed the event time.
+        assert_eq!(received(&on_time), vec![]);
+
+        input.advance_to(4);
+        input.flush();
+        for _ in 0 .. 10 { worker.step(); }
+
+        // The watermark has passed the event time, and the record is emitted at its event time.
+        assert_eq!(received(&on_time), vec![('a', 1, 1)]);
+
+        // One unit late is allowed, three units late is not, and updates at the same event time accumulate.
+        input.insert(('b', 3));
+        input.insert(('c', 1));
+        input.insert(('d', 5));
+        input.insert(('d', 5));
+        input.advance_to(6);
+        input.flush();
+        for _ in 0 .. 10 { worker.step(); }
+
+        assert_eq!(received(&on_time), vec![('b', 3, 1)]);
+        assert_eq!(received(&late), vec![(('c', 1), 4, 1)]);
+
+        // Completing the input releases all buffered records.
+        input.close();
+        for _ in 0 .. 10 { worker.step(); }
+
+        assert_eq!(received(&on_time), vec![('d', 5, 2)]);
+        assert_eq!(received(&late), vec![]);
+    });
+}
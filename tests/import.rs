@@ -2,6 +2,9 @@ extern crate timely;
 extern crate itertools;
 extern crate differential_dataflow;
 
+use std::rc::Rc;
+use std::cell::RefCell;
+
 use timely::dataflow::operators::*;
 use timely::dataflow::operators::capture::Extract;
 
@@ -246,3 +249,173 @@ fn import_skewed() {
         (4, vec![((0, 1), 1)]),
     ]);
 }
+
+#[test]
+fn test_import_frontier() {
+
+    let captured = timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut input = InputSession::new();
+
+        let (mut trace, probe1) = worker.dataflow(|scope| {
+
+            let arranged =
+            input
+                .to_collection(scope)
+                .arrange_by_self();
+
+            (arranged.trace, arranged.stream.probe())
+        });
+
+        input.insert("Hello".to_owned());
+        input.insert("World".to_owned());
+        input.advance_to(1);
+        input.remove("World".to_owned());
+        input.advance_to(2);
+        input.flush();
+
+        worker.step_while(|| probe1.less_than(input.time()));
+
+        // Compact the history through time 1, and import only the snapshot.
+        trace.advance_by(&[1]);
+
+        worker.dataflow(move |scope| {
+            trace
+                .import_frontier(scope)
+                .as_collection(|k: &String, _| k.clone())
+                .inner
+                .capture()
+        })
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    let results = captured.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    assert_eq!(results, vec![("Hello".to_owned(), 1, 1)]);
+}
+
+#[test]
+fn test_import_as_of() {
+
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut input = InputSession::new();
+
+        let (mut trace, probe1) = worker.dataflow(|scope| {
+
+            let arranged =
+            input
+                .to_collection(scope)
+                .arrange_by_self();
+
+            (arranged.trace, arranged.stream.probe())
+        });
+
+        // Ten rounds of history, each its own batch.
+        for round in 0 .. 10u64 {
+            input.insert(round);
+            input.advance_to(round + 1);
+            input.flush();
+            worker.step_while(|| probe1.less_than(input.time()));
+        }
+
+        let batches = Rc::new(RefCell::new(Vec::new()));
+        let updates = Rc::new(RefCell::new(Vec::new()));
+        let batches1 = batches.clone();
+        let updates1 = updates.clone();
+
+        let probe2 = worker.dataflow(move |scope| {
+            let imported = trace.import_as_of(scope, &[10]);
+            imported
+                .stream
+                .inspect(move |batch| batches1.borrow_mut().push(batch.len()));
+            imported
+                .as_collection(|k: &u64, _| *k)
+                .inner
+                .inspect(move |update| updates1.borrow_mut().push(update.clone()))
+                .probe()
+        });
+
+        input.insert(10);
+        input.advance_to(11);
+        input.flush();
+        worker.step_while(|| probe2.less_than(input.time()));
+
+        // The history arrives as a single batch at the frontier, followed by the new batch.
+        let batches = batches.borrow().iter().cloned().filter(|&len| len > 0).collect::<Vec<_>>();
+        assert_eq!(batches, vec![10, 1]);
+        let mut updates = updates.borrow().clone();
+        updates.sort();
+        assert_eq!(updates, (0 .. 11).map(|key| (key, 10, 1)).collect::<Vec<_>>());
+
+    }).unwrap();
+}
+
+#[test]
+fn test_import_keys() {
+
+    let captured = timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut input = InputSession::new();
+
+        let (mut trace, probe1) = worker.dataflow(|scope| {
+
+            let arranged =
+            input
+                .to_collection(scope)
+                .arrange_by_key();
+
+            (arranged.trace, arranged.stream.probe())
+        });
+
+        for key in 0 .. 10u64 {
+            input.insert((key, key * 10));
+        }
+        input.advance_to(1);
+        input.remove((4, 40));
+        input.insert((4, 41));
+        input.advance_to(2);
+        input.flush();
+
+        worker.step_while(|| probe1.less_than(input.time()));
+
+        worker.dataflow(move |scope| {
+            let range =
+            trace
+                .import_key_range(scope, 3 .. 6)
+                .as_collection(|k: &u64, v: &u64| (*k, *v))
+                .inner
+                .capture();
+            // Keys absent from the trace, and repeated keys, are ignored.
+            let set =
+            trace
+                .import_key_set(scope, vec![9, 1, 4, 12, 1])
+                .as_collection(|k: &u64, v: &u64| (*k, *v))
+                .inner
+                .capture();
+            (range, set)
+        })
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    let (range, set) = captured;
+
+    let mut range = range.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    range.sort();
+    assert_eq!(range, vec![
+        ((3, 30), 0, 1),
+        ((4, 40), 0, 1),
+        ((4, 40), 1, -1),
+        ((4, 41), 1, 1),
+        ((5, 50), 0, 1),
+    ]);
+
+    let mut set = set.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    set.sort();
+    assert_eq!(set, vec![
+        ((1, 10), 0, 1),
+        ((4, 40), 0, 1),
+        ((4, 40), 1, -1),
+        ((4, 41), 1, 1),
+        ((9, 90), 0, 1),
+    ]);
+}
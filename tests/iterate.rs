@@ -0,0 +1,99 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use timely::order::Product;
+use timely::dataflow::Scope;
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::{Consolidate, Iterate, Join};
+use differential_dataflow::operators::iterate::RecursiveGroup;
+use differential_dataflow::logging::DifferentialEvent;
+
+#[test]
+fn iterate_bounded_non_convergent() {
+
+    // Per-round counts reported by iteration events, summed over the events of each round.
+    let rounds = Arc::new(Mutex::new(BTreeMap::new()));
+    let rounds1 = rounds.clone();
+
+    let (result, residual) = timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let rounds = rounds1.clone();
+        worker.log_register().insert::<DifferentialEvent,_>("differential/arrange", move |_time, data| {
+            for (_, _, event) in data.drain(..) {
+                if let DifferentialEvent::Iteration(event) = event {
+                    *rounds.lock().unwrap().entry(event.round).or_insert(0) += event.updates;
+                }
+            }
+        });
+
+        worker.dataflow::<u32,_,_>(|scope| {
+            // Incrementing values never converges.
+            let (result, residual) =
+            scope.new_collection_from(vec![0u32]).1
+                 .iterate_bounded(5, |values| values.map(|x| x + 1));
+            (result.consolidate().inner.capture(), residual.consolidate().inner.capture())
+        })
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    // The loop is cut off after five rounds, and the final application of the body is withheld.
+    let mut result = result.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    result.sort();
+    assert_eq!(result, vec![(6, 0, 1)]);
+
+    let mut residual = residual.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    residual.sort();
+    assert_eq!(residual, vec![(5, 0, -1), (6, 0, 1)]);
+
+    // The body produces the first value in round zero, and then retracts and replaces it in each round.
+    let rounds = rounds.lock().unwrap().clone();
+    let expected = vec![(0, 1), (1, 2), (2, 2), (3, 2), (4, 2), (5, 2)].into_iter().collect::<BTreeMap<u64, usize>>();
+    assert_eq!(rounds, expected);
+}
+
+#[test]
+fn recursive_group_mutual_recursion() {
+
+    let (even, odd) = timely::execute(timely::Configuration::Thread, |worker| {
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let edges = scope.new_collection_from(vec![(0u32, 1u32), (1, 2), (2, 3)]).1;
+            let nodes = scope.new_collection_from(vec![0u32, 1, 2, 3]).1;
+
+            // even(x,x) := node(x)
+            // even(x,z) := odd(x,y), edge(y,z)
+            // odd(x,z) := even(x,y), edge(y,z)
+            let (even, odd) = scope.iterative::<u64,_,_>(|nested| {
+
+                let mut group = RecursiveGroup::new(nested, Product::new(Default::default(), 1));
+                let edges = group.enter("edges", &edges);
+                let nodes = group.enter("nodes", &nodes);
+                let even = group.set_relation_from("even", &nodes.map(|x| (x, x)));
+                let odd = group.set_relation("odd");
+                assert_eq!(group.names(), &["edges", "nodes", "even", "odd"]);
+
+                even.add_production(&odd.map(|(x,y)| (y,x)).join_map(&edges, |_y,&x,&z| (x,z)));
+                odd.add_production(&even.map(|(x,y)| (y,x)).join_map(&edges, |_y,&x,&z| (x,z)));
+
+                group.complete();
+                (even.leave(), odd.leave())
+            });
+
+            (even.consolidate().inner.capture(), odd.consolidate().inner.capture())
+        })
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    let mut even = even.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    even.sort();
+    assert_eq!(even, vec![((0, 0), 0, 1), ((0, 2), 0, 1), ((1, 1), 0, 1), ((1, 3), 0, 1), ((2, 2), 0, 1), ((3, 3), 0, 1)]);
+
+    let mut odd = odd.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    odd.sort();
+    assert_eq!(odd, vec![((0, 1), 0, 1), ((0, 3), 0, 1), ((1, 2), 0, 1), ((2, 3), 0, 1)]);
+}
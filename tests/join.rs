@@ -101,4 +101,41 @@ fn join_scaling(scale: u64) {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn join_skewed() {
+
+    use differential_dataflow::input::Input;
+    use differential_dataflow::operators::skew::JoinSkewed;
+
+    timely::execute(timely::Configuration::Process(3), move |worker| {
+
+        let index = worker.index();
+        let peers = worker.peers();
+
+        let (mut input1, mut input2) = worker.dataflow::<u32,_,_>(|scope| {
+
+            let (input1, col1) = scope.new_collection();
+            let (input2, col2) = scope.new_collection();
+
+            col1.join_skewed(&col2, 10, |_key, &a: &u32, &b: &u32| (a, b))
+                .assert_eq(&col1.join_map(&col2, |_key, &a, &b| (a, b)));
+
+            (input1, input2)
+        });
+
+        // key zero is heavy at the first time, and becomes light at the second time.
+        for i in (0 .. 100u32).filter(|i| *i as usize % peers == index) {
+            input1.insert((if i < 90 { 0 } else { i }, i));
+            input2.insert((i % 5, i));
+        }
+        input1.advance_to(1); input1.flush();
+        input2.advance_to(1); input2.flush();
+
+        for i in (5 .. 90u32).filter(|i| *i as usize % peers == index) {
+            input1.remove((0, i));
+        }
+        input2.insert((0, 1000 + index as u32));
+    }).unwrap();
+}
@@ -0,0 +1,55 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use timely::PartialOrder;
+use differential_dataflow::lattice::{Lattice, Vector};
+
+fn hash<T: Hash>(item: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn vector_trailing_defaults() {
+
+    let short = Vector::new(vec![3]);
+    let long = Vector::new(vec![3, 0]);
+
+    // Vectors that are less or equal to each other are equal, and agree in order and hash.
+    assert!(short.less_equal(&long));
+    assert!(long.less_equal(&short));
+    assert_eq!(short, long);
+    assert_eq!(short.cmp(&long), std::cmp::Ordering::Equal);
+    assert_eq!(hash(&short), hash(&long));
+
+    assert_eq!(long.join(&short), short);
+    assert_eq!(long.meet(&Vector::new(vec![4, 0, 0])), short);
+    assert_eq!(Vector::<u64>::new(vec![0, 0]), Vector::minimum());
+}
+
+#[test]
+fn vector_order_extends_partial_order() {
+
+    let times = vec![
+        Vector::new(vec![]),
+        Vector::new(vec![0, 1]),
+        Vector::new(vec![1]),
+        Vector::new(vec![1, 0]),
+        Vector::new(vec![1, 1]),
+        Vector::new(vec![2, 0, 1]),
+        Vector::new(vec![0, 0, 3]),
+    ];
+
+    for time1 in times.iter() {
+        for time2 in times.iter() {
+            if time1.less_equal(time2) {
+                assert!(time1 <= time2, "{:?} <= {:?}", time1, time2);
+            }
+            assert_eq!(time1 == time2, time1.less_equal(time2) && time2.less_equal(time1));
+        }
+    }
+}